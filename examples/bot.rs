@@ -0,0 +1,30 @@
+use signald_rust::signald::Signald;
+use signald_rust::bot::{Bot, Context};
+use regex::Regex;
+
+#[tokio::main]
+async fn main() {
+    let mut signald = Signald::connect();
+    println!("Connected to socket");
+
+    // Edit "my_number" to your linked signald phone number
+    let my_number = "my_number".to_string();
+    signald.subscribe(my_number.clone()).await.unwrap();
+
+    let mut bot = Bot::new(&my_number);
+    bot.middleware(|context: &Context| {
+            println!("RECEIVED: {}", context.body());
+            true
+        })
+        .command("deploy", |context| async move {
+            match context.arg(0) {
+                Some(env) => Some(format!("Deploying to {}", env)),
+                None => Some("Usage: /deploy <env>".to_string()),
+            }
+        })
+        .pattern(Regex::new(r"(?i)\bping\b").unwrap(), |_| async { Some("pong".to_string()) })
+        .on_group_join(|_| async { Some("Hi! Send /deploy <env> to deploy".to_string()) });
+
+    // Replies are sent back to the conversation each message came from
+    bot.run(&mut signald).await.unwrap();
+}
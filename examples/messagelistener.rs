@@ -18,22 +18,14 @@ async fn main() {
 
     // Print each sent/received message, note: messages sent via signald will not be received
     signald.get_rx().iter().for_each(|x| {
-        match x.data {
-            ResponseType::Message(message) => {
-                let message = message.unwrap();
-                if message.sync_message.is_some() {
-                    let sync = message.sync_message.unwrap();
-                    if sync.sent.is_some() {
-                        let mesg = sync.sent.unwrap().message.message;
-                        println!("SENT: {}", mesg);
-                    }
-                }
-                if message.data_message.is_some() {
-                    let mesg = message.data_message.unwrap().message;
-                    println!("RECEIVED: {}", mesg);
-                }
+        if let ResponseType::Message(message) = x.data {
+            let message = message.unwrap();
+            if let Some(sent) = message.sync_message.and_then(|sync| sync.sent) {
+                println!("SENT: {}", sent.message.message);
+            }
+            if let Some(data_message) = message.data_message {
+                println!("RECEIVED: {}", data_message.message);
             }
-            _ => {}
         }
     })
 
@@ -0,0 +1,178 @@
+use crate::signald::Signald;
+use crate::signaldrequest::{Recipient, UpdateGroupRequest, OutgoingAttachment};
+use crate::signaldresponse::{SignaldResponse, ResponseType, SendResult};
+use crate::signaladdress::SignalAddress;
+use crate::signalderror::SignaldError;
+use crate::socket::state::{AccountHealth, BlockList};
+use crate::v1;
+
+/// A handle to a single account on a Signald connection
+/// Every request made through the handle is made for its account
+pub struct Account<'a> {
+    signald: &'a mut Signald,
+    username: String,
+}
+impl<'a> Account<'a> {
+    pub fn new(signald: &'a mut Signald, username: String) -> Account<'a> {
+        Account {
+            signald,
+            username,
+        }
+    }
+    /// The username of this account
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Send a message
+    pub async fn send(&mut self, recipient: SignalAddress, message_body: Option<String>) -> Result<SendResult, SignaldError> {
+        self.signald.send(self.username.clone(), recipient, message_body).await
+    }
+    /// Send a message to a group
+    pub async fn send_group(&mut self, group_id: String, message_body: Option<String>) -> Result<SendResult, SignaldError> {
+        self.signald.send_group(self.username.clone(), group_id, message_body).await
+    }
+    /// Send a message with attachments on the signald host
+    pub async fn send_message(&mut self, recipient: Recipient, message_body: Option<String>,
+                              attachments: Vec<OutgoingAttachment>) -> Result<SendResult, SignaldError> {
+        self.signald.send_message(self.username.clone(), recipient, message_body, attachments).await
+    }
+    /// Replace the text of a message this account sent, identified by the timestamp of its SendResult
+    pub async fn edit_message(&mut self, recipient: Recipient, timestamp: i64, message_body: String) -> Result<SendResult, SignaldError> {
+        self.signald.edit_message(self.username.clone(), recipient, timestamp, message_body).await
+    }
+    /// Send attachments on the signald host that the recipients can only view once
+    pub async fn send_view_once(&mut self, recipient: Recipient, attachments: Vec<OutgoingAttachment>) -> Result<SendResult, SignaldError> {
+        self.signald.send_view_once(self.username.clone(), recipient, attachments).await
+    }
+    /// Enable receiving messages for this account
+    pub async fn subscribe(&mut self) -> Result<SignaldResponse, SignaldError> {
+        self.signald.subscribe(self.username.clone()).await
+    }
+    /// Disable receiving messages for this account
+    pub async fn unsubscribe(&mut self) -> Result<SignaldResponse, SignaldError> {
+        self.signald.unsubscribe(self.username.clone()).await
+    }
+    /// Register this account
+    pub async fn register(&mut self, voice: bool, captcha: Option<String>) -> Result<SignaldResponse, SignaldError> {
+        self.signald.register(self.username.clone(), voice, captcha).await
+    }
+    /// Verify this account with the received verification code
+    pub async fn verify(&mut self, code: String) -> Result<SignaldResponse, SignaldError> {
+        self.signald.verify(self.username.clone(), code).await
+    }
+    /// Query all the contacts of this account
+    pub async fn list_contacts(&mut self) -> Result<SignaldResponse, SignaldError> {
+        self.signald.list_contacts(self.username.clone()).await
+    }
+    /// Query all the groups this account is a member of
+    pub async fn list_groups(&mut self) -> Result<SignaldResponse, SignaldError> {
+        self.signald.list_groups(self.username.clone()).await
+    }
+    /// Create a group
+    pub async fn create_group(&mut self, group_name: String, members: Vec<SignalAddress>) -> Result<SignaldResponse, SignaldError> {
+        self.update_group(None, Some(group_name), Some(members)).await
+    }
+    /// Update the name or members of a group
+    pub async fn update_group(&mut self, group_id: Option<String>, group_name: Option<String>,
+                              members: Option<Vec<SignalAddress>>) -> Result<SignaldResponse, SignaldError> {
+        let request = UpdateGroupRequest {
+            username: self.username.clone(),
+            recipient_group_id: group_id,
+            group_name,
+            members,
+            ..Default::default()
+        };
+        self.signald.update_group(request).await
+    }
+    /// Leave a group
+    pub async fn leave_group(&mut self, group_id: String) -> Result<SignaldResponse, SignaldError> {
+        self.signald.leave_group(self.username.clone(), group_id).await
+    }
+    /// React to a message with an emoji
+    pub async fn react(&mut self, recipient: Recipient, reaction: v1::JsonReaction) -> Result<v1::SendResponse, SignaldError> {
+        self.signald.react(self.username.clone(), recipient, reaction).await
+    }
+    /// Get the profile of a user
+    pub async fn get_profile(&mut self, address: SignalAddress) -> Result<v1::Profile, SignaldError> {
+        self.signald.get_profile(self.username.clone(), address).await
+    }
+    /// Get the current state of a group
+    pub async fn get_group(&mut self, group_id: String) -> Result<v1::JsonGroupV2Info, SignaldError> {
+        self.signald.get_group(self.username.clone(), group_id).await
+    }
+    /// Accept an invitation to join a group
+    pub async fn accept_invitation(&mut self, group_id: String) -> Result<v1::JsonGroupV2Info, SignaldError> {
+        self.signald.accept_invitation(self.username.clone(), group_id).await
+    }
+    /// Decline an invitation to join a group
+    pub async fn decline_invitation(&mut self, group_id: String) -> Result<(), SignaldError> {
+        self.signald.decline_invitation(self.username.clone(), group_id).await
+    }
+    /// Join a group with an invitation link
+    pub async fn join_group(&mut self, uri: String) -> Result<v1::JsonGroupJoinInfo, SignaldError> {
+        self.signald.join_group(self.username.clone(), uri).await
+    }
+    /// Let users that asked to join a group in
+    pub async fn approve_join_requests(&mut self, group_id: String, members: Vec<SignalAddress>) -> Result<v1::JsonGroupV2Info, SignaldError> {
+        self.signald.approve_join_requests(self.username.clone(), group_id, members).await
+    }
+    /// Deny the requests of users that asked to join a group
+    pub async fn deny_join_requests(&mut self, group_id: String, members: Vec<SignalAddress>, ban: bool) -> Result<v1::JsonGroupV2Info, SignaldError> {
+        self.signald.deny_join_requests(self.username.clone(), group_id, members, ban).await
+    }
+    /// Replace the invitation link of a group
+    pub async fn reset_group_link(&mut self, group_id: String) -> Result<v1::JsonGroupV2Info, SignaldError> {
+        self.signald.reset_group_link(self.username.clone(), group_id).await
+    }
+    /// Make a group member an administrator
+    pub async fn promote_admin(&mut self, group_id: String, member: SignalAddress) -> Result<v1::JsonGroupV2Info, SignaldError> {
+        self.signald.promote_admin(self.username.clone(), group_id, member).await
+    }
+    /// Make a group administrator an ordinary member
+    pub async fn demote_admin(&mut self, group_id: String, member: SignalAddress) -> Result<v1::JsonGroupV2Info, SignaldError> {
+        self.signald.demote_admin(self.username.clone(), group_id, member).await
+    }
+    /// Change the access control of a group
+    pub async fn set_group_access(&mut self, group_id: String, access_control: v1::GroupAccessControl) -> Result<v1::JsonGroupV2Info, SignaldError> {
+        self.signald.set_group_access(self.username.clone(), group_id, access_control).await
+    }
+    /// Block a user
+    pub async fn block(&mut self, address: SignalAddress) -> Result<(), SignaldError> {
+        self.signald.block(self.username.clone(), address).await
+    }
+    /// Unblock a user
+    pub async fn unblock(&mut self, address: SignalAddress) -> Result<(), SignaldError> {
+        self.signald.unblock(self.username.clone(), address).await
+    }
+    /// Block a group
+    pub async fn block_group(&mut self, group_id: String) -> Result<(), SignaldError> {
+        self.signald.block_group(self.username.clone(), group_id).await
+    }
+    /// Unblock a group
+    pub async fn unblock_group(&mut self, group_id: String) -> Result<(), SignaldError> {
+        self.signald.unblock_group(self.username.clone(), group_id).await
+    }
+    /// The users and groups this account blocked
+    pub fn blocked(&self) -> BlockList {
+        self.signald.blocked(&self.username)
+    }
+    /// Send a contact sync request to the other devices on this account
+    pub fn sync_contacts(&mut self) -> Result<(), SignaldError> {
+        self.signald.sync_contacts(self.username.clone())
+    }
+
+    /// The connection health of this account
+    pub fn health(&self) -> Option<AccountHealth> {
+        self.signald.account_health(&self.username)
+    }
+    /// Get a stream that returns every message received by this account
+    pub fn messages(&mut self) -> impl Iterator<Item = SignaldResponse> {
+        let username = self.username.clone();
+        self.signald.get_rx().into_iter()
+            .filter(move |response| match &response.data {
+                ResponseType::Message(Some(message)) => message.username.as_deref() == Some(username.as_str()),
+                _ => false,
+            })
+    }
+}
@@ -0,0 +1,82 @@
+use clap::Parser;
+use signald_rust::bridge::{Bridge, BridgeConfig};
+use signald_rust::signald::{Signald, SOCKET_PATH};
+use signald_rust::v1::Protocol;
+use std::path::Path;
+use std::process;
+use std::time::Duration;
+
+/// An HTTP API and webhook bridge for signald
+#[derive(Parser)]
+#[command(name = "signald-bridge", version)]
+struct Cli {
+    /// The signald socket
+    #[arg(short, long, env = "SIGNALD_SOCKET", default_value = SOCKET_PATH)]
+    socket: String,
+    /// The account to send from and receive for
+    #[arg(short, long, env = "SIGNALD_ACCOUNT")]
+    account: String,
+    /// The address the HTTP API listens on
+    #[arg(short, long, env = "SIGNALD_BRIDGE_LISTEN", default_value = "127.0.0.1:8080")]
+    listen: String,
+    /// The token API requests must carry as "Authorization: Bearer <token>", required when not listening on localhost
+    #[arg(long, env = "SIGNALD_BRIDGE_TOKEN")]
+    api_token: Option<String>,
+    /// A url received messages are posted to, can be given more than once
+    #[arg(short, long = "webhook")]
+    webhooks: Vec<String>,
+    /// The secret webhook bodies are signed with
+    #[arg(long, env = "SIGNALD_BRIDGE_SECRET")]
+    webhook_secret: Option<String>,
+    /// The number of times a webhook is tried
+    #[arg(long, default_value_t = 5)]
+    webhook_attempts: u32,
+    /// The number of messages waiting for each webhook before new ones are dropped
+    #[arg(long, default_value_t = 1000)]
+    webhook_queue: usize,
+    /// Use the v1 protocol for requests that exist in both protocols
+    #[arg(long)]
+    v1: bool,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if !Path::new(&cli.socket).exists() {
+        eprintln!("error: no signald socket at {}", cli.socket);
+        process::exit(1);
+    }
+
+    let mut signald = Signald::connect_path(&cli.socket);
+    if cli.v1 {
+        if let Err(error) = signald.set_protocol(Protocol::V1) {
+            eprintln!("error: {}", error);
+            process::exit(1);
+        }
+    }
+
+    let config = BridgeConfig {
+        listen: cli.listen,
+        api_token: cli.api_token,
+        account: cli.account,
+        webhooks: cli.webhooks,
+        webhook_secret: cli.webhook_secret,
+        webhook_attempts: cli.webhook_attempts,
+        webhook_backoff: Duration::from_secs(1),
+        webhook_queue: cli.webhook_queue,
+    };
+    let mut bridge = match Bridge::new(signald, config) {
+        Ok(bridge) => bridge,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            process::exit(1);
+        }
+    };
+    bridge.on_dropped(|url, _| eprintln!("Dropped a message for webhook {}", url));
+    if let Some(addr) = bridge.local_addr() {
+        eprintln!("Listening on http://{}", addr);
+    }
+    if let Err(error) = bridge.run() {
+        eprintln!("error: {}", error);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,276 @@
+use clap::{Parser, Subcommand};
+use futures::executor::block_on;
+use qrcode::QrCode;
+use qrcode::render::unicode;
+use serde_json::Value;
+use signald_rust::signald::{Signald, SOCKET_PATH};
+use signald_rust::signaladdress::{SignalAddress, AddressError};
+use signald_rust::signaldrequest::{Recipient, OutgoingAttachment};
+use signald_rust::signaldresponse::{SignaldResponse, ResponseType, MessageData, SendResult, ErrorData};
+use signald_rust::signalderror::SignaldError;
+use signald_rust::v1::Protocol;
+use std::io::Read;
+use std::path::Path;
+use std::process;
+
+/// A command line client for signald
+#[derive(Parser)]
+#[command(name = "signald-rs", version)]
+struct Cli {
+    /// The signald socket
+    #[arg(short, long, global = true, env = "SIGNALD_SOCKET", default_value = SOCKET_PATH)]
+    socket: String,
+    /// The account to use
+    #[arg(short, long, global = true, env = "SIGNALD_ACCOUNT")]
+    account: Option<String>,
+    /// Print every response as a line of json
+    #[arg(long, global = true)]
+    json: bool,
+    /// Use the v1 protocol for requests that exist in both protocols
+    #[arg(long, global = true)]
+    v1: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Send a message, the message is read from stdin when it isn't given
+    Send {
+        /// A phone number, uuid, or group id with --group
+        recipient: String,
+        message: Option<String>,
+        /// Send to a group
+        #[arg(short, long)]
+        group: bool,
+        /// A file on the signald host to attach
+        #[arg(long = "attachment")]
+        attachments: Vec<String>,
+    },
+    /// Print the messages the account receives
+    #[command(alias = "tail")]
+    Receive {
+        /// Stop after this many messages
+        #[arg(short = 'n', long)]
+        count: Option<usize>,
+    },
+    /// Link signald as a new device of an existing account
+    Link {
+        /// The name of the new device
+        #[arg(long)]
+        device_name: Option<String>,
+    },
+    /// Register a new account
+    Register {
+        /// Get the verification code by voice call instead of sms
+        #[arg(long)]
+        voice: bool,
+        #[arg(long)]
+        captcha: Option<String>,
+    },
+    /// Verify a registered account with the received code
+    Verify {
+        code: String,
+    },
+    /// List the contacts of the account
+    ListContacts,
+    /// List the groups the account is a member of
+    ListGroups,
+    /// List the accounts signald manages
+    ListAccounts,
+    /// Print the signald version
+    Version,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if let Err(error) = block_on(run(cli)) {
+        eprintln!("error: {}", error);
+        process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), SignaldError> {
+    if !Path::new(&cli.socket).exists() {
+        return Err(SignaldError::Io(format!("no signald socket at {}", cli.socket)));
+    }
+    let mut signald = Signald::connect_path(&cli.socket);
+    if cli.v1 {
+        signald.set_protocol(Protocol::V1)?;
+    }
+    let account = || cli.account.clone().ok_or_else(|| input_error("an account is required, set --account".to_string()));
+
+    match &cli.command {
+        Command::Send { recipient, message, group, attachments } => {
+            let recipient = match group {
+                true => Recipient::Group(recipient.clone()),
+                false => Recipient::Address(parse_address(recipient)?),
+            };
+            let message = match message {
+                Some(message) => message.clone(),
+                None => read_stdin()?,
+            };
+            let attachments = attachments.iter()
+                .map(|filename| OutgoingAttachment {
+                    filename: filename.clone(),
+                    ..Default::default()
+                })
+                .collect();
+
+            let result = signald.send_message(account()?, recipient, Some(message), attachments).await?;
+            print_send_result(&result, cli.json);
+            if !result.is_success() {
+                process::exit(2);
+            }
+        }
+        Command::Receive { count } => {
+            let username = account()?;
+            let rx = signald.get_rx();
+            signald.subscribe(username.clone()).await?;
+
+            let messages = rx.into_iter()
+                .filter(|response| match &response.data {
+                    ResponseType::Message(Some(message)) => message.username.as_deref() == Some(username.as_str()),
+                    _ => false,
+                })
+                .take(count.unwrap_or(usize::MAX));
+            for response in messages {
+                match (&response.data, cli.json) {
+                    (_, true) => println!("{}", response.to_value()),
+                    (ResponseType::Message(Some(message)), false) => print_message(message),
+                    _ => {}
+                }
+            }
+        }
+        Command::Link { device_name } => {
+            let rx = signald.get_rx();
+            let response = signald.link_device(device_name.clone()).await?;
+            match &response.data {
+                ResponseType::LinkingUri(Some(uri)) if !cli.json => {
+                    println!("{}", render_qr(&uri.uri));
+                    println!("Scan the code with the Signal app or open: {}", uri.uri);
+                }
+                _ => print_response(&response, cli.json),
+            }
+
+            // Signald answers the same request again once linking finished
+            let result = rx.into_iter()
+                .find(|r| r.id == response.id && !matches!(r.data, ResponseType::LinkingUri(_)))
+                .ok_or_else(|| SignaldError::Io("the connection to signald closed before linking finished".to_string()))?
+                .into_result()?;
+            print_response(&result, cli.json);
+        }
+        Command::Register { voice, captcha } => {
+            let response = signald.register(account()?, *voice, captcha.clone()).await?;
+            print_response(&response, cli.json);
+        }
+        Command::Verify { code } => {
+            let response = signald.verify(account()?, code.clone()).await?;
+            print_response(&response, cli.json);
+        }
+        Command::ListContacts => {
+            let response = signald.list_contacts(account()?).await?;
+            match (&response.data, cli.json) {
+                (ResponseType::ContactList(Some(contacts)), false) => {
+                    for contact in contacts {
+                        println!("{}\t{}", contact.number.as_ref().map(|n| n.to_string()).unwrap_or_default(), contact.name.as_deref().unwrap_or_default());
+                    }
+                }
+                _ => print_response(&response, cli.json),
+            }
+        }
+        Command::ListGroups => {
+            let response = signald.list_groups(account()?).await?;
+            let groups = response.data.data_value().unwrap_or_default();
+            match (groups["groups"].as_array(), cli.json) {
+                (Some(groups), false) => {
+                    for group in groups {
+                        println!("{}\t{}", group["groupId"].as_str().unwrap_or_default(), group["name"].as_str().unwrap_or_default());
+                    }
+                }
+                _ => print_response(&response, cli.json),
+            }
+        }
+        Command::ListAccounts => {
+            let response = signald.list_accounts().await?;
+            match (&response.data, cli.json) {
+                (ResponseType::AccountList(Some(list)), false) => {
+                    for account in &list.accounts {
+                        println!("{}\t{}", account.username, if account.registered { "registered" } else { "unregistered" });
+                    }
+                }
+                _ => print_response(&response, cli.json),
+            }
+        }
+        Command::Version => {
+            let response = signald.version().await?;
+            match (&response.data, cli.json) {
+                (ResponseType::Version(Some(version)), false) => println!("{} {}", version.name, version.version),
+                _ => print_response(&response, cli.json),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_address(address: &str) -> Result<SignalAddress, SignaldError> {
+    address.parse().map_err(|e: AddressError| input_error(e.to_string()))
+}
+
+fn input_error(message: String) -> SignaldError {
+    SignaldError::InputError(ErrorData {
+        message,
+        ..Default::default()
+    })
+}
+
+fn read_stdin() -> Result<String, SignaldError> {
+    let mut message = String::new();
+    std::io::stdin().read_to_string(&mut message)?;
+    Ok(message.trim_end_matches('\n').to_string())
+}
+
+/// Print a response, as json or as its pretty printed data
+fn print_response(response: &SignaldResponse, json: bool) {
+    match json {
+        true => println!("{}", response.to_value()),
+        false => match response.data.data_value() {
+            Some(Value::String(data)) => println!("{}", data),
+            Some(data) => println!("{}", serde_json::to_string_pretty(&data).unwrap()),
+            None => println!("{}", response.data.type_name()),
+        },
+    }
+}
+
+fn print_send_result(result: &SendResult, json: bool) {
+    if json {
+        println!("{}", result.to_value());
+        return;
+    }
+
+    println!("Sent at {}", result.timestamp);
+    for r in result.failures() {
+        eprintln!("{}: {}", r.recipient(), r.status);
+    }
+}
+
+fn print_message(message: &MessageData) {
+    let sender = message.sender().map(|s| s.to_string()).unwrap_or_default();
+    if let Some(data_message) = &message.data_message {
+        match data_message.group_id() {
+            Some(group_id) => println!("{} [{}] {}: {}", message.timestamp, group_id, sender, data_message.message),
+            None => println!("{} {}: {}", message.timestamp, sender, data_message.message),
+        }
+    }
+    if let Some(sent) = message.sync_message.as_ref().and_then(|sync| sync.sent.as_ref()) {
+        println!("{} -> {}: {}", message.timestamp, sent.destination.as_ref().map(|d| d.to_string()).unwrap_or_default(), sent.message.message);
+    }
+}
+
+fn render_qr(uri: &str) -> String {
+    QrCode::new(uri).unwrap()
+        .render::<unicode::Dense1x2>()
+        .dark_color(unicode::Dense1x2::Light)
+        .light_color(unicode::Dense1x2::Dark)
+        .build()
+}
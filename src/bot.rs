@@ -0,0 +1,516 @@
+use std::future::Future;
+use std::sync::Mutex;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use regex::Regex;
+use serde::Deserialize;
+use crate::signald::Signald;
+use crate::signalderror::SignaldError;
+use crate::signaldrequest::Recipient;
+use crate::signaldresponse::{SignaldResponse, ResponseType, Message, GroupInfo, ErrorData};
+use crate::signaladdress::SignalAddress;
+use crate::conversation::Conversation;
+use crate::groups::{GroupTracker, GroupEvent};
+use crate::v1::{self, JsonReaction};
+
+type HandlerFn = Box<dyn Fn(Context) -> BoxFuture<'static, Option<String>> + Send + Sync>;
+
+/// What makes a handler run
+enum Trigger {
+    /// A message starting with the command prefix and the command name
+    Command(String),
+    /// A message body matching the regex
+    Pattern(Regex),
+    /// A reaction to a message
+    Reaction,
+    /// The account was added to a group
+    GroupJoin,
+}
+
+struct Handler {
+    trigger: Trigger,
+    handler: HandlerFn,
+}
+
+/// A received message that triggered a handler
+#[derive(Clone, Debug)]
+pub struct Context {
+    /// The account that received the message
+    pub account: String,
+    pub sender: Option<SignalAddress>,
+    /// The 1:1 or group conversation the message was sent in, replies are sent here
+    pub conversation: Recipient,
+    pub message: Message,
+    /// The whitespace separated arguments after a command
+    pub args: Vec<String>,
+    /// The capture groups of a matched pattern, the first one is the whole match
+    pub captures: Vec<Option<String>>,
+}
+impl Context {
+    /// The text of the message
+    pub fn body(&self) -> &str {
+        &self.message.message
+    }
+    /// A command argument
+    pub fn arg(&self, index: usize) -> Option<&str> {
+        self.args.get(index).map(|a| a.as_str())
+    }
+    /// A capture group of the matched pattern
+    pub fn capture(&self, index: usize) -> Option<&str> {
+        self.captures.get(index).and_then(|c| c.as_deref())
+    }
+    /// The conversation of the message including its sender, to keep dialog state in a ConversationStore
+    pub fn conversation(&self) -> Conversation {
+        Conversation {
+            account: self.account.clone(),
+            recipient: self.conversation.clone(),
+            sender: self.sender.clone(),
+        }
+    }
+    /// The reaction when the message is a reaction
+    pub fn reaction(&self) -> Option<&JsonReaction> {
+        self.message.reaction.as_ref()
+    }
+}
+
+/// A message a handler wants to send back
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reply {
+    pub recipient: Recipient,
+    pub body: String,
+}
+
+/// Runs before the handlers for every received message
+/// Returning false stops the message from reaching the handlers
+pub trait Middleware: Send + Sync {
+    fn handle(&self, context: &Context) -> bool;
+}
+impl<F: Fn(&Context) -> bool + Send + Sync> Middleware for F {
+    fn handle(&self, context: &Context) -> bool {
+        self(context)
+    }
+}
+
+/// The groups in a list_groups response
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct GroupList {
+    groups: Vec<GroupInfo>,
+    groupsv2: Vec<v1::JsonGroupV2Info>,
+}
+
+/// Routes the messages an account receives to handlers and sends their replies back
+/// Handlers are tried in the order they are registered, only the first matching handler runs
+pub struct Bot {
+    account: String,
+    prefix: String,
+    handlers: Vec<Handler>,
+    middleware: Vec<Box<dyn Middleware>>,
+    // The known state of the groups, to tell when the account was added to one
+    groups: Mutex<GroupTracker>,
+}
+impl Bot {
+    /// Create a bot for an account, commands start with "/"
+    pub fn new(account: &str) -> Bot {
+        Bot {
+            account: account.to_string(),
+            prefix: "/".to_string(),
+            handlers: Vec::new(),
+            middleware: Vec::new(),
+            groups: Mutex::new(GroupTracker::new()),
+        }
+    }
+    /// Change the prefix commands start with
+    pub fn set_prefix(&mut self, prefix: &str) -> &mut Bot {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    /// Handle a command such as "/deploy production", the words after the command are its arguments
+    pub fn command<F, Fut>(&mut self, name: &str, handler: F) -> &mut Bot
+        where F: Fn(Context) -> Fut + Send + Sync + 'static, Fut: Future<Output = Option<String>> + Send + 'static {
+        self.add_handler(Trigger::Command(name.to_string()), handler)
+    }
+    /// Handle messages matching a regex, its capture groups are available in the context
+    pub fn pattern<F, Fut>(&mut self, pattern: Regex, handler: F) -> &mut Bot
+        where F: Fn(Context) -> Fut + Send + Sync + 'static, Fut: Future<Output = Option<String>> + Send + 'static {
+        self.add_handler(Trigger::Pattern(pattern), handler)
+    }
+    /// Handle reactions to messages
+    pub fn on_reaction<F, Fut>(&mut self, handler: F) -> &mut Bot
+        where F: Fn(Context) -> Fut + Send + Sync + 'static, Fut: Future<Output = Option<String>> + Send + 'static {
+        self.add_handler(Trigger::Reaction, handler)
+    }
+    /// Handle the account being added to a group
+    pub fn on_group_join<F, Fut>(&mut self, handler: F) -> &mut Bot
+        where F: Fn(Context) -> Fut + Send + Sync + 'static, Fut: Future<Output = Option<String>> + Send + 'static {
+        self.add_handler(Trigger::GroupJoin, handler)
+    }
+    /// Remember a group the account is already in, such as the result of get_group
+    /// Without it the first update of the group after starting counts as the account joining
+    pub fn insert_group(&self, group: &v1::JsonGroupV2Info) {
+        self.groups.lock().unwrap().insert(&self.account, group);
+    }
+    /// Remember every group the account is already in, run does this before handling messages
+    pub async fn insert_groups(&self, signald: &mut Signald) -> Result<(), SignaldError> {
+        let response = signald.list_groups(self.account.clone()).await?;
+        let list: GroupList = match response.data.data_value().map(serde_json::from_value) {
+            Some(Ok(list)) => list,
+            _ => return Err(SignaldError::Other(response.data.type_name().to_string(), ErrorData {
+                message: "expected a group list".to_string(),
+                ..Default::default()
+            })),
+        };
+
+        let mut groups = self.groups.lock().unwrap();
+        for group in &list.groups {
+            groups.insert_legacy(&self.account, group);
+        }
+        for group in &list.groupsv2 {
+            groups.insert(&self.account, group);
+        }
+        Ok(())
+    }
+    /// Add a middleware, middleware runs in the order it's added
+    pub fn middleware<M: Middleware + 'static>(&mut self, middleware: M) -> &mut Bot {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+    fn add_handler<F, Fut>(&mut self, trigger: Trigger, handler: F) -> &mut Bot
+        where F: Fn(Context) -> Fut + Send + Sync + 'static, Fut: Future<Output = Option<String>> + Send + 'static {
+        self.handlers.push(Handler {
+            trigger,
+            handler: Box::new(move |context| handler(context).boxed()),
+        });
+        self
+    }
+
+    /// Run the matching handler for a response
+    /// Returns the reply to send back to the conversation the message came from
+    pub async fn dispatch(&self, response: &SignaldResponse) -> Option<Reply> {
+        let context = self.context(response)?;
+        let joined = self.is_group_join(response);
+        if !self.middleware.iter().all(|m| m.handle(&context)) {
+            return None;
+        }
+
+        let (handler, context) = self.handlers.iter()
+            .find_map(|handler| self.matches(&handler.trigger, &context, joined).map(|context| (handler, context)))?;
+        let recipient = context.conversation.clone();
+        let body = (handler.handler)(context).await?;
+
+        Some(Reply {
+            recipient,
+            body,
+        })
+    }
+
+    /// Handle every message the account receives until the connection closes
+    /// The groups the account is already in are inserted first, so restarting doesn't count as joining them
+    /// Returns an error when the groups can't be listed or a reply can't be written to the socket
+    pub async fn run(&self, signald: &mut Signald) -> Result<(), SignaldError> {
+        let mut rx = signald.get_rx();
+        self.insert_groups(signald).await?;
+        for response in rx.iter() {
+            if let Some(reply) = self.dispatch(&response).await {
+                let result = match reply.recipient {
+                    Recipient::Address(address) => signald.send(self.account.clone(), address, Some(reply.body)).await,
+                    Recipient::Group(group_id) => signald.send_group(self.account.clone(), group_id, Some(reply.body)).await,
+                };
+                // A reply that fails to send doesn't stop the bot, a broken socket does
+                if let Err(SignaldError::Io(error)) = result {
+                    return Err(SignaldError::Io(error));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Create a context for a message sent to this account
+    fn context(&self, response: &SignaldResponse) -> Option<Context> {
+        let data = match &response.data {
+            ResponseType::Message(Some(data)) => data,
+            _ => return None,
+        };
+        if data.username.as_deref().unwrap_or(&self.account) != self.account {
+            return None;
+        }
+
+        let message = data.data_message.clone()?;
+        let sender = data.sender();
+        let conversation = match message.group_id() {
+            Some(group_id) => Recipient::Group(group_id.to_string()),
+            None => Recipient::Address(sender.clone()?),
+        };
+
+        Some(Context {
+            account: self.account.clone(),
+            sender,
+            conversation,
+            message,
+            args: Vec::new(),
+            captures: Vec::new(),
+        })
+    }
+
+    /// Check if a trigger matches the message, the returned context holds the extracted arguments
+    fn matches(&self, trigger: &Trigger, context: &Context, joined: bool) -> Option<Context> {
+        let mut context = context.clone();
+        match trigger {
+            Trigger::Command(name) => {
+                let rest = context.body().strip_prefix(self.prefix.as_str())?.strip_prefix(name.as_str())?;
+                if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+                    return None;
+                }
+                context.args = rest.split_whitespace().map(|a| a.to_string()).collect();
+            }
+            Trigger::Pattern(regex) => {
+                let captures = regex.captures(context.body())?;
+                context.captures = captures.iter().map(|c| c.map(|c| c.as_str().to_string())).collect();
+            }
+            Trigger::Reaction => {
+                context.reaction()?;
+            }
+            Trigger::GroupJoin => {
+                if !joined {
+                    return None;
+                }
+            }
+        }
+        Some(context)
+    }
+
+    /// Check if the message added the account to a group
+    /// Every group update goes through the tracker, so a later update of the same group isn't a join
+    fn is_group_join(&self, response: &SignaldResponse) -> bool {
+        let data = match &response.data {
+            ResponseType::Message(Some(data)) => data,
+            _ => return false,
+        };
+        let update = match self.groups.lock().unwrap().update(data) {
+            Some(update) => update,
+            None => return false,
+        };
+        update.events.iter().any(|event| match event {
+            GroupEvent::MemberJoined(member) => *member == self.account.as_str(),
+            // A group that wasn't known yet was joined when the account is in its members
+            GroupEvent::FirstSeen => data.data_message.as_ref()
+                .and_then(|message| message.group_v2.as_ref().and_then(|group| group.members.as_ref())
+                    .or_else(|| message.group_info.as_ref().and_then(|group| group.members.as_ref())))
+                .map(|members| members.iter().any(|member| *member == self.account.as_str()))
+                .unwrap_or(false),
+            _ => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use crate::signaldresponse::{MessageData, GroupInfo, GroupV2Info};
+    use crate::conversation::ConversationStore;
+    use crate::socket::fakesignald::FakeSignald;
+
+    static ACCOUNT: &str = "+32000000000";
+    static SENDER: &str = "+32111111111";
+
+    fn response(message: Message) -> SignaldResponse {
+        SignaldResponse {
+            id: None,
+            defaulted: Vec::new(),
+            data: ResponseType::Message(Some(MessageData {
+                username: Some(ACCOUNT.to_string()),
+                source: Some(SignalAddress::from_number(SENDER).unwrap()),
+                data_message: Some(message),
+                ..Default::default()
+            })),
+        }
+    }
+
+    fn text(body: &str) -> SignaldResponse {
+        response(Message {
+            message: body.to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn reply_to_sender(body: &str) -> Option<Reply> {
+        Some(Reply {
+            recipient: Recipient::Address(SignalAddress::from_number(SENDER).unwrap()),
+            body: body.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_command_arguments() {
+        let mut bot = Bot::new(ACCOUNT);
+        bot.command("deploy", |context| async move {
+            Some(format!("Deploying {}", context.arg(0).unwrap_or("nothing")))
+        });
+
+        assert_eq!(block_on(bot.dispatch(&text("/deploy production"))), reply_to_sender("Deploying production"));
+        assert_eq!(block_on(bot.dispatch(&text("/deploy"))), reply_to_sender("Deploying nothing"));
+        assert_eq!(block_on(bot.dispatch(&text("/deployment"))), None);
+        assert_eq!(block_on(bot.dispatch(&text("deploy production"))), None);
+    }
+
+    #[test]
+    fn test_pattern_captures() {
+        let mut bot = Bot::new(ACCOUNT);
+        bot.pattern(Regex::new(r"ticket #(\d+)").unwrap(), |context| async move {
+            Some(format!("Looking up {}", context.capture(1).unwrap()))
+        });
+
+        assert_eq!(block_on(bot.dispatch(&text("what about ticket #42?"))), reply_to_sender("Looking up 42"));
+        assert_eq!(block_on(bot.dispatch(&text("no tickets here"))), None);
+    }
+
+    #[test]
+    fn test_first_matching_handler_runs() {
+        let mut bot = Bot::new(ACCOUNT);
+        bot.command("help", |_| async { Some("help".to_string()) })
+            .pattern(Regex::new(".*").unwrap(), |_| async { Some("fallback".to_string()) });
+
+        assert_eq!(block_on(bot.dispatch(&text("/help"))), reply_to_sender("help"));
+        assert_eq!(block_on(bot.dispatch(&text("hello"))), reply_to_sender("fallback"));
+    }
+
+    #[test]
+    fn test_reaction() {
+        let mut bot = Bot::new(ACCOUNT);
+        bot.on_reaction(|context| async move { Some(context.reaction().unwrap().emoji.clone()) });
+
+        let reaction = response(Message {
+            reaction: Some(JsonReaction {
+                emoji: "👍".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        assert_eq!(block_on(bot.dispatch(&reaction)), reply_to_sender("👍"));
+        assert_eq!(block_on(bot.dispatch(&text("hello"))), None);
+    }
+
+    #[test]
+    fn test_group_join_replies_to_group() {
+        let mut bot = Bot::new(ACCOUNT);
+        bot.on_group_join(|_| async { Some("Hello group".to_string()) });
+
+        let join = response(Message {
+            group_info: Some(GroupInfo {
+                group_id: "Z3JvdXBpZA==".to_string(),
+                typ: "UPDATE".to_string(),
+                members: Some(vec![SignalAddress::from_number(ACCOUNT).unwrap()]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        assert_eq!(block_on(bot.dispatch(&join)), Some(Reply {
+            recipient: Recipient::Group("Z3JvdXBpZA==".to_string()),
+            body: "Hello group".to_string(),
+        }));
+    }
+
+    fn group_v2(revision: i32, title: &str, members: &[&str]) -> SignaldResponse {
+        response(Message {
+            group_v2: Some(GroupV2Info {
+                id: "Z3JvdXBpZA==".to_string(),
+                revision,
+                title: Some(title.to_string()),
+                members: Some(members.iter().map(|m| SignalAddress::from_number(m).unwrap()).collect()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_group_rename_is_not_a_join() {
+        let mut bot = Bot::new(ACCOUNT);
+        bot.on_group_join(|_| async { Some("Hello group".to_string()) });
+        let hello = Some(Reply {
+            recipient: Recipient::Group("Z3JvdXBpZA==".to_string()),
+            body: "Hello group".to_string(),
+        });
+
+        assert_eq!(block_on(bot.dispatch(&group_v2(1, "Ops", &[SENDER]))), None);
+        assert_eq!(block_on(bot.dispatch(&group_v2(2, "Ops", &[SENDER, ACCOUNT]))), hello);
+        assert_eq!(block_on(bot.dispatch(&group_v2(3, "Operations", &[SENDER, ACCOUNT]))), None);
+
+        // A group the account was already in before the bot started
+        let bot = {
+            let mut bot = Bot::new(ACCOUNT);
+            bot.on_group_join(|_| async { Some("Hello group".to_string()) });
+            bot
+        };
+        bot.insert_group(&v1::JsonGroupV2Info {
+            id: "Z3JvdXBpZA==".to_string(),
+            revision: 2,
+            members: Some(vec![SignalAddress::from_number(ACCOUNT).unwrap()]),
+            ..Default::default()
+        });
+        assert_eq!(block_on(bot.dispatch(&group_v2(3, "Operations", &[SENDER, ACCOUNT]))), None);
+    }
+
+    #[test]
+    fn test_listed_groups_are_not_joins() {
+        let fake = FakeSignald::start();
+        fake.add_group(ACCOUNT, "Z3JvdXBpZA==", "Ops", &[ACCOUNT, SENDER]);
+        let mut signald = fake.connect();
+        let mut bot = Bot::new(ACCOUNT);
+        bot.on_group_join(|_| async { Some("Hello group".to_string()) });
+        block_on(bot.insert_groups(&mut signald)).unwrap();
+
+        // After a restart the next update of a group the account was in isn't a join
+        let update = response(Message {
+            group_info: Some(GroupInfo {
+                group_id: "Z3JvdXBpZA==".to_string(),
+                typ: "UPDATE".to_string(),
+                name: Some("Operations".to_string()),
+                members: Some(vec![SignalAddress::from_number(ACCOUNT).unwrap(), SignalAddress::from_number(SENDER).unwrap()]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        assert_eq!(block_on(bot.dispatch(&update)), None);
+    }
+
+    #[test]
+    fn test_middleware_stops_messages() {
+        let mut bot = Bot::new(ACCOUNT);
+        bot.middleware(|context: &Context| context.sender.as_ref().map(|s| s == "+32222222222").unwrap_or(false))
+            .command("deploy", |_| async { Some("Deploying".to_string()) });
+
+        assert_eq!(block_on(bot.dispatch(&text("/deploy"))), None);
+    }
+
+    #[test]
+    fn test_dialog_state() {
+        let store = ConversationStore::new();
+        let mut bot = Bot::new(ACCOUNT);
+        let deploy_store = store.clone();
+        bot.command("deploy", move |context| {
+            deploy_store.set(&context.conversation(), "environment");
+            async { Some("Which environment?".to_string()) }
+        });
+        bot.pattern(Regex::new(".*").unwrap(), move |context| {
+            let step = store.remove(&context.conversation());
+            async move {
+                step.map(|_| format!("Deploying to {}", context.body()))
+            }
+        });
+
+        assert_eq!(block_on(bot.dispatch(&text("production"))), None);
+        assert_eq!(block_on(bot.dispatch(&text("/deploy"))), reply_to_sender("Which environment?"));
+        assert_eq!(block_on(bot.dispatch(&text("production"))), reply_to_sender("Deploying to production"));
+    }
+
+    #[test]
+    fn test_other_account_is_ignored() {
+        let mut bot = Bot::new("+32333333333");
+        bot.command("deploy", |_| async { Some("Deploying".to_string()) });
+
+        assert_eq!(block_on(bot.dispatch(&text("/deploy"))), None);
+    }
+}
@@ -0,0 +1,306 @@
+use futures::executor::block_on;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tiny_http::{Header, Method, Request, Response, Server};
+use crate::signald::Signald;
+use crate::signaladdress::SignalAddress;
+use crate::signaldrequest::{Recipient, OutgoingAttachment};
+use crate::signaldresponse::{SignaldResponse, ResponseType};
+use crate::signalderror::SignaldError;
+
+/// The header webhook requests carry their signature in
+pub static SIGNATURE_HEADER: &str = "X-Signald-Signature";
+
+/// Called with the url and the message a webhook didn't take after every attempt or had no room for
+type DroppedFn = Arc<dyn Fn(&str, &SignaldResponse) + Send + Sync>;
+
+/// How the bridge serves its API and delivers webhooks
+#[derive(Clone, Debug)]
+pub struct BridgeConfig {
+    /// The address the HTTP API listens on
+    pub listen: String,
+    /// The token API requests must carry as "Authorization: Bearer <token>"
+    /// Required when the API listens on an address other than localhost
+    pub api_token: Option<String>,
+    /// The account messages are sent from and received for
+    pub account: String,
+    /// Every received message is posted to these urls
+    pub webhooks: Vec<String>,
+    /// The secret webhook bodies are signed with
+    pub webhook_secret: Option<String>,
+    /// The number of times a webhook is tried before the message is dropped
+    pub webhook_attempts: u32,
+    /// The wait after the first failed attempt, every next attempt waits twice as long
+    pub webhook_backoff: Duration,
+    /// The number of messages waiting for each webhook, messages that don't fit are dropped
+    pub webhook_queue: usize,
+}
+impl BridgeConfig {
+    /// Create a config for an account that listens on localhost
+    pub fn new(account: &str) -> BridgeConfig {
+        BridgeConfig {
+            listen: "127.0.0.1:8080".to_string(),
+            api_token: None,
+            account: account.to_string(),
+            webhooks: Vec::new(),
+            webhook_secret: None,
+            webhook_attempts: 5,
+            webhook_backoff: Duration::from_secs(1),
+            webhook_queue: 1000,
+        }
+    }
+}
+
+/// The body of a send request
+#[derive(Deserialize)]
+struct SendBody {
+    /// A phone number or uuid
+    recipient: Option<String>,
+    #[serde(rename = "groupId")]
+    group_id: Option<String>,
+    message: Option<String>,
+    /// Files on the signald host
+    #[serde(default)]
+    attachments: Vec<String>,
+}
+
+/// An HTTP bridge to signald for services that can't use the socket
+/// POST /send sends a message, GET /contacts and GET /groups list the contacts and groups of the account
+/// Every message the account receives is posted to the webhooks
+pub struct Bridge {
+    signald: Arc<Mutex<Signald>>,
+    config: BridgeConfig,
+    server: Server,
+    on_dropped: Mutex<Option<DroppedFn>>,
+}
+impl Bridge {
+    /// Start listening on the configured address
+    /// Returns an InvalidInput error when the address isn't localhost and there's no API token
+    pub fn new(signald: Signald, config: BridgeConfig) -> io::Result<Bridge> {
+        let server = Server::http(&config.listen).map_err(io::Error::other)?;
+        let local = server.server_addr().to_ip().map(|addr| addr.ip().is_loopback()).unwrap_or(true);
+        if !local && config.api_token.is_none() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "an API token is required to listen on an address other than localhost"));
+        }
+        Ok(Bridge {
+            signald: Arc::new(Mutex::new(signald)),
+            config,
+            server,
+            on_dropped: Mutex::new(None),
+        })
+    }
+    /// Handle the messages a webhook didn't take after every attempt or had no room for, they are dropped otherwise
+    pub fn on_dropped<F: Fn(&str, &SignaldResponse) + Send + Sync + 'static>(&mut self, handler: F) -> &mut Bridge {
+        *self.on_dropped.lock().unwrap() = Some(Arc::new(handler));
+        self
+    }
+    /// The address the API listens on
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+
+    /// Subscribe to the account, forward its messages to the webhooks and serve the API
+    /// This only returns when subscribing fails
+    pub fn run(&self) -> Result<(), SignaldError> {
+        let rx = {
+            let mut signald = self.signald.lock().unwrap();
+            let rx = signald.get_rx();
+            block_on(signald.subscribe(self.config.account.clone()))?;
+            rx
+        };
+
+        // Every webhook has its own queue and thread, so a slow webhook never holds up the socket or the other webhooks
+        let webhooks = Arc::new(Webhooks::new(&self.config));
+        let on_dropped = self.on_dropped.lock().unwrap().take();
+        let dropped = move |url: &str, response: &SignaldResponse| {
+            if let Some(on_dropped) = &on_dropped {
+                on_dropped(url, response);
+            }
+        };
+        let queues: Vec<(String, mpsc::SyncSender<SignaldResponse>)> = self.config.webhooks.iter()
+            .map(|url| {
+                let (tx, messages) = mpsc::sync_channel::<SignaldResponse>(self.config.webhook_queue);
+                let (webhooks, dropped, worker_url) = (webhooks.clone(), dropped.clone(), url.clone());
+                thread::spawn(move || {
+                    for response in messages {
+                        if !webhooks.deliver_to(&worker_url, &response.to_value().to_string()) {
+                            dropped(&worker_url, &response);
+                        }
+                    }
+                });
+                (url.clone(), tx)
+            })
+            .collect();
+
+        let account = self.config.account.clone();
+        thread::spawn(move || {
+            for response in rx {
+                let is_account_message = match &response.data {
+                    ResponseType::Message(Some(message)) => message.username.as_deref().unwrap_or(&account) == account,
+                    _ => false,
+                };
+                if !is_account_message {
+                    continue;
+                }
+                for (url, tx) in &queues {
+                    if let Err(mpsc::TrySendError::Full(response)) = tx.try_send(response.clone()) {
+                        dropped(url, &response);
+                    }
+                }
+            }
+        });
+
+        for request in self.server.incoming_requests() {
+            self.handle(request);
+        }
+        Ok(())
+    }
+
+    fn handle(&self, mut request: Request) {
+        let mut body = String::new();
+        let (status, response) = if !self.authorized(&request) {
+            (401, error_body("a valid API token is required"))
+        } else {
+            match request.as_reader().read_to_string(&mut body) {
+                Ok(_) => self.route(request.method(), request.url(), &body),
+                Err(e) => (400, error_body(&e.to_string())),
+            }
+        };
+
+        let response = Response::from_string(response.to_string())
+            .with_status_code(status)
+            .with_header(Header::from_bytes("Content-Type", "application/json").unwrap());
+        let _ = request.respond(response);
+    }
+
+    /// Check the API token of a request, every request is allowed without a token
+    fn authorized(&self, request: &Request) -> bool {
+        let token = match &self.config.api_token {
+            Some(token) => token,
+            None => return true,
+        };
+        request.headers().iter()
+            .find(|header| header.field.equiv("Authorization"))
+            .and_then(|header| header.value.as_str().strip_prefix("Bearer "))
+            .map(|given| same_secret(given, token))
+            .unwrap_or(false)
+    }
+
+    /// Handle an API request, returns the status code and json body
+    fn route(&self, method: &Method, url: &str, body: &str) -> (u16, Value) {
+        let path = url.split('?').next().unwrap_or_default();
+        let account = self.config.account.clone();
+        let mut signald = self.signald.lock().unwrap();
+
+        let result = match (method, path) {
+            (Method::Post, "/send") => {
+                let body: SendBody = match serde_json::from_str(body) {
+                    Ok(body) => body,
+                    Err(e) => return (400, error_body(&e.to_string())),
+                };
+                let recipient = match (body.recipient, body.group_id) {
+                    (_, Some(group_id)) => Recipient::Group(group_id),
+                    (Some(recipient), None) => match recipient.parse::<SignalAddress>() {
+                        Ok(address) => Recipient::Address(address),
+                        Err(e) => return (400, error_body(&e.to_string())),
+                    },
+                    (None, None) => return (400, error_body("a recipient or groupId is required")),
+                };
+                let attachments = body.attachments.into_iter()
+                    .map(|filename| OutgoingAttachment {
+                        filename,
+                        ..Default::default()
+                    })
+                    .collect();
+                block_on(signald.send_message(account, recipient, body.message, attachments)).map(|result| result.to_value())
+            }
+            (Method::Get, "/contacts") => block_on(signald.list_contacts(account)).map(|r| r.data.data_value().unwrap_or_default()),
+            (Method::Get, "/groups") => block_on(signald.list_groups(account)).map(|r| r.data.data_value().unwrap_or_default()),
+            _ => return (404, error_body("not found")),
+        };
+
+        match result {
+            Ok(value) => (200, value),
+            Err(error @ SignaldError::InputError(_)) | Err(error @ SignaldError::InvalidRequest(_)) => (400, error_body(&error.to_string())),
+            Err(error @ SignaldError::Timeout) => (504, error_body(&error.to_string())),
+            Err(error) => (502, error_body(&error.to_string())),
+        }
+    }
+}
+
+// Compare secrets in a time that doesn't depend on where they differ
+fn same_secret(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn error_body(message: &str) -> Value {
+    json!({ "error": message })
+}
+
+/// Posts received messages to webhook urls
+/// Every body is signed with the secret, failed posts are retried with exponential backoff
+pub struct Webhooks {
+    urls: Vec<String>,
+    secret: Option<String>,
+    attempts: u32,
+    backoff: Duration,
+    agent: ureq::Agent,
+}
+impl Webhooks {
+    pub fn new(config: &BridgeConfig) -> Webhooks {
+        Webhooks {
+            urls: config.webhooks.clone(),
+            secret: config.webhook_secret.clone(),
+            attempts: config.webhook_attempts.max(1),
+            backoff: config.webhook_backoff,
+            agent: ureq::AgentBuilder::new().timeout(Duration::from_secs(10)).build(),
+        }
+    }
+
+    /// Post a json body to every webhook
+    /// Returns the urls that could not be reached after every attempt
+    pub fn deliver(&self, body: &str) -> Vec<String> {
+        self.urls.iter()
+            .filter(|url| !self.deliver_to(url, body))
+            .cloned()
+            .collect()
+    }
+
+    /// Post a json body to a single webhook
+    /// Returns false when it could not be reached after every attempt
+    pub fn deliver_to(&self, url: &str, body: &str) -> bool {
+        let mut backoff = self.backoff;
+        for attempt in 1..=self.attempts {
+            let mut request = self.agent.post(url).set("Content-Type", "application/json");
+            if let Some(secret) = &self.secret {
+                request = request.set(SIGNATURE_HEADER, &sign(secret, body));
+            }
+            match request.send_string(body) {
+                Ok(_) => return true,
+                // Client errors won't go away by retrying
+                Err(ureq::Error::Status(code, _)) if (400..500).contains(&code) && code != 429 => return false,
+                Err(_) if attempt < self.attempts => {
+                    thread::sleep(backoff);
+                    backoff *= 2;
+                }
+                Err(_) => {}
+            }
+        }
+        false
+    }
+}
+
+/// Sign a webhook body with HMAC-SHA256
+/// Returns the signature as it is sent in the signature header: "sha256=<hex digest>"
+pub fn sign(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
@@ -0,0 +1,172 @@
+use std::fmt;
+use crate::signaldresponse::VersionData;
+use crate::signalderror::SignaldError;
+
+/// A feature that is only available in some signald versions
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Feature {
+    /// Requests and responses in the v1 protocol
+    ProtocolV1,
+    /// Reacting to messages
+    Reactions,
+    /// Querying user profiles
+    Profiles,
+    /// Querying and joining v2 groups
+    GroupsV2,
+    /// Editing sent messages
+    EditMessages,
+}
+impl Feature {
+    /// The first signald version that supports this feature
+    pub fn min_version(&self) -> SignaldVersion {
+        match self {
+            Feature::ProtocolV1 => SignaldVersion::new(0, 11, 0),
+            Feature::Reactions => SignaldVersion::new(0, 11, 0),
+            Feature::Profiles => SignaldVersion::new(0, 11, 0),
+            Feature::GroupsV2 => SignaldVersion::new(0, 11, 0),
+            Feature::EditMessages => SignaldVersion::new(0, 23, 0),
+        }
+    }
+}
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Feature::ProtocolV1 => "protocol v1",
+            Feature::Reactions => "reactions",
+            Feature::Profiles => "profiles",
+            Feature::GroupsV2 => "v2 groups",
+            Feature::EditMessages => "message edits",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A signald release number
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SignaldVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+impl SignaldVersion {
+    pub fn new(major: u32, minor: u32, patch: u32) -> SignaldVersion {
+        SignaldVersion { major, minor, patch }
+    }
+
+    /// Parse the leading release number of a version such as "0.9.0+git2020-03-08r1a9be52a.5"
+    /// Only the major version is needed, a missing or unreadable minor or patch version such as in "0.13.x" is 0
+    pub fn parse(version: &str) -> Option<SignaldVersion> {
+        let version = version.trim().trim_start_matches('v');
+        let mut numbers = version.split('.').map(|n| {
+            let digits: String = n.chars().take_while(|c| c.is_ascii_digit()).collect();
+            digits.parse::<u32>().ok()
+        });
+
+        let major = numbers.next()??;
+        let minor = numbers.next().flatten().unwrap_or(0);
+        let patch = numbers.next().flatten().unwrap_or(0);
+        Some(SignaldVersion::new(major, minor, patch))
+    }
+}
+impl fmt::Display for SignaldVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// The features of the connected signald daemon
+/// When the version is unknown no version dependent feature is assumed to be supported
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Capabilities {
+    /// The version data signald sent
+    pub version_data: Option<VersionData>,
+    /// The parsed release number
+    pub version: Option<SignaldVersion>,
+}
+impl Capabilities {
+    /// Detect the capabilities from the version signald reports
+    pub fn from_version(version_data: &VersionData) -> Capabilities {
+        Capabilities {
+            version: SignaldVersion::parse(&version_data.version),
+            version_data: Some(version_data.clone()),
+        }
+    }
+
+    /// Check if the daemon supports a feature
+    pub fn supports(&self, feature: Feature) -> bool {
+        match self.version {
+            Some(version) => version >= feature.min_version(),
+            None => false,
+        }
+    }
+
+    /// Returns an Unsupported error when the daemon lacks a feature
+    pub fn require(&self, feature: Feature) -> Result<(), SignaldError> {
+        if self.supports(feature) {
+            return Ok(());
+        }
+
+        Err(SignaldError::Unsupported {
+            feature,
+            version: self.version_data.as_ref().map(|v| v.version.clone()).unwrap_or_else(|| "unknown".to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version_data(version: &str) -> VersionData {
+        VersionData {
+            name: "signald".to_string(),
+            version: version.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(SignaldVersion::parse("0.9.0+git2020-03-08r1a9be52a.5"), Some(SignaldVersion::new(0, 9, 0)));
+        assert_eq!(SignaldVersion::parse("0.13.1-12-g1a9be52a"), Some(SignaldVersion::new(0, 13, 1)));
+        assert_eq!(SignaldVersion::parse("1"), Some(SignaldVersion::new(1, 0, 0)));
+        assert_eq!(SignaldVersion::parse("0.13.x"), Some(SignaldVersion::new(0, 13, 0)));
+        assert_eq!(SignaldVersion::parse("0.13"), Some(SignaldVersion::new(0, 13, 0)));
+        assert_eq!(SignaldVersion::parse("v0.23.2"), Some(SignaldVersion::new(0, 23, 2)));
+        assert_eq!(SignaldVersion::parse("0.14.0-rc1"), Some(SignaldVersion::new(0, 14, 0)));
+        assert_eq!(SignaldVersion::parse("unknown"), None);
+        assert_eq!(SignaldVersion::parse(""), None);
+    }
+
+    #[test]
+    fn test_supports_feature() {
+        let old = Capabilities::from_version(&version_data("0.9.0+git2020-03-08r1a9be52a.5"));
+        assert!(!old.supports(Feature::ProtocolV1));
+
+        let new = Capabilities::from_version(&version_data("0.13.1"));
+        assert!(new.supports(Feature::ProtocolV1));
+        assert!(new.require(Feature::GroupsV2).is_ok());
+    }
+
+    #[test]
+    fn test_unknown_version_supports_nothing() {
+        assert!(!Capabilities::default().supports(Feature::ProtocolV1));
+        assert!(!Capabilities::from_version(&version_data("unknown")).supports(Feature::Reactions));
+        match Capabilities::default().require(Feature::Profiles) {
+            Err(SignaldError::Unsupported { version, .. }) => assert_eq!(version, "unknown"),
+            _ => panic!("Expected an unsupported error")
+        }
+    }
+
+    #[test]
+    fn test_require_unsupported_feature() {
+        let old = Capabilities::from_version(&version_data("0.9.0"));
+        match old.require(Feature::Reactions) {
+            Err(SignaldError::Unsupported { feature, version }) => {
+                assert_eq!(feature, Feature::Reactions);
+                assert_eq!(version, "0.9.0");
+            }
+            _ => panic!("Expected an unsupported error")
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use std::sync::{Arc, Mutex};
+use crate::signaldrequest::Recipient;
+use crate::signaldresponse::{SignaldResponse, ResponseType, MessageData};
+use crate::signaladdress::SignalAddress;
+
+/// A 1:1 or group conversation of an account
+/// A conversation with a sender only covers the messages of that sender
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conversation {
+    pub account: String,
+    pub recipient: Recipient,
+    pub sender: Option<SignalAddress>,
+}
+impl Conversation {
+    /// The 1:1 conversation between an account and a user
+    pub fn direct(account: &str, address: SignalAddress) -> Conversation {
+        Conversation {
+            account: account.to_string(),
+            recipient: Recipient::Address(address.clone()),
+            sender: Some(address),
+        }
+    }
+    /// A group conversation of an account
+    pub fn group(account: &str, group_id: &str) -> Conversation {
+        Conversation {
+            account: account.to_string(),
+            recipient: Recipient::Group(group_id.to_string()),
+            sender: None,
+        }
+    }
+    /// Only cover the messages of one sender
+    pub fn with_sender(mut self, sender: SignalAddress) -> Conversation {
+        self.sender = Some(sender);
+        self
+    }
+
+    /// The conversation a received message belongs to, including its sender
+    pub fn of(message: &MessageData) -> Option<Conversation> {
+        let account = message.username.clone()?;
+        let sender = message.sender()?;
+        let conversation = match message.data_message.as_ref().and_then(|m| m.group_id()) {
+            Some(group_id) => Conversation::group(&account, group_id).with_sender(sender),
+            None => Conversation::direct(&account, sender),
+        };
+        Some(conversation)
+    }
+
+    /// Check if a response is a message in this conversation
+    pub fn matches(&self, response: &SignaldResponse) -> bool {
+        let message = match &response.data {
+            ResponseType::Message(Some(message)) if message.data_message.is_some() => message,
+            _ => return false,
+        };
+
+        match Conversation::of(message) {
+            Some(other) => {
+                other.account == self.account && other.recipient == self.recipient
+                    && self.sender.as_ref().map(|s| other.sender.as_ref() == Some(s)).unwrap_or(true)
+            }
+            None => false,
+        }
+    }
+}
+
+/// State kept per conversation, such as the step a dialog is in
+/// Clones share the same state so a store can be moved into several handlers
+#[derive(Clone, Debug)]
+pub struct ConversationStore<T> {
+    states: Arc<Mutex<Vec<(Conversation, T)>>>,
+}
+impl<T: Clone> ConversationStore<T> {
+    pub fn new() -> ConversationStore<T> {
+        ConversationStore {
+            states: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+    /// The state of a conversation
+    pub fn get(&self, conversation: &Conversation) -> Option<T> {
+        self.states.lock().unwrap().iter()
+            .find(|(c, _)| c == conversation)
+            .map(|(_, state)| state.clone())
+    }
+    /// Set the state of a conversation
+    pub fn set(&self, conversation: &Conversation, state: T) {
+        let mut states = self.states.lock().unwrap();
+        match states.iter_mut().find(|(c, _)| c == conversation) {
+            Some((_, current)) => *current = state,
+            None => states.push((conversation.clone(), state)),
+        }
+    }
+    /// Remove the state of a conversation and return it
+    pub fn remove(&self, conversation: &Conversation) -> Option<T> {
+        let mut states = self.states.lock().unwrap();
+        let index = states.iter().position(|(c, _)| c == conversation)?;
+        Some(states.remove(index).1)
+    }
+}
+impl<T: Clone> Default for ConversationStore<T> {
+    fn default() -> Self {
+        ConversationStore::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signaldresponse::{Message, GroupInfo};
+
+    static ACCOUNT: &str = "+32000000000";
+
+    fn address(number: &str) -> SignalAddress {
+        SignalAddress::from_number(number).unwrap()
+    }
+
+    fn message(source: &str, group_id: Option<&str>) -> SignaldResponse {
+        SignaldResponse {
+            id: None,
+            defaulted: Vec::new(),
+            data: ResponseType::Message(Some(MessageData {
+                username: Some(ACCOUNT.to_string()),
+                source: Some(address(source)),
+                data_message: Some(Message {
+                    group_info: group_id.map(|id| GroupInfo {
+                        group_id: id.to_string(),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })),
+        }
+    }
+
+    #[test]
+    fn test_direct_conversation_matches() {
+        let conversation = Conversation::direct(ACCOUNT, address("+32111111111"));
+        assert!(conversation.matches(&message("+32111111111", None)));
+        assert!(!conversation.matches(&message("+32222222222", None)));
+        assert!(!conversation.matches(&message("+32111111111", Some("Z3JvdXBpZA=="))));
+    }
+
+    #[test]
+    fn test_group_conversation_matches() {
+        let group = Conversation::group(ACCOUNT, "Z3JvdXBpZA==");
+        assert!(group.matches(&message("+32111111111", Some("Z3JvdXBpZA=="))));
+        assert!(group.matches(&message("+32222222222", Some("Z3JvdXBpZA=="))));
+        assert!(!group.matches(&message("+32111111111", None)));
+
+        let sender = group.with_sender(address("+32111111111"));
+        assert!(sender.matches(&message("+32111111111", Some("Z3JvdXBpZA=="))));
+        assert!(!sender.matches(&message("+32222222222", Some("Z3JvdXBpZA=="))));
+    }
+
+    #[test]
+    fn test_store() {
+        let store = ConversationStore::new();
+        let first = Conversation::direct(ACCOUNT, address("+32111111111"));
+        let second = Conversation::direct(ACCOUNT, address("+32222222222"));
+
+        store.set(&first, "asked environment");
+        assert_eq!(store.get(&first), Some("asked environment"));
+        assert_eq!(store.get(&second), None);
+
+        store.clone().set(&first, "deploying");
+        assert_eq!(store.remove(&first), Some("deploying"));
+        assert_eq!(store.get(&first), None);
+    }
+}
@@ -0,0 +1,334 @@
+use std::ops::Not;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use regex::Regex;
+use crate::conversation::Conversation;
+use crate::signaladdress::SignalAddress;
+use crate::signaldresponse::{SignaldResponse, ResponseType, MessageData, Message};
+
+/// A condition on the responses received from signald
+/// Every filter except Id and Type only matches received messages
+/// Filters are combined with and, or and !
+#[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum Filter {
+    /// Responses to the request with this id
+    Id(String),
+    /// Responses of the same type, the data of the type is ignored
+    Type(ResponseType),
+    /// Messages received by this account
+    Account(String),
+    /// Messages sent by this address
+    Sender(SignalAddress),
+    /// Messages sent in the group with this id
+    Group(String),
+    /// Messages in a conversation
+    Conversation(Conversation),
+    /// Messages of a kind, such as reactions or receipts
+    Kind(MessageKind),
+    /// Messages with a body matching the regex
+    Body(Regex),
+    /// Messages with at least one attachment
+    HasAttachment,
+    /// Messages sent between two timestamps in milliseconds, both bounds are inclusive
+    Time(Option<i64>, Option<i64>),
+    /// Responses matching every filter
+    All(Vec<Filter>),
+    /// Responses matching any of the filters
+    Any(Vec<Filter>),
+    /// Messages not matching the filter, other responses never match
+    Not(Box<Filter>),
+}
+impl Filter {
+    /// Messages with a body matching a regex pattern
+    pub fn body(pattern: &str) -> Result<Filter, regex::Error> {
+        Ok(Filter::Body(Regex::new(pattern)?))
+    }
+    /// Messages sent at or after a timestamp in milliseconds
+    pub fn after(timestamp: i64) -> Filter {
+        Filter::Time(Some(timestamp), None)
+    }
+    /// Messages sent at or before a timestamp in milliseconds
+    pub fn before(timestamp: i64) -> Filter {
+        Filter::Time(None, Some(timestamp))
+    }
+    /// Messages sent less than a duration ago
+    pub fn within(duration: Duration) -> Filter {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        Filter::after(now.saturating_sub(duration).as_millis() as i64)
+    }
+
+    /// Responses matching this filter and another one
+    pub fn and(self, other: Filter) -> Filter {
+        match self {
+            Filter::All(mut filters) => {
+                filters.push(other);
+                Filter::All(filters)
+            }
+            filter => Filter::All(vec![filter, other]),
+        }
+    }
+    /// Responses matching this filter or another one
+    pub fn or(self, other: Filter) -> Filter {
+        match self {
+            Filter::Any(mut filters) => {
+                filters.push(other);
+                Filter::Any(filters)
+            }
+            filter => Filter::Any(vec![filter, other]),
+        }
+    }
+
+    /// Check if a response matches the filter
+    pub fn matches(&self, response: &SignaldResponse) -> bool {
+        match self {
+            Filter::Id(id) => response.id.as_deref() == Some(id.as_str()),
+            Filter::Type(typ) => std::mem::discriminant(typ) == std::mem::discriminant(&response.data),
+            Filter::Conversation(conversation) => conversation.matches(response),
+            Filter::All(filters) => filters.iter().all(|f| f.matches(response)),
+            Filter::Any(filters) => filters.iter().any(|f| f.matches(response)),
+            filter => match &response.data {
+                ResponseType::Message(Some(message)) => filter.matches_message(message),
+                _ => false,
+            },
+        }
+    }
+
+    /// Check if a received message matches the filter
+    pub fn matches_message(&self, message: &MessageData) -> bool {
+        match self {
+            Filter::Id(_) | Filter::Type(_) | Filter::Conversation(_) => self.matches(&SignaldResponse {
+                id: None,
+                defaulted: Vec::new(),
+                data: ResponseType::Message(Some(message.clone())),
+            }),
+            Filter::Account(account) => message.username.as_deref() == Some(account.as_str()),
+            Filter::Sender(sender) => message.sender().as_ref() == Some(sender),
+            Filter::Group(group_id) => content(message).and_then(|m| m.group_id()) == Some(group_id.as_str()),
+            Filter::Kind(kind) => MessageKind::of(message) == Some(*kind),
+            Filter::Body(regex) => content(message).map(|m| regex.is_match(&m.message)).unwrap_or(false),
+            Filter::HasAttachment => content(message)
+                .and_then(|m| m.attachments.as_ref())
+                .map(|a| !a.is_empty())
+                .unwrap_or(false),
+            Filter::Time(after, before) => {
+                after.map(|t| message.timestamp >= t).unwrap_or(true)
+                    && before.map(|t| message.timestamp <= t).unwrap_or(true)
+            }
+            Filter::All(filters) => filters.iter().all(|f| f.matches_message(message)),
+            Filter::Any(filters) => filters.iter().any(|f| f.matches_message(message)),
+            Filter::Not(filter) => !filter.matches_message(message),
+        }
+    }
+}
+impl Not for Filter {
+    type Output = Filter;
+
+    fn not(self) -> Filter {
+        match self {
+            Filter::Not(filter) => *filter,
+            filter => Filter::Not(Box::new(filter)),
+        }
+    }
+}
+
+/// The kind of a received message
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MessageKind {
+    /// A message with a body or attachments
+    Text,
+    /// A reaction to an earlier message
+    Reaction,
+    /// The sender deleted an earlier message
+    RemoteDelete,
+    /// The sender edited an earlier message
+    Edit,
+    /// A delivery or read receipt
+    Receipt,
+    /// A typing indicator
+    Typing,
+    /// A message from another device of the account
+    Sync,
+}
+impl MessageKind {
+    /// The kind of a received message
+    /// Returns None for messages without any content, such as empty envelopes
+    pub fn of(message: &MessageData) -> Option<MessageKind> {
+        if message.receipt.is_some() || message.is_receipt {
+            return Some(MessageKind::Receipt);
+        }
+        if message.typing.is_some() {
+            return Some(MessageKind::Typing);
+        }
+        if message.sync_message.is_some() {
+            return Some(MessageKind::Sync);
+        }
+        if message.edit_message.is_some() {
+            return Some(MessageKind::Edit);
+        }
+        let data_message = message.data_message.as_ref()?;
+        if data_message.reaction.is_some() {
+            Some(MessageKind::Reaction)
+        } else if data_message.remote_delete.is_some() {
+            Some(MessageKind::RemoteDelete)
+        } else {
+            Some(MessageKind::Text)
+        }
+    }
+}
+
+/// The content of a message, including the new version of edits and messages sent from another device of the account
+fn content(message: &MessageData) -> Option<&Message> {
+    message.data_message.as_ref()
+        .or_else(|| message.edit_message.as_ref().map(|e| &e.data_message))
+        .or_else(|| message.sync_message.as_ref()?.sent.as_ref().map(|s| &s.message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signaldresponse::{Attachment, GroupInfo, SentMessage, SyncMessage, Typing};
+
+    static ACCOUNT: &str = "+32000000000";
+    static ALICE: &str = "+32111111111";
+
+    fn message(body: &str, timestamp: i64) -> MessageData {
+        MessageData {
+            username: Some(ACCOUNT.to_string()),
+            source: Some(SignalAddress::from_number(ALICE).unwrap()),
+            timestamp,
+            data_message: Some(Message {
+                timestamp,
+                message: body.to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn response(message: MessageData) -> SignaldResponse {
+        SignaldResponse {
+            id: None,
+            defaulted: Vec::new(),
+            data: ResponseType::Message(Some(message)),
+        }
+    }
+
+    #[test]
+    fn test_filter_request_id_success() {
+        let id = "test".to_string();
+
+        let message = SignaldResponse {
+            id: Some(id.clone()),
+            defaulted: Vec::new(),
+            data: ResponseType::Subscribed
+        };
+
+        assert!(Filter::Id(id).matches(&message));
+    }
+
+    #[test]
+    fn test_filter_request_id_wrong() {
+        let id = "test".to_string();
+
+        let message = SignaldResponse {
+            id: Some(id.clone()),
+            defaulted: Vec::new(),
+            data: ResponseType::Subscribed
+        };
+
+        assert!(!Filter::Id("INCORRECT_ID".to_string()).matches(&message));
+    }
+
+    #[test]
+    fn test_filter_request_type_correct() {
+        let message = SignaldResponse {
+            id: None,
+            defaulted: Vec::new(),
+            data: ResponseType::Subscribed
+        };
+
+        assert!(Filter::Type(ResponseType::Subscribed).matches(&message));
+    }
+
+    #[test]
+    fn test_filter_request_type_wrong() {
+        let message = SignaldResponse {
+            id: None,
+            defaulted: Vec::new(),
+            data: ResponseType::Subscribed
+        };
+
+        assert!(!Filter::Type(ResponseType::Unsubscribed).matches(&message));
+    }
+
+    #[test]
+    fn test_message_filters() {
+        let mut group_message = message("deploy finished", 2000);
+        group_message.data_message.as_mut().unwrap().group_info = Some(GroupInfo {
+            group_id: "Z3JvdXBpZA==".to_string(),
+            ..Default::default()
+        });
+        let group_message = response(group_message);
+
+        assert!(Filter::Account(ACCOUNT.to_string()).matches(&group_message));
+        assert!(Filter::Sender(SignalAddress::from_number(ALICE).unwrap()).matches(&group_message));
+        assert!(Filter::Group("Z3JvdXBpZA==".to_string()).matches(&group_message));
+        assert!(Filter::Conversation(Conversation::group(ACCOUNT, "Z3JvdXBpZA==")).matches(&group_message));
+        assert!(Filter::body("^deploy").unwrap().matches(&group_message));
+        assert!(Filter::Kind(MessageKind::Text).matches(&group_message));
+        assert!(Filter::Time(Some(1000), Some(2000)).matches(&group_message));
+
+        assert!(!Filter::Account(ALICE.to_string()).matches(&group_message));
+        assert!(!Filter::Group("b3RoZXI=".to_string()).matches(&group_message));
+        assert!(!Filter::body("failed").unwrap().matches(&group_message));
+        assert!(!Filter::HasAttachment.matches(&group_message));
+        assert!(!Filter::after(2001).matches(&group_message));
+        // Message filters never match other responses, not even negated
+        let subscribed = SignaldResponse { id: None, data: ResponseType::Subscribed, defaulted: Vec::new() };
+        assert!(!Filter::Account(ACCOUNT.to_string()).matches(&subscribed));
+        assert!(!(!Filter::Account(ALICE.to_string())).matches(&subscribed));
+        assert!(!(!Filter::body("^/").unwrap()).matches(&subscribed));
+    }
+
+    #[test]
+    fn test_combinators() {
+        let filter = Filter::Account(ACCOUNT.to_string())
+            .and(Filter::body("(?i)alert").unwrap().or(Filter::HasAttachment))
+            .and(!Filter::Kind(MessageKind::Reaction));
+
+        let mut with_attachment = message("report", 1000);
+        with_attachment.data_message.as_mut().unwrap().attachments = Some(vec![Attachment::default()]);
+
+        assert!(filter.matches(&response(message("ALERT: disk full", 1000))));
+        assert!(filter.matches(&response(with_attachment)));
+        assert!(!filter.matches(&response(message("all good", 1000))));
+        assert!(matches!(!!Filter::HasAttachment, Filter::HasAttachment));
+    }
+
+    #[test]
+    fn test_sync_and_typing_kinds() {
+        let sent = MessageData {
+            username: Some(ACCOUNT.to_string()),
+            sync_message: Some(SyncMessage {
+                sent: Some(SentMessage {
+                    message: Message {
+                        message: "sent from my phone".to_string(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(MessageKind::of(&sent), Some(MessageKind::Sync));
+        assert!(Filter::body("phone").unwrap().matches_message(&sent));
+
+        let typing = MessageData {
+            typing: Some(Typing::default()),
+            ..Default::default()
+        };
+        assert_eq!(MessageKind::of(&typing), Some(MessageKind::Typing));
+        assert_eq!(MessageKind::of(&MessageData::default()), None);
+    }
+}
@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::fmt;
+use crate::signaladdress::SignalAddress;
+use crate::signaldresponse::{MessageData, GroupInfo, GroupV2Info};
+use crate::v1;
+
+/// The role of a member in a v2 group
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GroupRole {
+    Default,
+    Administrator,
+}
+impl GroupRole {
+    /// The name signald uses for this role
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GroupRole::Default => "DEFAULT",
+            GroupRole::Administrator => "ADMINISTRATOR",
+        }
+    }
+}
+impl fmt::Display for GroupRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Who is allowed to do something in a v2 group
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccessLevel {
+    /// Anyone with the invitation link
+    Any,
+    /// Every member
+    Member,
+    /// Only administrators
+    Administrator,
+    /// Nobody, used to turn the invitation link off
+    Unsatisfiable,
+}
+impl AccessLevel {
+    /// The name signald uses for this access level
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccessLevel::Any => "ANY",
+            AccessLevel::Member => "MEMBER",
+            AccessLevel::Administrator => "ADMINISTRATOR",
+            AccessLevel::Unsatisfiable => "UNSATISFIABLE",
+        }
+    }
+}
+impl fmt::Display for AccessLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A single change to a group
+#[derive(Clone, Debug, PartialEq)]
+pub enum GroupEvent {
+    TitleChanged(String),
+    MemberJoined(SignalAddress),
+    MemberLeft(SignalAddress),
+    /// A user was invited and hasn't accepted yet
+    MemberInvited(SignalAddress),
+    /// A user asked to join through the invitation link
+    JoinRequested(SignalAddress),
+    AdminPromoted(SignalAddress),
+    AdminDemoted(SignalAddress),
+    AccessControlChanged(v1::GroupAccessControl),
+    InviteLinkChanged(String),
+    /// The group wasn't known yet, its state is remembered without comparing it to anything
+    FirstSeen,
+}
+
+/// A group update received in a message
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupUpdate {
+    pub account: String,
+    pub group_id: String,
+    /// The user that changed the group
+    pub editor: Option<SignalAddress>,
+    /// The new revision of a v2 group
+    pub revision: Option<i32>,
+    /// The changes since the last known state of the group
+    pub events: Vec<GroupEvent>,
+}
+
+// The known state of a group, fields are None until a message or query included them
+#[derive(Clone, Debug, Default, PartialEq)]
+struct GroupState {
+    revision: Option<i32>,
+    title: Option<String>,
+    members: Option<Vec<SignalAddress>>,
+    admins: Option<Vec<SignalAddress>>,
+    pending: Option<Vec<SignalAddress>>,
+    requesting: Option<Vec<SignalAddress>>,
+    access_control: Option<v1::GroupAccessControl>,
+    invite_link: Option<String>,
+}
+impl GroupState {
+    fn from_v2(group: &GroupV2Info) -> GroupState {
+        GroupState {
+            revision: Some(group.revision),
+            title: group.title.clone(),
+            members: group.members.clone(),
+            admins: group.member_detail.as_ref().map(|members| admins(members)),
+            pending: group.pending_members.clone(),
+            requesting: group.requesting_members.clone(),
+            access_control: group.access_control.clone(),
+            invite_link: group.invite_link.clone(),
+        }
+    }
+
+    fn from_v1(group: &v1::JsonGroupV2Info) -> GroupState {
+        GroupState {
+            revision: Some(group.revision),
+            title: group.title.clone(),
+            members: group.members.clone(),
+            admins: group.member_detail.as_ref().map(|members| admins(members)),
+            pending: group.pending_members.clone(),
+            requesting: group.requesting_members.clone(),
+            access_control: group.access_control.clone(),
+            invite_link: group.invite_link.clone(),
+        }
+    }
+
+    fn from_legacy(group: &GroupInfo) -> GroupState {
+        GroupState {
+            title: group.name.clone(),
+            members: group.members.clone(),
+            ..Default::default()
+        }
+    }
+
+    // The events that turn this state into a newer one, fields the newer state doesn't have are skipped
+    fn events(&self, new: &GroupState) -> Vec<GroupEvent> {
+        let mut events = Vec::new();
+        if let Some(title) = &new.title {
+            if self.title.as_ref() != Some(title) {
+                events.push(GroupEvent::TitleChanged(title.clone()));
+            }
+        }
+        if let (Some(old), Some(members)) = (&self.members, &new.members) {
+            events.extend(added(old, members).map(GroupEvent::MemberJoined));
+            events.extend(added(members, old).map(GroupEvent::MemberLeft));
+        }
+        if let (Some(old), Some(pending)) = (&self.pending, &new.pending) {
+            events.extend(added(old, pending).map(GroupEvent::MemberInvited));
+        }
+        if let (Some(old), Some(requesting)) = (&self.requesting, &new.requesting) {
+            events.extend(added(old, requesting).map(GroupEvent::JoinRequested));
+        }
+        if let (Some(old), Some(admins)) = (&self.admins, &new.admins) {
+            events.extend(added(old, admins).map(GroupEvent::AdminPromoted));
+            // Admins that left the group aren't demoted
+            let members = new.members.as_ref();
+            events.extend(added(admins, old)
+                .filter(|admin| members.map(|m| m.contains(admin)).unwrap_or(true))
+                .map(GroupEvent::AdminDemoted));
+        }
+        if let Some(access_control) = &new.access_control {
+            if self.access_control.as_ref() != Some(access_control) {
+                events.push(GroupEvent::AccessControlChanged(access_control.clone()));
+            }
+        }
+        if let Some(invite_link) = &new.invite_link {
+            if self.invite_link.as_ref() != Some(invite_link) {
+                events.push(GroupEvent::InviteLinkChanged(invite_link.clone()));
+            }
+        }
+        events
+    }
+
+    fn merge(&mut self, new: GroupState) {
+        self.revision = new.revision.or(self.revision);
+        self.title = new.title.or(self.title.take());
+        self.members = new.members.or(self.members.take());
+        self.admins = new.admins.or(self.admins.take());
+        self.pending = new.pending.or(self.pending.take());
+        self.requesting = new.requesting.or(self.requesting.take());
+        self.access_control = new.access_control.or(self.access_control.take());
+        self.invite_link = new.invite_link.or(self.invite_link.take());
+    }
+}
+
+// The addresses in new that aren't in old
+fn added<'a>(old: &'a [SignalAddress], new: &'a [SignalAddress]) -> impl Iterator<Item = SignalAddress> + 'a {
+    new.iter().filter(move |address| !old.contains(address)).cloned()
+}
+
+fn admins(members: &[v1::GroupMember]) -> Vec<SignalAddress> {
+    members.iter()
+        .filter(|member| member.role.as_deref() == Some(GroupRole::Administrator.as_str()))
+        .filter_map(|member| SignalAddress::from_uuid(member.uuid.as_deref()?).ok())
+        .collect()
+}
+
+/// Turns the group updates in received messages into typed events
+/// Keeps the last known state of every group, events are the differences with that state
+#[derive(Clone, Debug, Default)]
+pub struct GroupTracker {
+    groups: HashMap<(String, String), GroupState>,
+}
+impl GroupTracker {
+    pub fn new() -> GroupTracker {
+        GroupTracker::default()
+    }
+
+    /// Remember the state of a group, such as the result of get_group
+    pub fn insert(&mut self, account: &str, group: &v1::JsonGroupV2Info) {
+        self.groups.insert((account.to_string(), group.id.clone()), GroupState::from_v1(group));
+    }
+    /// Remember the state of a legacy group, such as one in the result of list_groups
+    pub fn insert_legacy(&mut self, account: &str, group: &GroupInfo) {
+        self.groups.insert((account.to_string(), group.group_id.clone()), GroupState::from_legacy(group));
+    }
+
+    /// The group update in a received message
+    /// Returns None when the message doesn't change a group
+    /// The first update of a group that isn't known yet only has a FirstSeen event
+    /// Insert the groups the account is already in to get the changes in their first update
+    pub fn update(&mut self, message: &MessageData) -> Option<GroupUpdate> {
+        let account = message.username.clone()?;
+        let data_message = message.data_message.as_ref()?;
+        let editor = message.sender();
+
+        let (group_id, revision, new) = match (&data_message.group_v2, &data_message.group_info) {
+            (Some(group), _) => (group.id.clone(), Some(group.revision), GroupState::from_v2(group)),
+            (None, Some(group)) if group.typ == "UPDATE" => (group.group_id.clone(), None, GroupState::from_legacy(group)),
+            (None, Some(group)) if group.typ == "QUIT" => {
+                let key = (account.clone(), group.group_id.clone());
+                let editor = editor?;
+                if let Some(members) = self.groups.get_mut(&key).and_then(|state| state.members.as_mut()) {
+                    members.retain(|member| *member != editor);
+                }
+                return Some(GroupUpdate {
+                    account,
+                    group_id: group.group_id.clone(),
+                    editor: Some(editor.clone()),
+                    revision: None,
+                    events: vec![GroupEvent::MemberLeft(editor)],
+                });
+            }
+            _ => return None,
+        };
+
+        let key = (account.clone(), group_id.clone());
+        let events = match self.groups.get_mut(&key) {
+            Some(state) => {
+                // Ordinary messages in a v2 group carry the revision it's already at
+                if revision.is_some() && state.revision >= revision {
+                    return None;
+                }
+                let events = state.events(&new);
+                state.merge(new);
+                events
+            }
+            None => {
+                self.groups.insert(key, new);
+                vec![GroupEvent::FirstSeen]
+            }
+        };
+
+        Some(GroupUpdate {
+            account,
+            group_id,
+            editor,
+            revision,
+            events,
+        })
+    }
+}
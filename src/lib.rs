@@ -1,4 +1,20 @@
+pub mod account;
+pub mod bot;
+#[cfg(feature = "bridge")]
+pub mod bridge;
+pub mod capabilities;
+pub mod conversation;
+pub mod filter;
+pub mod groups;
+pub mod queue;
 pub mod signald;
+pub mod signaladdress;
+pub mod signalderror;
 pub mod signaldresponse;
 pub mod signaldrequest;
 pub mod socket;
+#[cfg(feature = "store")]
+pub mod store;
+pub mod v0;
+pub mod v1;
+pub mod viewonce;
@@ -0,0 +1,373 @@
+use serde::{Serialize, Deserialize};
+use futures::channel::oneshot;
+use futures::future::FutureExt;
+use std::collections::VecDeque;
+use std::fs;
+use std::future::Future;
+use std::io::{self, Write, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use crate::signald::Signald;
+use crate::signaldrequest::Recipient;
+use crate::signaldresponse::{SignaldResponse, SendResult, RecipientStatus};
+use crate::signalderror::SignaldError;
+use crate::signaladdress::SignalAddress;
+use crate::v1::Protocol;
+
+/// How the queue retries and limits its sends
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueueConfig {
+    /// The number of times a message is tried before it fails
+    pub max_attempts: u32,
+    /// The wait after the first failed attempt, every next attempt waits twice as long
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// At most this many messages are sent in every rate limit period
+    pub rate_limit: u32,
+    pub rate_limit_period: Duration,
+}
+impl Default for QueueConfig {
+    fn default() -> Self {
+        QueueConfig {
+            max_attempts: 8,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(300),
+            rate_limit: 30,
+            rate_limit_period: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Where a queued message is in its delivery
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Waiting to be sent or retried
+    Pending,
+    /// Signald sent the message, the timestamp identifies it
+    Sent { timestamp: i64 },
+    /// Sending failed for good
+    Failed { error: String },
+}
+
+/// A message in the queue
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct QueuedMessage {
+    pub id: u64,
+    pub username: String,
+    pub recipient: Recipient,
+    pub message_body: String,
+    pub attempts: u32,
+    /// When the next attempt may be made, in milliseconds since the unix epoch
+    pub next_attempt: u64,
+    pub status: DeliveryStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// The group members a partly sent group message still has to reach, retries only go to them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remaining_members: Vec<SignalAddress>,
+    /// The timestamp of a partly sent group message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+}
+
+/// What to do after an attempt
+enum Outcome {
+    Sent(i64),
+    Retry(String),
+    /// Retry for the group members the message did not reach, with the timestamp of this attempt
+    RetryMembers(String, i64, Vec<SignalAddress>),
+    RateLimited(String),
+    Failed(String),
+}
+
+/// A queue of outgoing messages that are retried until signald confirms sending them
+/// A queue opened at a path keeps its messages in that file, so pending messages survive a restart
+/// The next id is kept in a file next to it with an ".id" extension, ids are never reused
+pub struct OutboundQueue {
+    path: Option<PathBuf>,
+    config: QueueConfig,
+    messages: Vec<QueuedMessage>,
+    next_id: u64,
+    // When the last messages were sent, for the rate limit
+    sent_at: VecDeque<Instant>,
+    // No messages are sent before this time after signald reported a rate limit
+    paused_until: u64,
+}
+impl OutboundQueue {
+    /// Create a queue that only lives in memory
+    pub fn new(config: QueueConfig) -> OutboundQueue {
+        OutboundQueue {
+            path: None,
+            config,
+            messages: Vec::new(),
+            next_id: 0,
+            sent_at: VecDeque::new(),
+            paused_until: 0,
+        }
+    }
+    /// Open a queue kept in a file, the messages already in the file are loaded
+    pub fn open<P: AsRef<Path>>(path: P, config: QueueConfig) -> io::Result<OutboundQueue> {
+        let mut queue = OutboundQueue::new(config);
+        queue.path = Some(path.as_ref().to_path_buf());
+
+        if path.as_ref().exists() {
+            let reader = BufReader::new(fs::File::open(&path)?);
+            for line in reader.lines() {
+                let message = serde_json::from_str(&line?)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                queue.messages.push(message);
+            }
+        }
+
+        // Queues written before the id file existed continue after their highest id
+        let next_id = match fs::read_to_string(id_path(path.as_ref())) {
+            Ok(id) => id.trim().parse().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(e),
+        };
+        queue.next_id = queue.messages.iter().map(|m| m.id + 1).fold(next_id, u64::max);
+        Ok(queue)
+    }
+
+    /// Add a message to the queue
+    /// Returns the id to look up its status with
+    pub fn push(&mut self, username: String, recipient: Recipient, message_body: String) -> io::Result<u64> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.messages.push(QueuedMessage {
+            id,
+            username,
+            recipient,
+            message_body,
+            attempts: 0,
+            next_attempt: 0,
+            status: DeliveryStatus::Pending,
+            last_error: None,
+            remaining_members: Vec::new(),
+            timestamp: None,
+        });
+        self.save()?;
+        Ok(id)
+    }
+    /// The delivery status of a message
+    pub fn status(&self, id: u64) -> Option<DeliveryStatus> {
+        self.get(id).map(|m| m.status.clone())
+    }
+    /// A message in the queue
+    pub fn get(&self, id: u64) -> Option<&QueuedMessage> {
+        self.messages.iter().find(|m| m.id == id)
+    }
+    /// The number of messages that still have to be sent
+    pub fn pending(&self) -> usize {
+        self.messages.iter().filter(|m| m.status == DeliveryStatus::Pending).count()
+    }
+    /// Remove the sent and failed messages
+    pub fn clear_finished(&mut self) -> io::Result<()> {
+        self.messages.retain(|m| m.status == DeliveryStatus::Pending);
+        self.save()
+    }
+
+    /// Try to send every pending message that is due, within the rate limit
+    /// Returns the ids and statuses of the messages that were tried
+    pub async fn process(&mut self, signald: &mut Signald) -> io::Result<Vec<(u64, DeliveryStatus)>> {
+        let mut tried = Vec::new();
+
+        for index in 0..self.messages.len() {
+            let now = now_millis();
+            let message = self.messages[index].clone();
+            if message.status != DeliveryStatus::Pending || message.next_attempt > now {
+                continue;
+            }
+            if now < self.paused_until || !self.take_rate_limit() {
+                break;
+            }
+
+            // Only v1 signald can send a group message to some of its members
+            let partial = signald.protocol() == Protocol::V1 && matches!(message.recipient, Recipient::Group(_));
+            let result = match (&message.recipient, message.timestamp) {
+                (Recipient::Group(group_id), Some(timestamp)) if partial && !message.remaining_members.is_empty() => {
+                    signald.redeliver(message.username.clone(), group_id.clone(), message.remaining_members.clone(), timestamp,
+                                      Some(message.message_body.clone())).await
+                }
+                _ => signald.deliver(message.username.clone(), message.recipient.clone(), Some(message.message_body.clone())).await,
+            };
+            let status = self.apply_outcome(index, outcome(result, partial));
+            tried.push((message.id, status));
+            self.save()?;
+        }
+
+        Ok(tried)
+    }
+
+    /// Process the queue until no messages are pending or the timeout passes
+    /// Returns true when every message was sent or failed
+    pub async fn flush(&mut self, signald: &mut Signald, timeout: Duration) -> io::Result<bool> {
+        let end = Instant::now() + timeout;
+        loop {
+            self.process(signald).await?;
+            if self.pending() == 0 {
+                return Ok(true);
+            }
+            if Instant::now() >= end {
+                return Ok(false);
+            }
+            delay(Duration::from_millis(10).max(self.until_next_attempt()).min(end.saturating_duration_since(Instant::now()))).await;
+        }
+    }
+
+    /// The time until the next message may be tried
+    fn until_next_attempt(&self) -> Duration {
+        let now = now_millis();
+        let next = self.messages.iter()
+            .filter(|m| m.status == DeliveryStatus::Pending)
+            .map(|m| m.next_attempt.max(self.paused_until))
+            .min()
+            .unwrap_or(now);
+        let rate_limit = match (self.sent_at.len() as u32 >= self.config.rate_limit, self.sent_at.front()) {
+            (true, Some(first)) => (*first + self.config.rate_limit_period).saturating_duration_since(Instant::now()),
+            _ => Duration::from_millis(0),
+        };
+        Duration::from_millis(next.saturating_sub(now)).max(rate_limit)
+    }
+
+    /// Count a send against the rate limit, returns false when the limit is reached
+    fn take_rate_limit(&mut self) -> bool {
+        let now = Instant::now();
+        while self.sent_at.front().map(|t| now.duration_since(*t) >= self.config.rate_limit_period).unwrap_or(false) {
+            self.sent_at.pop_front();
+        }
+        if self.sent_at.len() as u32 >= self.config.rate_limit {
+            return false;
+        }
+        self.sent_at.push_back(now);
+        true
+    }
+
+    fn apply_outcome(&mut self, index: usize, outcome: Outcome) -> DeliveryStatus {
+        let now = now_millis();
+        let config = self.config.clone();
+        let message = &mut self.messages[index];
+        message.attempts += 1;
+
+        let backoff = config.initial_backoff.checked_mul(2u32.saturating_pow(message.attempts - 1))
+            .unwrap_or(config.max_backoff)
+            .min(config.max_backoff);
+        let retry = |message: &mut QueuedMessage, error: String| {
+            if message.attempts >= config.max_attempts {
+                message.status = DeliveryStatus::Failed { error: error.clone() };
+            } else {
+                message.next_attempt = now + backoff.as_millis() as u64;
+            }
+            message.last_error = Some(error);
+        };
+
+        match outcome {
+            Outcome::Sent(timestamp) => {
+                message.status = DeliveryStatus::Sent { timestamp: message.timestamp.unwrap_or(timestamp) };
+                message.remaining_members.clear();
+            }
+            Outcome::Retry(error) => retry(message, error),
+            Outcome::RetryMembers(error, timestamp, members) => {
+                // Later attempts reuse the first timestamp, it identifies the message once every member has it
+                message.timestamp.get_or_insert(timestamp);
+                message.remaining_members = members;
+                retry(message, error);
+            }
+            Outcome::RateLimited(error) => {
+                retry(message, error);
+                self.paused_until = now + backoff.as_millis() as u64;
+            }
+            Outcome::Failed(error) => {
+                message.status = DeliveryStatus::Failed { error: error.clone() };
+                message.last_error = Some(error);
+            }
+        }
+        self.messages[index].status.clone()
+    }
+
+    /// Write the queue to its file
+    /// The file is replaced at once so a crash never leaves half a queue behind
+    fn save(&self) -> io::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        // The id is written first, a crash in between skips an id instead of reusing one
+        let temp_path = PathBuf::from(format!("{}.tmp", id_path(path).display()));
+        fs::write(&temp_path, self.next_id.to_string())?;
+        fs::rename(temp_path, id_path(path))?;
+
+        let temp_path = PathBuf::from(format!("{}.tmp", path.display()));
+        let mut file = fs::File::create(&temp_path)?;
+        for message in &self.messages {
+            writeln!(file, "{}", serde_json::to_string(message).unwrap())?;
+        }
+        file.sync_all()?;
+        fs::rename(temp_path, path)
+    }
+}
+
+/// Decide what to do with a message after signald's response
+/// With partial set, network failures are retried for the failed group members only
+fn outcome(result: Result<SignaldResponse, SignaldError>, partial: bool) -> Outcome {
+    let response = match result {
+        Ok(response) => response,
+        Err(error) => return match &error {
+            SignaldError::Other(typ, _) if typ == "RateLimitError" => Outcome::RateLimited(error.to_string()),
+            SignaldError::Timeout | SignaldError::Io(_) | SignaldError::UnexpectedError(_) | SignaldError::Other(..) => {
+                Outcome::Retry(error.to_string())
+            }
+            _ => Outcome::Failed(error.to_string()),
+        },
+    };
+
+    // Anything but a send result doesn't tell if the message was sent, so it's tried again
+    let send_result = match SendResult::from_response(&response) {
+        Ok(send_result) => send_result,
+        Err(error) => return Outcome::Retry(error.to_string()),
+    };
+
+    let mut failed = Vec::new();
+    for result in send_result.failures() {
+        match &result.status {
+            RecipientStatus::UnregisteredFailure => return Outcome::Failed(format!("{} is not registered", result.recipient())),
+            RecipientStatus::IdentityFailure(_) => return Outcome::Failed(format!("the identity of {} changed", result.recipient())),
+            _ => failed.push(result),
+        }
+    }
+
+    match failed.first() {
+        None => Outcome::Sent(send_result.timestamp),
+        Some(result) => {
+            let error = format!("network failure sending to {}", result.recipient());
+            // Members can only be retried when signald said who they are
+            let members: Option<Vec<SignalAddress>> = failed.iter().map(|r| r.address.clone()).collect();
+            match (partial, members) {
+                (true, Some(members)) => Outcome::RetryMembers(error, send_result.timestamp, members),
+                _ => Outcome::Retry(error),
+            }
+        }
+    }
+}
+
+/// The file the next id of a queue file is kept in
+fn id_path(path: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.id", path.display()))
+}
+
+/// A future that completes after the duration, without blocking the executor it runs on
+fn delay(duration: Duration) -> impl Future<Output = ()> {
+    let (tx, rx) = oneshot::channel();
+    thread::spawn(move || {
+        thread::sleep(duration);
+        let _ = tx.send(());
+    });
+    rx.map(|_| ())
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64
+}
@@ -0,0 +1,294 @@
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::Error;
+use serde::ser::SerializeMap;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::str::FromStr;
+
+/// The address of a Signal user
+/// Contains an E.164 phone number, a UUID or both
+/// Two addresses are equal when either their numbers or their UUIDs match
+/// Addresses read from signald are kept as they were sent, only the constructors validate
+#[derive(Clone, Debug)]
+pub struct SignalAddress {
+    number: Option<String>,
+    uuid: Option<String>,
+    // The wire format this address was read from, used to write it back identically
+    format: AddressFormat,
+    // The other keys of the object format, such as "relay"
+    extra: Map<String, Value>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum AddressFormat {
+    // {"number": "+32...", "uuid": "..."}
+    #[default]
+    Object,
+    // "+32..." or "..."
+    Plain,
+}
+
+/// An error returned when a phone number or UUID is malformed
+#[derive(Clone, Debug, PartialEq)]
+pub enum AddressError {
+    /// The number is not a valid E.164 number
+    InvalidNumber(String),
+    /// The string is not a valid UUID
+    InvalidUuid(String),
+    /// Neither a number nor a UUID was given
+    Empty,
+}
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressError::InvalidNumber(n) => write!(f, "invalid E.164 number: {}", n),
+            AddressError::InvalidUuid(u) => write!(f, "invalid uuid: {}", u),
+            AddressError::Empty => write!(f, "an address needs a number or a uuid"),
+        }
+    }
+}
+impl std::error::Error for AddressError {}
+
+impl SignalAddress {
+    /// Create an address from an optional number and an optional uuid
+    /// The number is normalised to E.164, the uuid to lowercase
+    pub fn new(number: Option<&str>, uuid: Option<&str>) -> Result<SignalAddress, AddressError> {
+        if number.is_none() && uuid.is_none() {
+            return Err(AddressError::Empty);
+        }
+
+        Ok(SignalAddress {
+            number: number.map(normalise_number).transpose()?,
+            uuid: uuid.map(normalise_uuid).transpose()?,
+            format: AddressFormat::Object,
+            extra: Map::new(),
+        })
+    }
+    /// Create an address from a phone number
+    /// Accepts common notations such as "+32 470 12 34 56" or "0032470123456"
+    pub fn from_number(number: &str) -> Result<SignalAddress, AddressError> {
+        SignalAddress::new(Some(number), None)
+    }
+    /// Create an address from a uuid
+    pub fn from_uuid(uuid: &str) -> Result<SignalAddress, AddressError> {
+        SignalAddress::new(None, Some(uuid))
+    }
+
+    /// The E.164 number of this address
+    pub fn number(&self) -> Option<&str> {
+        self.number.as_deref()
+    }
+    /// The uuid of this address
+    pub fn uuid(&self) -> Option<&str> {
+        self.uuid.as_deref()
+    }
+
+    /// Fill in the identifiers this address is missing from another address
+    pub fn merge(&mut self, other: &SignalAddress) {
+        if self.number.is_none() {
+            self.number = other.number.clone();
+        }
+        if self.uuid.is_none() {
+            self.uuid = other.uuid.clone();
+        }
+    }
+}
+
+/// Normalise a phone number to E.164: a '+' followed by 8 to 15 digits
+fn normalise_number(number: &str) -> Result<String, AddressError> {
+    let stripped: String = number.chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, '-' | '.' | '(' | ')' | '/'))
+        .collect();
+
+    let digits = if let Some(d) = stripped.strip_prefix('+') {
+        d
+    } else if let Some(d) = stripped.strip_prefix("00") {
+        d
+    } else {
+        return Err(AddressError::InvalidNumber(number.to_string()));
+    };
+
+    let valid = (8..=15).contains(&digits.len())
+        && digits.chars().all(|c| c.is_ascii_digit())
+        && !digits.starts_with('0');
+    if !valid {
+        return Err(AddressError::InvalidNumber(number.to_string()));
+    }
+
+    Ok(format!("+{}", digits))
+}
+
+/// Normalise a uuid to its lowercase hyphenated form
+fn normalise_uuid(uuid: &str) -> Result<String, AddressError> {
+    let groups: Vec<&str> = uuid.split('-').collect();
+    let lengths: Vec<usize> = groups.iter().map(|g| g.len()).collect();
+    let valid = lengths == [8, 4, 4, 4, 12]
+        && groups.iter().all(|g| g.chars().all(|c| c.is_ascii_hexdigit()));
+    if !valid {
+        return Err(AddressError::InvalidUuid(uuid.to_string()));
+    }
+
+    Ok(uuid.to_lowercase())
+}
+
+impl FromStr for SignalAddress {
+    type Err = AddressError;
+
+    /// Parse either a phone number or a uuid
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match normalise_uuid(s) {
+            Ok(uuid) => SignalAddress::from_uuid(&uuid),
+            Err(_) => SignalAddress::from_number(s),
+        }
+    }
+}
+
+impl fmt::Display for SignalAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.number, &self.uuid) {
+            (Some(number), _) => write!(f, "{}", number),
+            (None, Some(uuid)) => write!(f, "{}", uuid),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+impl PartialEq for SignalAddress {
+    fn eq(&self, other: &Self) -> bool {
+        let number_match = matches!((&self.number, &other.number), (Some(a), Some(b)) if a == b);
+        let uuid_match = matches!((&self.uuid, &other.uuid), (Some(a), Some(b)) if a.eq_ignore_ascii_case(b));
+        number_match || uuid_match
+    }
+}
+
+impl PartialEq<str> for SignalAddress {
+    fn eq(&self, other: &str) -> bool {
+        match other.parse::<SignalAddress>() {
+            Ok(address) => *self == address,
+            Err(_) => false,
+        }
+    }
+}
+
+impl PartialEq<&str> for SignalAddress {
+    fn eq(&self, other: &&str) -> bool {
+        *self == **other
+    }
+}
+
+impl Serialize for SignalAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match (self.format, &self.number, &self.uuid) {
+            (AddressFormat::Plain, Some(number), None) => serializer.serialize_str(number),
+            (AddressFormat::Plain, None, Some(uuid)) => serializer.serialize_str(uuid),
+            _ => {
+                let mut map = serializer.serialize_map(None)?;
+                if let Some(number) = &self.number {
+                    map.serialize_entry("number", number)?;
+                }
+                if let Some(uuid) = &self.uuid {
+                    map.serialize_entry("uuid", uuid)?;
+                }
+                for (key, value) in &self.extra {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SignalAddress {
+    /// Signald sends addresses either as a plain number/uuid string or as a
+    /// {"number", "uuid"} object, both are accepted
+    /// The number and uuid are taken as they are, signald may send numbers that aren't E.164
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::String(s) => {
+                let is_uuid = normalise_uuid(&s).is_ok();
+                Ok(SignalAddress {
+                    number: if is_uuid { None } else { Some(s.clone()) },
+                    uuid: if is_uuid { Some(s) } else { None },
+                    format: AddressFormat::Plain,
+                    extra: Map::new(),
+                })
+            }
+            Value::Object(mut extra) => {
+                let mut take = |key: &str| match extra.remove(key) {
+                    Some(Value::String(s)) => Some(s),
+                    Some(other) => {
+                        extra.insert(key.to_string(), other);
+                        None
+                    }
+                    None => None,
+                };
+                let number = take("number");
+                let uuid = take("uuid");
+                Ok(SignalAddress {
+                    number,
+                    uuid,
+                    format: AddressFormat::Object,
+                    extra,
+                })
+            }
+            other => Err(D::Error::custom(format!("expected an address, got {}", other))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalise_number() {
+        let address = SignalAddress::from_number("+32 470 12-34-56").unwrap();
+        assert_eq!(address.number(), Some("+32470123456"));
+
+        let address = SignalAddress::from_number("0032470123456").unwrap();
+        assert_eq!(address.number(), Some("+32470123456"));
+    }
+
+    #[test]
+    fn test_invalid_number() {
+        assert!(SignalAddress::from_number("0470123456").is_err());
+        assert!(SignalAddress::from_number("+32abc").is_err());
+        assert!(SignalAddress::from_number("+1234567890123456").is_err());
+    }
+
+    #[test]
+    fn test_equality_on_either_identifier() {
+        let uuid = "8f2c4b4e-1d3a-4c8e-9a71-0123456789ab";
+        let both = SignalAddress::new(Some("+32470123456"), Some(uuid)).unwrap();
+
+        assert_eq!(both, SignalAddress::from_number("+32470123456").unwrap());
+        assert_eq!(both, SignalAddress::from_uuid(&uuid.to_uppercase()).unwrap());
+        assert_ne!(both, SignalAddress::from_number("+32470000000").unwrap());
+        assert_eq!(both, "+32470123456");
+    }
+
+    #[test]
+    fn test_serde_keeps_wire_format() {
+        let plain = serde_json::json!("+32470123456");
+        let address: SignalAddress = serde_json::from_value(plain.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&address).unwrap(), plain);
+
+        let object = serde_json::json!({"number": "+32470123456"});
+        let address: SignalAddress = serde_json::from_value(object.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&address).unwrap(), object);
+    }
+
+    #[test]
+    fn test_serde_keeps_unchecked_addresses() {
+        // Short codes and numbers without a country code are kept as signald sent them
+        let plain = serde_json::json!("12345");
+        let address: SignalAddress = serde_json::from_value(plain.clone()).unwrap();
+        assert_eq!(address.number(), Some("12345"));
+        assert_eq!(serde_json::to_value(&address).unwrap(), plain);
+
+        let object = serde_json::json!({"number": "0470123456", "uuid": "8F2C4B4E-1D3A-4C8E-9A71-0123456789AB", "relay": "textsecure"});
+        let address: SignalAddress = serde_json::from_value(object.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&address).unwrap(), object);
+        assert_eq!(address, SignalAddress::from_uuid("8f2c4b4e-1d3a-4c8e-9a71-0123456789ab").unwrap());
+    }
+}
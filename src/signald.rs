@@ -1,243 +1,592 @@
-use crate::signaldrequest::SignaldRequestBuilder;
-use crate::signaldrequest::SignaldRequest;
+use crate::signaldrequest::{SignaldRequest, Request, Recipient, AccountRequest, SendRequest, LinkRequest,
+                            RegisterRequest, VerifyRequest, UpdateGroupRequest, LeaveGroupRequest, OutgoingAttachment};
 use std::time::{Instant, Duration};
 use bus::{BusReader};
-use std::sync::mpsc::RecvTimeoutError::Timeout;
-use std::sync::mpsc::RecvTimeoutError;
-use crate::signaldresponse::{SignaldResponse, ResponseType};
-use crate::signald::FilterType::{Id, Type};
+use crate::signaldresponse::{SignaldResponse, ResponseType, ParseMode, MessageData, SendResult};
+use crate::filter::Filter;
+use crate::filter::Filter::{Id, Type};
 use crate::signaldresponse::ResponseType::BusUpdate;
 use crate::socket::Socket;
+use crate::socket::state::{AccountHealth, BlockList};
+use crate::account::Account;
+use crate::conversation::Conversation;
 use crate::socket::signaldsocket::SignaldSocket;
+use crate::signaladdress::SignalAddress;
+use crate::signalderror::SignaldError;
+use crate::signaldresponse::ErrorData;
+use crate::v1;
+use crate::v1::Protocol;
+use crate::capabilities::{Capabilities, Feature};
+use crate::groups::GroupRole;
+use serde::de::DeserializeOwned;
 
-pub static SOCKET_PATH: &'static str = "/var/run/signald/signald.sock";
-
-pub enum FilterType {
-    Id(String),
-    Type(ResponseType)
-}
+pub static SOCKET_PATH: &str = "/var/run/signald/signald.sock";
 
 pub struct Signald {
     // The signald socket
-    // socket: Box<dyn Socket + Send + Sync>,
-    socket: SignaldSocket,
+    socket: Box<dyn Socket + Send>,
+    // A receiver made before a request was sent, so its response can't be missed
+    response_rx: Option<BusReader<SignaldResponse>>,
     // A count of all the sent messages on this socket
     message_count: u32,
+    // The protocol used for requests that exist in both versions
+    protocol: Protocol,
+    // The features of the connected signald version
+    capabilities: Capabilities,
 }
 impl Signald {
 
     /// Connect the default Signald socket
     pub fn connect() -> Signald {
-        Signald::connect_path(&SOCKET_PATH)
+        Signald::connect_path(SOCKET_PATH)
     }
     /// Connect to a custom Signald socket
     pub fn connect_path(socket_path: &str) -> Self {
-        let socket: SignaldSocket = SignaldSocket::connect(socket_path.to_string(), 100);
+        Signald::connect_path_with_mode(socket_path, ParseMode::default())
+    }
+    /// Connect to a custom Signald socket and parse responses with the given parse mode
+    pub fn connect_path_with_mode(socket_path: &str, parse_mode: ParseMode) -> Self {
+        let socket = SignaldSocket::connect_with_mode(socket_path.to_string(), 100, parse_mode);
+        Signald::with_socket(Box::new(socket))
+    }
+    /// Use any socket, such as a recording or replay socket
+    pub fn with_socket(mut socket: Box<dyn Socket + Send>) -> Self {
+        // Wait for the version signald sends on connect, without it no version dependent feature is used
+        // The socket keeps the version in its state, every message on the bus is a moment to check it
+        if socket.state().version.is_none() {
+            let mut rx = socket.get_rx();
+            let end = Instant::now() + Duration::from_millis(1000);
+            while socket.state().version.is_none() {
+                let remaining = end.saturating_duration_since(Instant::now());
+                if remaining == Duration::from_millis(0) || rx.recv_timeout(remaining).is_err() {
+                    break;
+                }
+            }
+        }
+        let capabilities = match socket.state().version {
+            Some(version) => Capabilities::from_version(&version),
+            None => Capabilities::default(),
+        };
 
         Self {
-            // socket: Box::new(socket),
-            socket: socket,
+            socket,
+            response_rx: None,
             message_count: 0,
+            protocol: Protocol::default(),
+            capabilities,
+        }
+    }
+    /// The features of the connected signald version
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+    /// Choose the protocol used for requests that exist in both protocol versions
+    /// Returns an Unsupported error when choosing v1 on a signald version without it
+    pub fn set_protocol(&mut self, protocol: Protocol) -> Result<(), SignaldError> {
+        if protocol == Protocol::V1 {
+            self.capabilities.require(Feature::ProtocolV1)?;
         }
+        self.protocol = protocol;
+        Ok(())
+    }
+    /// The protocol used for requests that exist in both protocol versions
+    pub fn protocol(&self) -> Protocol {
+        self.protocol
     }
     /// Send a signald request on the socket
-    pub fn send_request(&mut self, request: &SignaldRequest) {
+    /// Returns an Io error when writing to the socket failed
+    pub fn send_request(&mut self, request: &SignaldRequest) -> Result<(), SignaldError> {
         self.message_count += 1;
-        self.socket.send_request(&request);
+        self.socket.send_request(request)?;
+        Ok(())
+    }
+
+    /// Send a typed request with a new id
+    /// Returns the id the response will carry
+    fn send_request_with_id(&mut self, request: Request) -> Result<String, SignaldError> {
+        let id = self.message_count.to_string();
+        // Only keep the receiver once the request is sent, an unread receiver blocks the socket when its buffer is full
+        let rx = self.socket.get_rx();
+        self.send_request(&SignaldRequest::with_id(request, id.clone()))?;
+        self.response_rx = Some(rx);
+        Ok(id)
     }
 
     // Signald messages
     // Todo: add attachments, etc
-    /// Send a message to the socket
-    pub async fn send(&mut self, username: String, recipient_number: String, message_body: Option<String>) {
-        let mut request_builder = SignaldRequestBuilder::new();
-        request_builder.set_type("send".to_string());
-        request_builder.set_username(username);
-        request_builder.set_recipient_number(recipient_number);
-        if let Some(i) = message_body {
-            request_builder.set_message_body(i);
+    /// Send a message and wait for the result of sending it to every recipient
+    pub async fn send(&mut self, username: String, recipient: SignalAddress, message_body: Option<String>) -> Result<SendResult, SignaldError> {
+        self.send_message(username, Recipient::Address(recipient), message_body, Vec::new()).await
+    }
+    /// Send a message to a group and wait for the result of sending it to every member
+    pub async fn send_group(&mut self, username: String, group_id: String, message_body: Option<String>) -> Result<SendResult, SignaldError> {
+        self.send_message(username, Recipient::Group(group_id), message_body, Vec::new()).await
+    }
+    /// Send a message with attachments and wait for the result of sending it
+    /// The attachments are files on the signald host
+    pub async fn send_message(&mut self, username: String, recipient: Recipient, message_body: Option<String>,
+                              attachments: Vec<OutgoingAttachment>) -> Result<SendResult, SignaldError> {
+        let request = self.send_message_request(username, recipient, message_body, attachments);
+        let id = self.send_request_with_id(request)?;
+        let response = self.wait_for_request(Id(id)).await?;
+        SendResult::from_response(&response)
+    }
+    /// Replace the text of a message the account sent earlier
+    /// The original message is identified by the timestamp of its SendResult
+    pub async fn edit_message(&mut self, username: String, recipient: Recipient, timestamp: i64,
+                              message_body: String) -> Result<SendResult, SignaldError> {
+        let mut request = Signald::v1_send_request(username, recipient, Some(message_body), Vec::new());
+        request.edit_timestamp = Some(timestamp);
+        self.capabilities.require(Feature::EditMessages)?;
+        let response: v1::SendResponse = self.request_v1(v1::Request::Send(request)).await?;
+        Ok(SendResult::from(response))
+    }
+    /// Send attachments the recipients can only view once
+    /// The attachments are files on the signald host
+    pub async fn send_view_once(&mut self, username: String, recipient: Recipient,
+                                attachments: Vec<OutgoingAttachment>) -> Result<SendResult, SignaldError> {
+        let mut request = Signald::v1_send_request(username, recipient, None, attachments);
+        request.view_once = Some(true);
+        let response: v1::SendResponse = self.request_v1(v1::Request::Send(request)).await?;
+        Ok(SendResult::from(response))
+    }
+    /// Send a message and wait for signald to report the result of sending it
+    pub async fn deliver(&mut self, username: String, recipient: Recipient, message_body: Option<String>) -> Result<SignaldResponse, SignaldError> {
+        let request = self.send_message_request(username, recipient, message_body, Vec::new());
+        let id = self.send_request_with_id(request)?;
+        self.wait_for_request(Id(id)).await
+    }
+    /// Send a group message again to some of its members and wait for the result
+    /// The timestamp of the first attempt is reused so the members receive the same message
+    pub async fn redeliver(&mut self, username: String, group_id: String, members: Vec<SignalAddress>, timestamp: i64,
+                           message_body: Option<String>) -> Result<SignaldResponse, SignaldError> {
+        let mut request = Signald::v1_send_request(username, Recipient::Group(group_id), message_body, Vec::new());
+        request.members = Some(members);
+        request.timestamp = Some(timestamp);
+        let id = self.send_request_with_id(Request::V1(v1::Request::Send(request)))?;
+        self.wait_for_request(Id(id)).await
+    }
+    fn send_message_request(&self, username: String, recipient: Recipient, message_body: Option<String>,
+                            attachments: Vec<OutgoingAttachment>) -> Request {
+        let attachments = match attachments.is_empty() {
+            true => None,
+            false => Some(attachments),
+        };
+        match self.protocol {
+            Protocol::V0 => {
+                let mut send_request = SendRequest::new(username, recipient);
+                send_request.message_body = message_body;
+                send_request.attachments = attachments;
+                Request::Send(send_request)
+            }
+            Protocol::V1 => {
+                let send_request = Signald::v1_send_request(username, recipient, message_body, attachments.unwrap_or_default());
+                Request::V1(v1::Request::Send(send_request))
+            }
         }
-
-        let request = request_builder.build();
-        self.send_request(&request);
+    }
+    fn v1_send_request(username: String, recipient: Recipient, message_body: Option<String>,
+                       attachments: Vec<OutgoingAttachment>) -> v1::SendRequest {
+        let attachments: Vec<v1::JsonAttachment> = attachments.into_iter()
+            .map(|a| v1::JsonAttachment {
+                filename: a.filename,
+                caption: a.caption,
+                voice_note: a.voice_note,
+                ..Default::default()
+            })
+            .collect();
+        let mut send_request = v1::SendRequest {
+            username,
+            message_body,
+            attachments: if attachments.is_empty() { None } else { Some(attachments) },
+            ..Default::default()
+        };
+        match recipient {
+            Recipient::Address(address) => send_request.recipient_address = Some(address),
+            Recipient::Group(group_id) => send_request.recipient_group_id = Some(group_id),
+        }
+        send_request
     }
 
     /// Enable receiving user events such as received messages
-    pub async fn subscribe(&mut self, username: String) -> Result<SignaldResponse, RecvTimeoutError> {
-        let id = self.message_count.to_string();
-
-        let mut request_builder = SignaldRequestBuilder::new();
-        request_builder.set_type("subscribe".to_string());
-        request_builder.set_username(username);
-        request_builder.set_id(id.clone());
-        let request = request_builder.build();
-
-        self.send_request(&request);
+    pub async fn subscribe(&mut self, username: String) -> Result<SignaldResponse, SignaldError> {
+        let id = self.send_request_with_id(Request::Subscribe(AccountRequest::new(username)))?;
         self.wait_for_request(Id(id)).await
     }
     /// Disable receiving user events such as received messages
-    pub async fn unsubscribe(&mut self, username: String) -> Result<SignaldResponse, RecvTimeoutError> {
-        let id = self.message_count.to_string();
-
-        let mut request_builder = SignaldRequestBuilder::new();
-        request_builder.set_type("unsubscribe".to_string());
-        request_builder.set_username(username);
-        request_builder.set_id(id.clone());
-        let request = request_builder.build();
-
-        self.send_request(&request);
+    pub async fn unsubscribe(&mut self, username: String) -> Result<SignaldResponse, SignaldError> {
+        let id = self.send_request_with_id(Request::Unsubscribe(AccountRequest::new(username)))?;
         self.wait_for_request(Id(id)).await
     }
     /// Link an existing signal account
-    pub async fn link(&mut self) -> Result<SignaldResponse, RecvTimeoutError> {
-        let id = self.message_count.to_string();
-
-        let mut request_builder = SignaldRequestBuilder::new();
-        request_builder.set_type("link".to_string());
-        request_builder.set_id(id.clone());
-        let request = request_builder.build();
-
-        self.send_request(&request);
+    pub async fn link(&mut self) -> Result<SignaldResponse, SignaldError> {
+        self.link_device(None).await
+    }
+    /// Link an existing signal account as a device with the given name
+    /// Returns the linking uri, signald answers the same request id again once linking finished
+    pub async fn link_device(&mut self, device_name: Option<String>) -> Result<SignaldResponse, SignaldError> {
+        let id = self.send_request_with_id(Request::Link(LinkRequest { device_name, ..Default::default() }))?;
+        self.wait_for_request(Id(id)).await
+    }
+    /// Register a new signal account
+    /// Signald will send a verification code by sms, or by voice call when voice is set
+    pub async fn register(&mut self, username: String, voice: bool, captcha: Option<String>) -> Result<SignaldResponse, SignaldError> {
+        let request = RegisterRequest {
+            username,
+            voice: Some(voice),
+            captcha,
+            ..Default::default()
+        };
+        let id = self.send_request_with_id(Request::Register(request))?;
+        self.wait_for_request(Id(id)).await
+    }
+    /// Verify a registered account with the received verification code
+    pub async fn verify(&mut self, username: String, code: String) -> Result<SignaldResponse, SignaldError> {
+        let id = self.send_request_with_id(Request::Verify(VerifyRequest { username, code, ..Default::default() }))?;
         self.wait_for_request(Id(id)).await
     }
     /// Get the current signald version
-    pub async fn version(&mut self) -> Result<SignaldResponse, RecvTimeoutError> {
-        let id = self.message_count.to_string();
-
-        let mut request_builder = SignaldRequestBuilder::new();
-        request_builder.set_type("version".to_string());
-        request_builder.set_id(id.clone());
-        let request = request_builder.build();
-
-        self.send_request(&request);
-        self.wait_for_request(Type(ResponseType::Version(None))).await
+    /// This also updates the capabilities
+    pub async fn version(&mut self) -> Result<SignaldResponse, SignaldError> {
+        self.send_request_with_id(Request::Version)?;
+        let response = self.wait_for_request(Type(ResponseType::Version(None))).await?;
+        if let ResponseType::Version(Some(version)) = &response.data {
+            self.capabilities = Capabilities::from_version(version);
+        }
+        Ok(response)
+    }
+    /// Query all the accounts signald manages
+    pub async fn list_accounts(&mut self) -> Result<SignaldResponse, SignaldError> {
+        let id = self.send_request_with_id(Request::ListAccounts)?;
+        self.wait_for_request(Id(id)).await
+    }
+    /// Get a handle to one of the accounts signald manages
+    pub fn account(&mut self, username: &str) -> Account<'_> {
+        Account::new(self, username.to_string())
     }
     /// Query all the user's contacts
-    pub async fn list_contacts(&mut self, username: String) -> Result<SignaldResponse, RecvTimeoutError> {
-        let id = self.message_count.to_string();
-
-        let mut request_builder = SignaldRequestBuilder::new();
-        request_builder.set_type("list_contacts".to_string());
-        request_builder.set_username(username);
-        request_builder.set_id(id.clone());
-        let request = request_builder.build();
-
-        self.send_request(&request);
+    pub async fn list_contacts(&mut self, username: String) -> Result<SignaldResponse, SignaldError> {
+        let id = self.send_request_with_id(Request::ListContacts(AccountRequest::new(username)))?;
         self.wait_for_request(Id(id)).await
     }
-    /// Send a contact sync request to the other devices on this account
-    pub fn sync_contacts(&mut self, username: String) {
-        let mut request_builder = SignaldRequestBuilder::new();
-        request_builder.set_type("sync_contacts".to_string());
-        request_builder.set_username(username);
-        request_builder.set_id(self.message_count.to_string());
-        let request = request_builder.build();
+    /// Query all the groups the user is a member of
+    pub async fn list_groups(&mut self, username: String) -> Result<SignaldResponse, SignaldError> {
+        let id = self.send_request_with_id(Request::ListGroups(AccountRequest::new(username)))?;
+        self.wait_for_request(Id(id)).await
+    }
+    /// Create a group or update an existing group
+    pub async fn update_group(&mut self, request: UpdateGroupRequest) -> Result<SignaldResponse, SignaldError> {
+        let id = self.send_request_with_id(Request::UpdateGroup(request))?;
+        self.wait_for_request(Id(id)).await
+    }
+    /// Leave a group
+    pub async fn leave_group(&mut self, username: String, group_id: String) -> Result<SignaldResponse, SignaldError> {
+        let request = LeaveGroupRequest {
+            username,
+            recipient_group_id: group_id,
+            ..Default::default()
+        };
+        let id = self.send_request_with_id(Request::LeaveGroup(request))?;
+        self.wait_for_request(Id(id)).await
+    }
+    // Protocol v1 requests
+    /// Send a v1 request and parse the data of its response
+    async fn request_v1<T: DeserializeOwned>(&mut self, request: v1::Request) -> Result<T, SignaldError> {
+        self.capabilities.require(Feature::ProtocolV1)?;
+        let id = self.send_request_with_id(Request::V1(request))?;
+        let response = self.wait_for_request(Id(id)).await?;
+        match response.data {
+            ResponseType::V1(response) => response.parse(),
+            other => Err(SignaldError::Other(other.type_name().to_string(), ErrorData {
+                message: "expected a v1 response".to_string(),
+                ..Default::default()
+            })),
+        }
+    }
+    /// React to a message with an emoji
+    pub async fn react(&mut self, username: String, recipient: Recipient, reaction: v1::JsonReaction) -> Result<v1::SendResponse, SignaldError> {
+        let mut request = v1::ReactRequest {
+            username,
+            reaction,
+            ..Default::default()
+        };
+        match recipient {
+            Recipient::Address(address) => request.recipient_address = Some(address),
+            Recipient::Group(group_id) => request.recipient_group_id = Some(group_id),
+        }
+        self.capabilities.require(Feature::Reactions)?;
+        self.request_v1(v1::Request::React(request)).await
+    }
+    /// Get the profile of a user
+    pub async fn get_profile(&mut self, account: String, address: SignalAddress) -> Result<v1::Profile, SignaldError> {
+        let request = v1::GetProfileRequest {
+            account,
+            address: Some(address),
+            ..Default::default()
+        };
+        self.capabilities.require(Feature::Profiles)?;
+        self.request_v1(v1::Request::GetProfile(request)).await
+    }
+    /// Get the current state of a group
+    pub async fn get_group(&mut self, account: String, group_id: String) -> Result<v1::JsonGroupV2Info, SignaldError> {
+        let request = v1::GetGroupRequest {
+            account,
+            group_id,
+            ..Default::default()
+        };
+        self.capabilities.require(Feature::GroupsV2)?;
+        self.request_v1(v1::Request::GetGroup(request)).await
+    }
+    /// Accept an invitation to join a group
+    pub async fn accept_invitation(&mut self, account: String, group_id: String) -> Result<v1::JsonGroupV2Info, SignaldError> {
+        let request = v1::AcceptInvitationRequest {
+            account,
+            group_id,
+            ..Default::default()
+        };
+        self.capabilities.require(Feature::GroupsV2)?;
+        self.request_v1(v1::Request::AcceptInvitation(request)).await
+    }
+    /// Decline an invitation to join a group
+    pub async fn decline_invitation(&mut self, account: String, group_id: String) -> Result<(), SignaldError> {
+        let request = v1::LeaveGroupRequest {
+            account,
+            group_id,
+            ..Default::default()
+        };
+        self.capabilities.require(Feature::GroupsV2)?;
+        let _: v1::GroupInfo = self.request_v1(v1::Request::LeaveGroup(request)).await?;
+        Ok(())
+    }
+    /// Join a group with an invitation link
+    /// When the group requires approval the join info has pending_admin_approval set
+    pub async fn join_group(&mut self, account: String, uri: String) -> Result<v1::JsonGroupJoinInfo, SignaldError> {
+        let request = v1::JoinGroupRequest {
+            account,
+            uri,
+            ..Default::default()
+        };
+        self.capabilities.require(Feature::GroupsV2)?;
+        self.request_v1(v1::Request::JoinGroup(request)).await
+    }
+    /// Let users that asked to join a group in
+    pub async fn approve_join_requests(&mut self, account: String, group_id: String, members: Vec<SignalAddress>) -> Result<v1::JsonGroupV2Info, SignaldError> {
+        let request = v1::ApproveMembershipRequest {
+            account,
+            group_id,
+            members,
+            ..Default::default()
+        };
+        self.capabilities.require(Feature::GroupsV2)?;
+        self.request_v1(v1::Request::ApproveMembership(request)).await
+    }
+    /// Deny the requests of users that asked to join a group
+    /// Banned users can't ask to join again
+    pub async fn deny_join_requests(&mut self, account: String, group_id: String, members: Vec<SignalAddress>,
+                                    ban: bool) -> Result<v1::JsonGroupV2Info, SignaldError> {
+        let request = v1::RefuseMembershipRequest {
+            account,
+            group_id,
+            members,
+            also_ban: Some(ban),
+            ..Default::default()
+        };
+        self.capabilities.require(Feature::GroupsV2)?;
+        self.request_v1(v1::Request::RefuseMembership(request)).await
+    }
+    /// Replace the invitation link of a group, the old link stops working
+    pub async fn reset_group_link(&mut self, account: String, group_id: String) -> Result<v1::JsonGroupV2Info, SignaldError> {
+        self.update_group_v2(v1::UpdateGroupRequest {
+            account,
+            group_id,
+            reset_link: Some(true),
+            ..Default::default()
+        }).await
+    }
+    /// Change the role of a group member
+    /// Returns an InputError when the address of the member has no uuid
+    pub async fn set_group_role(&mut self, account: String, group_id: String, member: SignalAddress,
+                                role: GroupRole) -> Result<v1::JsonGroupV2Info, SignaldError> {
+        let uuid = member.uuid().ok_or_else(|| SignaldError::InputError(ErrorData {
+            message: format!("the uuid of {} is needed to change its role", member),
+            ..Default::default()
+        }))?;
+        self.update_group_v2(v1::UpdateGroupRequest {
+            account,
+            group_id,
+            update_role: Some(v1::GroupMember {
+                uuid: Some(uuid.to_string()),
+                role: Some(role.as_str().to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }).await
+    }
+    /// Make a group member an administrator
+    pub async fn promote_admin(&mut self, account: String, group_id: String, member: SignalAddress) -> Result<v1::JsonGroupV2Info, SignaldError> {
+        self.set_group_role(account, group_id, member, GroupRole::Administrator).await
+    }
+    /// Make a group administrator an ordinary member
+    pub async fn demote_admin(&mut self, account: String, group_id: String, member: SignalAddress) -> Result<v1::JsonGroupV2Info, SignaldError> {
+        self.set_group_role(account, group_id, member, GroupRole::Default).await
+    }
+    /// Change who can edit the group, add members and join with the invitation link
+    /// Only the set fields of the access control are changed, the values are AccessLevel names
+    pub async fn set_group_access(&mut self, account: String, group_id: String,
+                                  access_control: v1::GroupAccessControl) -> Result<v1::JsonGroupV2Info, SignaldError> {
+        self.update_group_v2(v1::UpdateGroupRequest {
+            account,
+            group_id,
+            update_access_control: Some(access_control),
+            ..Default::default()
+        }).await
+    }
+    async fn update_group_v2(&mut self, request: v1::UpdateGroupRequest) -> Result<v1::JsonGroupV2Info, SignaldError> {
+        self.capabilities.require(Feature::GroupsV2)?;
+        let group: v1::GroupInfo = self.request_v1(v1::Request::UpdateGroup(request)).await?;
+        group.v2.ok_or_else(|| SignaldError::Other("update_group".to_string(), ErrorData {
+            message: "expected a v2 group".to_string(),
+            ..Default::default()
+        }))
+    }
 
-        self.send_request(&request);
+    /// Send a contact sync request to the other devices on this account
+    pub fn sync_contacts(&mut self, username: String) -> Result<(), SignaldError> {
+        // The response isn't read, so no receiver is kept for it
+        let id = self.message_count.to_string();
+        self.send_request(&SignaldRequest::with_id(Request::SyncContacts(AccountRequest::new(username)), id))
     }
     /// Get a response stream that returns every received message on the socket
     pub fn get_rx(&mut self) -> BusReader<SignaldResponse> {
         self.socket.get_rx()
     }
-
-    /// Get a response from the bus with a matching id or type
-    /// Returns a RecvTimeoutError if the message took more than 3 seconds to return
-    async fn wait_for_request(&mut self, filter: FilterType) -> Result<SignaldResponse, RecvTimeoutError> {
-        // The max possible time to receive a message
-        let end = Instant::now() + Duration::from_millis(3000);
-        let mut rx = self.socket.get_rx();
-
-        let result = rx.iter()
-            // Stop the receiver once the time is over, this keeps updating thanks to the update messages in systemdsocket
-            .take_while(|_| Instant::now() < end )
-            .find(|response| {
-                // The systemdsocket sends an 'update' message each second, don't parse this
-                if let BusUpdate = response.data { return false; }
-
-                Signald::filter_request(&filter, &response)
-            });
-
-        // When no results are found within the time limit, an error is returned
-        match result {
-            Some(x) => {
-                Ok(x)
-            },
-            None => {
-                Err(Timeout)
-            }
-        }
-
+    /// Get a stream of the received messages that match a filter
+    pub fn messages(&mut self, filter: Filter) -> impl Iterator<Item = MessageData> {
+        self.socket.get_rx().into_iter()
+            .filter(move |response| filter.matches(response))
+            .filter_map(|response| match response.data {
+                ResponseType::Message(Some(message)) => Some(message),
+                _ => None,
+            })
     }
-
-    fn filter_request(filter: &FilterType, message: &SignaldResponse) -> bool {
-        match filter {
-            // Filter on id
-            Id(req_id) => {
-                match &message.id {
-                    Some(s) => {
-                        return s == req_id.as_str();
-                    },
-                    None => {
-                        false
-                    }
-                }
-            }
-            // Filter on response type
-            Type(req_type) => {
-                let disc1 = std::mem::discriminant(req_type);
-                let disc2 = std::mem::discriminant(&message.data);
-                return disc1 == disc2;
-            }
-        }
+    /// The connection health of an account
+    /// Returns None when signald hasn't sent any events about the account yet
+    pub fn account_health(&self, username: &str) -> Option<AccountHealth> {
+        self.socket.state().accounts.get(username).cloned()
     }
-
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_filter_request_id_success() {
-        let id = "test".to_string();
-
-        let message = SignaldResponse {
-            id: Some(id.clone()),
-            data: ResponseType::Subscribed
+    /// Block a user, their messages are dropped when dropping blocked messages is turned on
+    pub async fn block(&mut self, account: String, address: SignalAddress) -> Result<(), SignaldError> {
+        let request = v1::BlockRequest {
+            account: account.clone(),
+            address: Some(address.clone()),
+            ..Default::default()
         };
-
-        assert!(Signald::filter_request(&Id(id), &message));
+        let _: v1::Empty = self.request_v1(v1::Request::Block(request)).await?;
+        self.update_block_list(&account, |block_list| block_list.block_address(address.clone()));
+        Ok(())
     }
-
-    #[test]
-    fn test_filter_request_id_wrong() {
-        let id = "test".to_string();
-
-        let message = SignaldResponse {
-            id: Some(id.clone()),
-            data: ResponseType::Subscribed
+    /// Unblock a user
+    pub async fn unblock(&mut self, account: String, address: SignalAddress) -> Result<(), SignaldError> {
+        let request = v1::UnblockRequest {
+            account: account.clone(),
+            address: Some(address.clone()),
+            ..Default::default()
         };
+        let _: v1::Empty = self.request_v1(v1::Request::Unblock(request)).await?;
+        self.update_block_list(&account, |block_list| block_list.unblock_address(&address));
+        Ok(())
+    }
+    /// Block a group
+    pub async fn block_group(&mut self, account: String, group_id: String) -> Result<(), SignaldError> {
+        let request = v1::BlockRequest {
+            account: account.clone(),
+            group_id: Some(group_id.clone()),
+            ..Default::default()
+        };
+        let _: v1::Empty = self.request_v1(v1::Request::Block(request)).await?;
+        self.update_block_list(&account, |block_list| block_list.block_group(group_id.clone()));
+        Ok(())
+    }
+    /// Unblock a group
+    pub async fn unblock_group(&mut self, account: String, group_id: String) -> Result<(), SignaldError> {
+        let request = v1::UnblockRequest {
+            account: account.clone(),
+            group_id: Some(group_id.clone()),
+            ..Default::default()
+        };
+        let _: v1::Empty = self.request_v1(v1::Request::Unblock(request)).await?;
+        self.update_block_list(&account, |block_list| block_list.unblock_group(&group_id));
+        Ok(())
+    }
+    fn update_block_list<F: FnMut(&mut BlockList)>(&mut self, account: &str, mut update: F) {
+        self.socket.update_state(&mut |state| update(state.blocked.entry(account.to_string()).or_default()));
+    }
+    /// The users and groups an account blocked
+    /// Known from the block list other devices sync and the block requests sent on this connection
+    pub fn blocked(&self, account: &str) -> BlockList {
+        self.socket.state().blocked.get(account).cloned().unwrap_or_default()
+    }
+    /// Drop received messages from blocked users and groups before they reach any receiver
+    pub fn set_drop_blocked(&mut self, drop: bool) {
+        self.socket.update_state(&mut |state| state.drop_blocked = drop);
+    }
+    /// Get a stream that returns the health of an account every time it changes
+    pub fn watch_account_health(&mut self, username: String) -> impl Iterator<Item = AccountHealth> {
+        let mut health = self.account_health(&username).unwrap_or_else(|| AccountHealth::new(username));
+        self.socket.get_rx().into_iter()
+            .filter_map(move |response| match health.update(&response.data) {
+                true => Some(health.clone()),
+                false => None,
+            })
+    }
 
-        assert!(!Signald::filter_request(&Id("INCORRECT_ID".to_string()), &message));
+    /// Wait for the next message in a conversation
+    /// Other receivers still get every message, including the returned one
+    /// Returns a Timeout error if no message arrived in time
+    pub async fn next_message_from(&mut self, conversation: &Conversation, timeout: Duration) -> Result<MessageData, SignaldError> {
+        let response = self.wait_for(&Filter::Conversation(conversation.clone()), timeout).await?;
+        match response.data {
+            ResponseType::Message(Some(message)) => Ok(message),
+            _ => Err(SignaldError::Timeout),
+        }
     }
 
-    #[test]
-    fn test_filter_request_type_correct() {
-        let message = SignaldResponse {
-            id: None,
-            data: ResponseType::Subscribed
-        };
+    /// Wait for the first response that matches a filter
+    /// Returns a Timeout error if no response matched in time
+    /// Returns the error signald sent when the response is an error
+    pub async fn wait_for(&mut self, filter: &Filter, timeout: Duration) -> Result<SignaldResponse, SignaldError> {
+        // A new receiver, so responses from before the call or meant for a request aren't returned
+        let rx = self.socket.get_rx();
+        Signald::wait_for_response(rx, timeout, |response| filter.matches(response))
+            .ok_or(SignaldError::Timeout)?
+            .into_result()
+    }
 
-        assert!(Signald::filter_request(&Type(ResponseType::Subscribed), &message));
+    /// Get a response from the bus with a matching id or type
+    /// Returns a Timeout error if the message took more than 3 seconds to return
+    /// Returns the error signald sent when the response is an error
+    async fn wait_for_request(&mut self, filter: Filter) -> Result<SignaldResponse, SignaldError> {
+        // The receiver made before the request was sent
+        let rx = self.response_rx.take().unwrap_or_else(|| self.socket.get_rx());
+        Signald::wait_for_response(rx, Duration::from_millis(3000), |response| filter.matches(response))
+            .ok_or(SignaldError::Timeout)?
+            .into_result()
     }
 
-    #[test]
-    fn test_filter_request_type_wrong() {
-        let message = SignaldResponse {
-            id: None,
-            data: ResponseType::Subscribed
-        };
+    /// Get the first response from the bus that matches the filter within the timeout
+    fn wait_for_response<F: Fn(&SignaldResponse) -> bool>(mut rx: BusReader<SignaldResponse>, timeout: Duration, filter: F) -> Option<SignaldResponse> {
+        // The max possible time to receive a message
+        let end = Instant::now() + timeout;
+
+        rx.iter()
+            // Stop the receiver once the time is over, this keeps updating thanks to the update messages in systemdsocket
+            .take_while(|_| Instant::now() < end )
+            .find(|response| {
+                // The systemdsocket sends an 'update' message each second, don't parse this
+                if let BusUpdate = response.data { return false; }
 
-        assert!(!Signald::filter_request(&Type(ResponseType::Unsubscribed), &message));
+                filter(response)
+            })
     }
+
 }
@@ -0,0 +1,129 @@
+use serde_json::Value;
+use std::fmt;
+use crate::capabilities::Feature;
+use crate::signaldresponse::{ErrorData, UntrustedIdentity, LinkingError, ParseMode, ParseError, parse_data};
+
+/// An error returned by the Signald API
+/// Every variant except Timeout, Io and Unsupported is a rejection sent by signald
+#[derive(Clone, Debug, PartialEq)]
+pub enum SignaldError {
+    /// No response was received in time
+    Timeout,
+    /// Writing to the socket failed
+    Io(String),
+    /// Signald failed while handling the request
+    UnexpectedError(ErrorData),
+    /// The identity of the recipient changed and has to be trusted again
+    UntrustedIdentity(Box<UntrustedIdentity>),
+    /// The request contained invalid input
+    InputError(ErrorData),
+    /// Signald could not understand the request
+    InvalidRequest(ErrorData),
+    /// Linking a new device failed
+    LinkingError(Box<LinkingError>),
+    /// Any other error, with the type signald gave it
+    Other(String, ErrorData),
+    /// The connected signald version doesn't support the feature
+    Unsupported {
+        feature: Feature,
+        version: String,
+    },
+}
+impl SignaldError {
+    /// Create an error from an error response
+    /// Returns None when the type isn't an error type
+    pub fn parse(typ: &str, val: &Value, mode: ParseMode) -> Option<Result<SignaldError, ParseError>> {
+        let error = match typ {
+            "unexpected_error" => parse_data(typ, val, mode).map(SignaldError::UnexpectedError),
+            "untrusted_identity" => parse_data(typ, val, mode).map(|data| SignaldError::UntrustedIdentity(Box::new(data))),
+            "input_error" => parse_data(typ, val, mode).map(SignaldError::InputError),
+            "invalid_request" => parse_data(typ, val, mode).map(SignaldError::InvalidRequest),
+            "linking_error" => parse_data(typ, val, mode).map(|data| SignaldError::LinkingError(Box::new(data))),
+            _ => return None,
+        };
+        Some(error)
+    }
+
+    /// Create an error from the error_type and error of a v1 response
+    pub fn from_v1(error_type: &str, error: Value) -> SignaldError {
+        match error_type {
+            "InternalError" => SignaldError::UnexpectedError(from_value_or_default(error)),
+            "InvalidRequestError" => SignaldError::InvalidRequest(from_value_or_default(error)),
+            "UntrustedIdentityError" => SignaldError::UntrustedIdentity(Box::new(from_value_or_default(error))),
+            _ => SignaldError::Other(error_type.to_string(), from_value_or_default(error)),
+        }
+    }
+
+    /// The signald type of this error
+    pub fn type_name(&self) -> &str {
+        match self {
+            SignaldError::Timeout => "timeout",
+            SignaldError::Io(_) => "io_error",
+            SignaldError::UnexpectedError(_) => "unexpected_error",
+            SignaldError::UntrustedIdentity(_) => "untrusted_identity",
+            SignaldError::InputError(_) => "input_error",
+            SignaldError::InvalidRequest(_) => "invalid_request",
+            SignaldError::LinkingError(_) => "linking_error",
+            SignaldError::Other(typ, _) => typ,
+            SignaldError::Unsupported { .. } => "unsupported",
+        }
+    }
+
+    /// The data of this error as json
+    pub fn data_value(&self) -> Option<Value> {
+        let val = match self {
+            SignaldError::Timeout | SignaldError::Io(_) | SignaldError::Unsupported { .. } => return None,
+            SignaldError::UnexpectedError(data) | SignaldError::InputError(data) | SignaldError::InvalidRequest(data)
+            | SignaldError::Other(_, data) => {
+                serde_json::to_value(data)
+            }
+            SignaldError::UntrustedIdentity(data) => serde_json::to_value(data),
+            SignaldError::LinkingError(data) => serde_json::to_value(data),
+        };
+        Some(val.unwrap())
+    }
+
+    /// The request signald echoed back with the error
+    pub fn request(&self) -> Option<Value> {
+        match self {
+            SignaldError::Timeout | SignaldError::Io(_) | SignaldError::Unsupported { .. } => None,
+            SignaldError::UnexpectedError(data) | SignaldError::InputError(data) | SignaldError::InvalidRequest(data)
+            | SignaldError::Other(_, data) => {
+                data.request.clone()
+            }
+            SignaldError::UntrustedIdentity(data) => data.request.clone(),
+            SignaldError::LinkingError(data) => serde_json::to_value(&data.request).ok(),
+        }
+    }
+}
+
+fn from_value_or_default<T: serde::de::DeserializeOwned + Default>(val: Value) -> T {
+    serde_json::from_value(val).unwrap_or_default()
+}
+
+impl fmt::Display for SignaldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignaldError::Timeout => write!(f, "timed out waiting for signald"),
+            SignaldError::Io(message) => write!(f, "socket error: {}", message),
+            SignaldError::UnexpectedError(data) | SignaldError::InputError(data) | SignaldError::InvalidRequest(data)
+            | SignaldError::Other(_, data) => {
+                write!(f, "{}: {}", self.type_name(), data.message)
+            }
+            SignaldError::UntrustedIdentity(data) => {
+                let number = data.number.as_ref().map(|n| n.to_string()).unwrap_or_default();
+                write!(f, "untrusted identity: {}", number)
+            }
+            SignaldError::LinkingError(data) => write!(f, "linking error: {}", data.message),
+            SignaldError::Unsupported { feature, version } => {
+                write!(f, "signald {} does not support {}", version, feature)
+            }
+        }
+    }
+}
+impl std::error::Error for SignaldError {}
+impl From<std::io::Error> for SignaldError {
+    fn from(e: std::io::Error) -> Self {
+        SignaldError::Io(e.to_string())
+    }
+}
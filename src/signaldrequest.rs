@@ -1,79 +1,166 @@
-use serde::Serialize;
+use serde::{Serialize, Deserialize, Deserializer};
+use serde::de::Error;
+use serde_json::Value;
+use crate::signaladdress::SignalAddress;
+use crate::v1;
+
+pub use crate::v0::{AccountRequest, ContactRequest, SendRequest, OutgoingAttachment, Quote, LinkRequest, AddDeviceRequest,
+                    RegisterRequest, VerifyRequest, TrustRequest, UpdateContactRequest, ContactUpdate, UpdateGroupRequest,
+                    LeaveGroupRequest, SetExpirationRequest};
 
 /// A Signald request
-/// Contains of all the possible fields necessary by signald
-#[derive(Serialize, Default, Clone)]
+/// Wraps a typed request together with the id signald echoes back in its response
+#[derive(Serialize, Clone)]
 pub struct SignaldRequest {
-    #[serde(rename = "type")]
-    pub typ: String,
-    pub username: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<String>,
-    #[serde(rename = "messageBody", skip_serializing_if = "Option::is_none")]
-    pub message_body: Option<String>,
-    #[serde(rename = "recipientNumber", skip_serializing_if = "Option::is_none")]
-    pub recipient_number: Option<String>,
-    #[serde(rename = "recipientGroupId",skip_serializing_if = "Option::is_none")]
-    pub recipient_group_id: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub voice: Option<bool>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub code: Option<String>,
-    #[serde(rename = "deviceName", skip_serializing_if = "Option::is_none")]
-    pub device_name: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub attachment_filenames: Option<Vec<String>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub quote: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub uri: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub group_name: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub members: Option<Vec<String>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub avatar: Option<String>,
+    #[serde(flatten)]
+    pub request: Request,
 }
 impl SignaldRequest {
+    /// Create a request without an id
+    pub fn new(request: Request) -> SignaldRequest {
+        SignaldRequest {
+            id: None,
+            request,
+        }
+    }
+    /// Create a request with an id, the response to it will carry the same id
+    pub fn with_id(request: Request, id: String) -> SignaldRequest {
+        SignaldRequest {
+            id: Some(id),
+            request,
+        }
+    }
     /// Parse a request to json
     pub fn to_json_string(&self) -> String {
         serde_json::to_string(self).unwrap()
     }
 }
+impl<'de> Deserialize<'de> for SignaldRequest {
+    /// Requests with "version": "v1" are read as v1 requests, all others as v0 requests
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut val = Value::deserialize(deserializer)?;
+        let id = val["id"].as_str().map(|x| x.to_string());
+
+        let request = match val.as_object_mut() {
+            Some(map) if map.get("version").and_then(|v| v.as_str()) == Some("v1") => {
+                map.remove("id");
+                map.remove("version");
+                Request::V1(serde_json::from_value(val).map_err(D::Error::custom)?)
+            }
+            Some(map) => {
+                map.remove("id");
+                serde_json::from_value(val).map_err(D::Error::custom)?
+            }
+            None => return Err(D::Error::custom("expected a request object")),
+        };
+
+        Ok(SignaldRequest {
+            id,
+            request,
+        })
+    }
+}
+
+/// Every command signald accepts
+/// The variant determines the "type" of the request, its data the other fields
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    Send(SendRequest),
+    Subscribe(AccountRequest),
+    Unsubscribe(AccountRequest),
+    Link(LinkRequest),
+    AddDevice(AddDeviceRequest),
+    Register(RegisterRequest),
+    Verify(VerifyRequest),
+    GetUser(ContactRequest),
+    GetIdentities(ContactRequest),
+    Trust(TrustRequest),
+    SyncContacts(AccountRequest),
+    SyncGroups(AccountRequest),
+    SyncConfiguration(AccountRequest),
+    ListContacts(AccountRequest),
+    ListGroups(AccountRequest),
+    UpdateContact(UpdateContactRequest),
+    UpdateGroup(UpdateGroupRequest),
+    LeaveGroup(LeaveGroupRequest),
+    SetExpiration(SetExpirationRequest),
+    ListAccounts,
+    Version,
+    /// A request in the v1 protocol
+    #[serde(untagged)]
+    V1(v1::Request),
+}
 
-pub struct SignaldRequestBuilder {
-    request: SignaldRequest
+/// The receiver of a message, either a single user or a group
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Recipient {
+    #[serde(rename = "recipientAddress")]
+    Address(SignalAddress),
+    #[serde(rename = "recipientGroupId")]
+    Group(String),
 }
-impl SignaldRequestBuilder {
-    pub fn new() -> SignaldRequestBuilder {
-        SignaldRequestBuilder {
-            request: Default::default(),
+
+impl Recipient {
+    /// The recipientAddress and recipientGroupId fields of a request to this recipient
+    pub fn into_fields(self) -> (Option<SignalAddress>, Option<String>) {
+        match self {
+            Recipient::Address(address) => (Some(address), None),
+            Recipient::Group(group_id) => (None, Some(group_id)),
         }
     }
-
-    pub fn set_type(&mut self, typ: String) {
-        self.request.typ = typ;
+    /// The recipient set by the recipientAddress and recipientGroupId fields of a request
+    pub fn from_fields(address: &Option<SignalAddress>, group_id: &Option<String>) -> Option<Recipient> {
+        match (address, group_id) {
+            (_, Some(group_id)) => Some(Recipient::Group(group_id.clone())),
+            (Some(address), None) => Some(Recipient::Address(address.clone())),
+            (None, None) => None,
+        }
     }
+}
 
-    pub fn set_username(&mut self, username: String) {
-        self.request.username = username;
+impl AccountRequest {
+    pub fn new(username: String) -> AccountRequest {
+        AccountRequest {
+            username,
+            ..Default::default()
+        }
     }
+}
 
-    pub fn set_recipient_number(&mut self, recipient_number: String) {
-        self.request.recipient_number = Some(recipient_number);
+impl SendRequest {
+    /// Create an empty message to a recipient
+    pub fn new(username: String, recipient: Recipient) -> SendRequest {
+        let (recipient_address, recipient_group_id) = recipient.into_fields();
+        SendRequest {
+            username,
+            recipient_address,
+            recipient_group_id,
+            ..Default::default()
+        }
     }
-
-    pub fn set_message_body(&mut self, message_body: String) {
-        self.request.message_body = Some(message_body);
+    /// The user or group the message is sent to
+    pub fn recipient(&self) -> Option<Recipient> {
+        Recipient::from_fields(&self.recipient_address, &self.recipient_group_id)
     }
+}
 
-    pub fn set_id(&mut self, id: String) {
-        self.request.id = Some(id);
+impl SetExpirationRequest {
+    /// Set the time messages to a recipient disappear after
+    pub fn new(username: String, recipient: Recipient, expires_in_seconds: i32) -> SetExpirationRequest {
+        let (recipient_address, recipient_group_id) = recipient.into_fields();
+        SetExpirationRequest {
+            username,
+            recipient_address,
+            recipient_group_id,
+            expires_in_seconds,
+            ..Default::default()
+        }
     }
-
-    /// Create a request
-    /// Returns a clone of the internal request
-    pub fn build(&self) -> SignaldRequest {
-        self.request.clone()
+    /// The user or group the expiration is set for
+    pub fn recipient(&self) -> Option<Recipient> {
+        Recipient::from_fields(&self.recipient_address, &self.recipient_group_id)
     }
 }
@@ -1,20 +1,82 @@
-use serde::Serialize;
-use serde::Deserialize;
-use serde_json::Value;
-use std::collections::HashMap;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::{Error, DeserializeOwned};
+use serde_json::{Value, Map};
+use std::fmt;
+use crate::signaladdress::SignalAddress;
+use crate::signalderror::SignaldError;
+use crate::v1;
+use crate::viewonce::ViewOnceAttachment;
 use crate::signaldresponse::ResponseType::{Version, Unknown};
 
+pub use crate::v0::{VersionData, WebSocketConnectionState, MessageData, SyncMessage, Message, EditMessage, RemoteDelete,
+                    GroupInfo, GroupV2Info, SentMessage, ReadMessage, Typing, Receipt, Attachment, BlockedList, ViewOnceOpen,
+                    Verified, Configuration, StickerPackOperation, MessageRequestResponse, Account, AccountListData,
+                    AccountInfo, LinkingUri, LinkingError, Request, ErrorData, UntrustedIdentity};
+
+/// How strictly responses are parsed
+/// In both modes unknown fields are kept in the `extra` map of every struct
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ParseMode {
+    /// Every field of the typed data has to be present in the response
+    Strict,
+    /// Missing fields get their default value
+    #[default]
+    Lenient,
+}
+
+/// An error returned when a response can't be parsed
+#[derive(Debug)]
+pub enum ParseError {
+    /// The response has no type
+    MissingType,
+    /// The data does not match the structure of its type
+    Json(serde_json::Error),
+    /// Strict parsing found fields missing from the data of this type
+    Incomplete(String),
+}
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::MissingType => write!(f, "response has no type"),
+            ParseError::Json(e) => write!(f, "invalid response data: {}", e),
+            ParseError::Incomplete(typ) => write!(f, "{} response is missing fields", typ),
+        }
+    }
+}
+impl std::error::Error for ParseError {}
+impl From<serde_json::Error> for ParseError {
+    fn from(e: serde_json::Error) -> Self {
+        ParseError::Json(e)
+    }
+}
+
 /// Indicates which kind of Signald message is received
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
+#[allow(clippy::large_enum_variant)]
 pub enum ResponseType {
     BusUpdate,
     Message(Option<MessageData>),
     Version(Option<VersionData>),
     ContactList(Option<Vec<Account>>),
+    AccountList(Option<AccountListData>),
     LinkingUri(Option<LinkingUri>),
     LinkingError(Option<LinkingError>),
     Subscribed,
     Unsubscribed,
+    /// Signald started receiving messages for the account
+    ListenStarted(Option<String>),
+    /// Signald stopped receiving messages for the account
+    ListenStopped(Option<String>),
+    /// Signald refreshed the keys and profile of the account
+    AccountRefreshed(Option<String>),
+    /// The connection of an account to the Signal server changed
+    WebSocketConnectionState(Option<WebSocketConnectionState>),
+    /// The results of sending a message
+    SendResults(Option<v1::SendResponse>),
+    /// An error signald sent in response to a request
+    Error(SignaldError),
+    /// A response in the v1 protocol
+    V1(v1::Response),
     /// An unknown response
     /// String parameter is the type
     /// Value parameter is a Value of the data
@@ -22,211 +84,469 @@ pub enum ResponseType {
 }
 impl ResponseType {
     /// Create a ResponseType from response data
+    /// Data that can't be parsed leniently is returned as an Unknown response
     pub fn new(typ: &str, val: &Value) -> ResponseType {
-        return match typ {
-            "contact_list" => {
-                let data = serde_json::from_value(val.clone()).unwrap();
-                ResponseType::ContactList(data)
+        ResponseType::parse(typ, val, ParseMode::Lenient).unwrap_or_else(|_| Unknown(typ.to_string(), val.clone()))
+    }
+
+    /// Create a ResponseType from response data with the given parse mode
+    pub fn parse(typ: &str, val: &Value, mode: ParseMode) -> Result<ResponseType, ParseError> {
+        Ok(match typ {
+            "contact_list" => ResponseType::ContactList(parse_data(typ, val, mode)?),
+            "account_list" => ResponseType::AccountList(parse_data(typ, val, mode)?),
+            "version" => Version(parse_data(typ, val, mode)?),
+            "message" => ResponseType::Message(parse_data(typ, val, mode)?),
+            "linking_uri" => ResponseType::LinkingUri(parse_data(typ, val, mode)?),
+            "linking_error" => ResponseType::LinkingError(parse_data(typ, val, mode)?),
+            "bus_update" => ResponseType::BusUpdate,
+            "subscribed" => ResponseType::Subscribed,
+            "unsubscribed" => ResponseType::Unsubscribed,
+            "listen_started" => ResponseType::ListenStarted(parse_data(typ, val, mode)?),
+            "listen_stopped" => ResponseType::ListenStopped(parse_data(typ, val, mode)?),
+            "account_refreshed" => ResponseType::AccountRefreshed(parse_data(typ, val, mode)?),
+            "websocket_connection_state" => ResponseType::WebSocketConnectionState(parse_data(typ, val, mode)?),
+            "send_results" => ResponseType::SendResults(parse_data(typ, val, mode)?),
+            _ => match SignaldError::parse(typ, val, mode) {
+                Some(error) => ResponseType::Error(error?),
+                None => Unknown(typ.to_string(), val.clone())
             }
-            "version" => {
-                let data = serde_json::from_value(val.clone()).unwrap();
-                Version(data)
+        })
+    }
+
+    /// The signald type of this response
+    pub fn type_name(&self) -> &str {
+        match self {
+            ResponseType::BusUpdate => "bus_update",
+            ResponseType::Message(_) => "message",
+            ResponseType::Version(_) => "version",
+            ResponseType::ContactList(_) => "contact_list",
+            ResponseType::AccountList(_) => "account_list",
+            ResponseType::LinkingUri(_) => "linking_uri",
+            ResponseType::LinkingError(_) => "linking_error",
+            ResponseType::Subscribed => "subscribed",
+            ResponseType::Unsubscribed => "unsubscribed",
+            ResponseType::ListenStarted(_) => "listen_started",
+            ResponseType::ListenStopped(_) => "listen_stopped",
+            ResponseType::AccountRefreshed(_) => "account_refreshed",
+            ResponseType::WebSocketConnectionState(_) => "websocket_connection_state",
+            ResponseType::SendResults(_) => "send_results",
+            ResponseType::Error(error) => error.type_name(),
+            ResponseType::V1(response) => &response.typ,
+            ResponseType::Unknown(typ, _) => typ,
+        }
+    }
+
+    /// The data of this response as json
+    /// Returns None when the response carries no data
+    pub fn data_value(&self) -> Option<Value> {
+        let val = match self {
+            ResponseType::Message(data) => serde_json::to_value(data),
+            ResponseType::Version(data) => serde_json::to_value(data),
+            ResponseType::ContactList(data) => serde_json::to_value(data),
+            ResponseType::AccountList(data) => serde_json::to_value(data),
+            ResponseType::LinkingUri(data) => serde_json::to_value(data),
+            ResponseType::LinkingError(data) => serde_json::to_value(data),
+            ResponseType::ListenStarted(data) | ResponseType::ListenStopped(data) | ResponseType::AccountRefreshed(data) => {
+                serde_json::to_value(data)
             }
-            "message" => {
-                let data = serde_json::from_value(val.clone()).unwrap();
-                ResponseType::Message(data)
+            ResponseType::WebSocketConnectionState(data) => serde_json::to_value(data),
+            ResponseType::SendResults(data) => serde_json::to_value(data),
+            ResponseType::Error(error) => Ok(error.data_value().unwrap_or_default()),
+            ResponseType::V1(response) => Ok(response.data.clone().unwrap_or_default()),
+            ResponseType::Unknown(_, val) => Ok(val.clone()),
+            ResponseType::BusUpdate | ResponseType::Subscribed | ResponseType::Unsubscribed => Ok(Value::Null),
+        };
+
+        match val.unwrap() {
+            Value::Null => None,
+            val => Some(val),
+        }
+    }
+
+    /// The account an account state event is about
+    pub fn account(&self) -> Option<&str> {
+        match self {
+            ResponseType::ListenStarted(account) | ResponseType::ListenStopped(account) | ResponseType::AccountRefreshed(account) => {
+                account.as_deref()
             }
-            "linking_uri" => {
-                let data = serde_json::from_value(val.clone()).unwrap();
-                ResponseType::LinkingUri(data)
+            ResponseType::WebSocketConnectionState(Some(state)) => Some(&state.account),
+            _ => None,
+        }
+    }
+}
+impl Serialize for ResponseType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let ResponseType::V1(response) = self {
+            return response.serialize(serializer);
+        }
+
+        let mut map = Map::new();
+        map.insert("type".to_string(), Value::String(self.type_name().to_string()));
+        if let Some(data) = self.data_value() {
+            map.insert("data".to_string(), data);
+        }
+        map.serialize(serializer)
+    }
+}
+impl<'de> Deserialize<'de> for ResponseType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let val = Value::deserialize(deserializer)?;
+        let typ = val["type"].as_str().ok_or_else(|| D::Error::missing_field("type"))?;
+        if is_v1(&val) {
+            return serde_json::from_value(val).map(ResponseType::V1).map_err(D::Error::custom);
+        }
+        Ok(ResponseType::new(typ, &val["data"]))
+    }
+}
+
+/// Check if a response is sent in the v1 protocol
+fn is_v1(val: &Value) -> bool {
+    val["version"].as_str() == Some("v1")
+}
+
+/// Parse a v1 response, the id is kept outside of the response data
+fn parse_v1(val: &Value) -> Result<ResponseType, ParseError> {
+    let mut val = val.clone();
+    if let Some(map) = val.as_object_mut() {
+        map.remove("id");
+    }
+    Ok(ResponseType::V1(serde_json::from_value(val)?))
+}
+
+/// Parse the data of a response
+/// In strict mode the parsed data has to serialize back to the same json, any field that
+/// was filled in with a default value makes the parsing fail
+pub(crate) fn parse_data<T: DeserializeOwned + Serialize>(typ: &str, val: &Value, mode: ParseMode) -> Result<T, ParseError> {
+    let data: T = serde_json::from_value(val.clone())?;
+
+    if mode == ParseMode::Strict && strip_nulls(serde_json::to_value(&data)?) != strip_nulls(val.clone()) {
+        return Err(ParseError::Incomplete(typ.to_string()));
+    }
+
+    Ok(data)
+}
+
+/// Collect the fields of the parsed json that aren't in the original json
+/// Fields inside a defaulted field aren't collected separately
+fn find_defaulted(parsed: &Value, original: &Value, pointer: String, defaulted: &mut Vec<(String, Value)>) {
+    match (parsed, original) {
+        (Value::Object(parsed), Value::Object(original)) => {
+            for (key, value) in parsed {
+                let field_pointer = format!("{}/{}", pointer, key.replace('~', "~0").replace('/', "~1"));
+                match original.get(key) {
+                    None | Some(Value::Null) => defaulted.push((field_pointer, value.clone())),
+                    Some(original) => find_defaulted(value, original, field_pointer, defaulted),
+                }
             }
-            "linking_error" => {
-                let data = serde_json::from_value(val.clone()).unwrap();
-                ResponseType::LinkingError(data)
+        }
+        (Value::Array(parsed), Value::Array(original)) => {
+            for (i, (value, original)) in parsed.iter().zip(original).enumerate() {
+                find_defaulted(value, original, format!("{}/{}", pointer, i), defaulted);
             }
-            "subscribed" => ResponseType::Subscribed,
-            "unsubscribed" => ResponseType::Unsubscribed,
-            _ => Unknown(typ.to_string(), val.clone())
         }
+        _ => (),
+    }
+}
+
+/// Remove a defaulted field from the json when it still has its default value
+fn remove_default(val: &mut Value, pointer: &str, default: &Value) {
+    if val.pointer(pointer) != Some(default) {
+        return;
+    }
+    let (parent, key) = pointer.split_at(pointer.rfind('/').unwrap_or(0));
+    let key = key.trim_start_matches('/').replace("~1", "/").replace("~0", "~");
+    if let Some(Value::Object(map)) = val.pointer_mut(parent) {
+        map.remove(&key);
+    }
+}
 
+/// Remove all null fields, these are skipped when serializing
+fn strip_nulls(val: Value) -> Value {
+    match val {
+        Value::Object(map) => Value::Object(map.into_iter()
+            .filter(|(_, v)| !v.is_null())
+            .map(|(k, v)| (k, strip_nulls(v)))
+            .collect()),
+        Value::Array(values) => Value::Array(values.into_iter().map(strip_nulls).collect()),
+        val => val,
     }
 }
 
 /// A Signald response
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct SignaldResponse {
     pub id: Option<String>,
     pub data: ResponseType,
+    /// The fields lenient parsing filled in with a default value, by their json pointer and default value
+    /// They are left out of the json again as long as they keep that value
+    pub defaulted: Vec<(String, Value)>,
+}
+impl PartialEq for SignaldResponse {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && self.data == other.data
+    }
 }
 impl SignaldResponse {
+    /// Parse a response leniently
+    /// This never fails, responses that can't be parsed are returned as Unknown
     pub fn from_value(val: Value) -> SignaldResponse {
+        SignaldResponse::from_value_with_mode(val, ParseMode::Lenient)
+    }
+
+    /// Parse a response with the given parse mode
+    /// Responses that can't be parsed in this mode are returned as Unknown
+    pub fn from_value_with_mode(val: Value, mode: ParseMode) -> SignaldResponse {
+        let id = val["id"].as_str().map(|x| x.to_string());
+
+        let typ = val["type"].as_str().unwrap_or_default();
+        let parsed = match is_v1(&val) {
+            true => parse_v1(&val),
+            false => ResponseType::parse(typ, &val["data"], mode),
+        };
+        let data: ResponseType = parsed.unwrap_or_else(|_| Unknown(typ.to_string(), val["data"].clone()));
+
+        SignaldResponse::with_defaults(id, data, &val)
+    }
+
+    /// Parse a response with the given parse mode
+    pub fn parse(val: Value, mode: ParseMode) -> Result<SignaldResponse, ParseError> {
         let id = val["id"].as_str().map(|x| x.to_string());
+        let typ = val["type"].as_str().ok_or(ParseError::MissingType)?;
+        let data = match is_v1(&val) {
+            true => parse_v1(&val)?,
+            false => ResponseType::parse(typ, &val["data"], mode)?,
+        };
 
-        let data: ResponseType = ResponseType::new(val["type"].as_str().unwrap(), &val["data"]);
+        Ok(SignaldResponse::with_defaults(id, data, &val))
+    }
 
+    /// A response parsed from json, remembering the fields that weren't in the json
+    fn with_defaults(id: Option<String>, data: ResponseType, val: &Value) -> SignaldResponse {
+        let mut defaulted = Vec::new();
+        find_defaulted(&serde_json::to_value(&data).unwrap(), val, String::new(), &mut defaulted);
         SignaldResponse {
             id,
-            data
+            data,
+            defaulted,
+        }
+    }
+
+    /// Turn an error response into an Err
+    pub fn into_result(self) -> Result<SignaldResponse, SignaldError> {
+        match &self.data {
+            ResponseType::Error(error) => Err(error.clone()),
+            ResponseType::LinkingError(Some(error)) => Err(SignaldError::LinkingError(Box::new(error.clone()))),
+            ResponseType::V1(response) => match response.error() {
+                Some(error) => Err(error),
+                None => Ok(self),
+            },
+            _ => Ok(self),
         }
+    }
 
+    /// Convert the response back to the json signald sent
+    /// Fields lenient parsing filled in are left out, unless their value changed since
+    pub fn to_value(&self) -> Value {
+        let mut val = serde_json::to_value(&self.data).unwrap();
+        for (pointer, default) in &self.defaulted {
+            remove_default(&mut val, pointer, default);
+        }
+        if let Some(id) = &self.id {
+            val["id"] = Value::String(id.clone());
+        }
+        val
+    }
+}
+impl Serialize for SignaldResponse {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_value().serialize(serializer)
+    }
+}
+impl<'de> Deserialize<'de> for SignaldResponse {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let val = Value::deserialize(deserializer)?;
+        if !val["type"].is_string() {
+            return Err(D::Error::missing_field("type"));
+        }
+        Ok(SignaldResponse::from_value(val))
     }
 }
 
 pub trait ResponseData {}
 
-// ========================================= VERSION ===============================================
-#[derive(Serialize, Deserialize, Default, Clone)]
-pub struct VersionData {
-    #[serde(rename = "name")]
-    pub name: String,
-    #[serde(rename = "version")]
-    pub version: String,
-    #[serde(rename = "branch")]
-    pub branch: String,
-    #[serde(rename = "commit")]
-    pub commit: String,
+// ==================================== SEND RESULTS ===============================================
+/// The result of sending a message
+#[derive(Clone, Debug, PartialEq)]
+pub struct SendResult {
+    /// The timestamp of the sent message, reactions, quotes and deletes refer to the message by it
+    pub timestamp: i64,
+    pub results: Vec<RecipientResult>,
 }
+impl SendResult {
+    /// Read the send result from signald's response to a send request
+    pub fn from_response(response: &SignaldResponse) -> Result<SendResult, SignaldError> {
+        let send_response = match &response.data {
+            ResponseType::V1(response) => response.parse::<v1::SendResponse>()?,
+            ResponseType::SendResults(Some(send_response)) => send_response.clone(),
+            ResponseType::Error(error) => return Err(error.clone()),
+            other => return Err(SignaldError::Other(other.type_name().to_string(), ErrorData {
+                message: "expected a send response".to_string(),
+                ..Default::default()
+            })),
+        };
+        Ok(SendResult::from(send_response))
+    }
 
-// ========================================= MESSAGE ===============================================
-#[derive(Serialize, Deserialize, Default, Clone)]
-pub struct MessageData {
-    #[serde(rename = "username")]
-    pub username: Option<String>,
-    #[serde(rename = "uuid")]
-    pub uuid: Option<String>,
-    #[serde(rename = "source")]
-    pub source: Option<String>,
-    #[serde(rename = "sourceDevice")]
-    pub source_device: Option<i32>,
-    #[serde(rename = "type")]
-    pub typ: i32,
-    #[serde(rename = "timestamp")]
-    pub timestamp: i64,
-    #[serde(rename = "timestampISO")]
-    pub timestamp_iso: String,
-    #[serde(rename = "serverTimestamp")]
-    pub server_timestamp: i64,
-    #[serde(rename = "hasLegacyMessage")]
-    pub has_legacy_message: bool,
-    #[serde(rename = "hasContent")]
-    pub has_content: bool,
-    #[serde(rename = "isSignalMessage")]
-    pub is_signal_message: Option<bool>,
-    #[serde(rename = "isPrekeySignalMessage")]
-    pub is_prekey_signal_message: Option<bool>,
-    #[serde(rename = "isReceipt")]
-    pub is_receipt: bool,
-    #[serde(rename = "isUnidentifiedSender")]
-    pub is_unidentified_sender: bool,
-    #[serde(rename = "syncMessage")]
-    pub sync_message: Option<SyncMessage>,
-    #[serde(rename = "dataMessage")]
-    pub data_message: Option<Message>,
-    #[serde(rename = "typing")]
-    pub typing: Option<Typing>,
-    #[serde(rename = "receipt")]
-    pub receipt: Option<Receipt>,
-}
-
-#[derive(Serialize, Deserialize, Default, Clone)]
-pub struct SyncMessage {
-    #[serde(rename = "sent")]
-    pub sent: Option<SentMessage>,
-    // #[serde(rename = "contacts")]
-    // pub contacts: Option<Contacts>,
-    #[serde(rename = "contactsComplete")]
-    pub contacts_complete: bool,
-    #[serde(rename = "readMessages")]
-    pub read_messages: Option<Vec<ReadMessage>>,
-    #[serde(rename = "stickerPackOperations")]
-    pub sticker_pack_operations: Option<Vec<String>>,
-    #[serde(rename = "unidentifiedStatus")]
-    pub unidentified_status: Option<HashMap<String, bool>>,
-    // #[serde(rename = "isRecipientUpdate")]
-    // pub is_recipient_update: bool,
-}
-
-#[derive(Serialize, Deserialize, Default, Clone)]
-pub struct Message {
-    #[serde(rename = "timestamp")]
-    pub timestamp: i64,
-    #[serde(rename = "message")]
-    pub message: String,
-    #[serde(rename = "expiresInSeconds")]
-    pub expires_in_seconds: i32,
-}
-#[derive(Serialize, Deserialize, Default, Clone)]
-pub struct SentMessage {
-    #[serde(rename = "destination")]
-    pub destination: String,
-    #[serde(rename = "timestamp")]
-    pub timestamp: i64,
-    #[serde(rename = "expirationStartTimestamp")]
-    pub expiration_start_timestamp: i64,
-    #[serde(rename = "message")]
-    pub message: Message,
-    #[serde(rename = "unidentifiedStatus")]
-    pub unidentified_status: HashMap<String, bool>,
-    #[serde(rename = "isRecipientUpdate")]
-    pub is_recipient_update: bool,
-}
-#[derive(Serialize, Deserialize, Default, Clone)]
-pub struct ReadMessage {
-    #[serde(rename = "sender")]
-    pub sender: String,
-    #[serde(rename = "timestamp")]
-    pub timestamp: i64,
+    /// The result for a single recipient
+    pub fn get(&self, address: &SignalAddress) -> Option<&RecipientStatus> {
+        self.results.iter().find(|r| r.address.as_ref() == Some(address)).map(|r| &r.status)
+    }
+    /// Check if the message reached every recipient
+    pub fn is_success(&self) -> bool {
+        self.results.iter().all(|r| r.status.is_success())
+    }
+    /// The recipients the message did not reach
+    pub fn failures(&self) -> impl Iterator<Item = &RecipientResult> {
+        self.results.iter().filter(|r| !r.status.is_success())
+    }
+    /// A short json summary with the status of every recipient
+    pub fn to_value(&self) -> Value {
+        let results: Vec<Value> = self.results.iter()
+            .map(|r| serde_json::json!({
+                "address": r.recipient(),
+                "status": r.status.to_string(),
+            }))
+            .collect();
+        serde_json::json!({ "timestamp": self.timestamp, "results": results })
+    }
 }
-#[derive(Serialize, Deserialize, Default, Clone)]
-pub struct Typing {
-    #[serde(rename = "action")]
-    pub action: String,
-    #[serde(rename = "timestamp")]
-    pub timestamp: i64,
+impl From<v1::SendResponse> for SendResult {
+    fn from(response: v1::SendResponse) -> Self {
+        SendResult {
+            timestamp: response.timestamp,
+            results: response.results.into_iter().map(RecipientResult::from).collect(),
+        }
+    }
 }
-#[derive(Serialize, Deserialize, Default, Clone)]
-pub struct Receipt {
-    #[serde(rename = "type")]
-    pub typ: String,
-    #[serde(rename = "timestamps")]
-    pub timestamps: Vec<u64>,
-    #[serde(rename = "when")]
-    pub when: u64,
+
+/// The result of sending a message to one recipient
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecipientResult {
+    /// None when signald left the address out
+    pub address: Option<SignalAddress>,
+    pub status: RecipientStatus,
+}
+impl RecipientResult {
+    /// The address of the recipient as text
+    pub fn recipient(&self) -> String {
+        self.address.as_ref().map(|a| a.to_string()).unwrap_or_else(|| "unknown recipient".to_string())
+    }
 }
+impl From<v1::SendMessageResult> for RecipientResult {
+    fn from(result: v1::SendMessageResult) -> Self {
+        let status = match (result.success, result.identity_failure) {
+            (Some(success), _) => RecipientStatus::Success {
+                unidentified: success.unidentified,
+                needs_sync: success.needs_sync,
+            },
+            (None, Some(identity_key)) => RecipientStatus::IdentityFailure(identity_key),
+            (None, None) if result.unregistered_failure => RecipientStatus::UnregisteredFailure,
+            (None, None) => RecipientStatus::NetworkFailure,
+        };
 
-// ==================================== CONTACT LIST ===============================================
-#[derive(Serialize, Deserialize, Default, Clone)]
-pub struct ContactListData {
-    #[serde(flatten)]
-    pub contacts: Vec<Account>
+        RecipientResult {
+            address: result.address,
+            status,
+        }
+    }
 }
-#[derive(Serialize, Deserialize, Default, Clone)]
-pub struct Account {
-    pub name: Option<String>,
-    pub number: String,
-    pub color: String,
-    #[serde(rename = "profileKey")]
-    pub profile_key: Option<String>,
+
+/// Whether a message reached a recipient
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecipientStatus {
+    /// The message was delivered to the Signal server
+    /// Unidentified messages were sent with sealed sender
+    Success { unidentified: bool, needs_sync: bool },
+    /// The Signal server could not be reached, sending again may work
+    NetworkFailure,
+    /// The recipient is not registered on Signal
+    UnregisteredFailure,
+    /// The safety number of the recipient changed, contains the new identity key
+    IdentityFailure(String),
+}
+impl RecipientStatus {
+    pub fn is_success(&self) -> bool {
+        matches!(self, RecipientStatus::Success { .. })
+    }
+}
+impl fmt::Display for RecipientStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecipientStatus::Success { .. } => write!(f, "success"),
+            RecipientStatus::NetworkFailure => write!(f, "network failure"),
+            RecipientStatus::UnregisteredFailure => write!(f, "unregistered"),
+            RecipientStatus::IdentityFailure(_) => write!(f, "identity changed"),
+        }
+    }
 }
 
-// ========================================= LINK ==================================================
-#[derive(Serialize, Deserialize, Default, Clone)]
-pub struct LinkingUri {
-    pub uri: String,
+// ==================================== ACCOUNT STATE ==============================================
+impl WebSocketConnectionState {
+    pub fn is_connected(&self) -> bool {
+        self.state == "CONNECTED"
+    }
 }
-#[derive(Serialize, Deserialize, Default, Clone)]
-pub struct LinkingError {
-    pub msg_number: u32,
-    pub message: String,
-    pub error: bool,
-    pub request: Request,
+
+// ========================================= MESSAGE ===============================================
+impl MessageData {
+    /// The address of the sender
+    /// Combines the source with the uuid that older signald versions send separately
+    pub fn sender(&self) -> Option<SignalAddress> {
+        let uuid = self.uuid.clone();
+        match (self.source.clone(), uuid) {
+            (Some(mut source), Some(uuid)) => {
+                source.merge(&uuid);
+                Some(source)
+            }
+            (source, uuid) => source.or(uuid),
+        }
+    }
+    /// The author and timestamp of the message an edit replaces
+    /// Returns None when the message isn't an edit
+    pub fn edit_target(&self) -> Option<(SignalAddress, i64)> {
+        let edit = self.edit_message.as_ref()?;
+        Some((self.sender()?, edit.target_sent_timestamp))
+    }
 }
-#[derive(Serialize, Deserialize, Default, Clone)]
-pub struct Request {
-    #[serde(rename = "type")]
-    pub typ: String,
-    #[serde(rename = "expiresInSeconds")]
-    pub expires_in_seconds: u32,
-    pub when: u64,
+impl Message {
+    /// The id of the group the message was sent in
+    pub fn group_id(&self) -> Option<&str> {
+        match (&self.group_info, &self.group_v2) {
+            (Some(group), _) => Some(&group.group_id),
+            (None, Some(group)) => Some(&group.id),
+            (None, None) => None,
+        }
+    }
+    /// The attachments can only be viewed once
+    pub fn is_view_once(&self) -> bool {
+        self.view_once.unwrap_or(false)
+    }
+    /// Take the downloaded attachments out of a view-once message, so they can only be consumed once
+    /// Returns no attachments when the message isn't view-once or they were already taken
+    pub fn take_view_once_attachments(&mut self) -> Vec<ViewOnceAttachment> {
+        if !self.is_view_once() {
+            return Vec::new();
+        }
+        self.attachments.take().into_iter().flatten()
+            .filter_map(ViewOnceAttachment::new)
+            .collect()
+    }
 }
 
+
+// ==================================== CONTACT LIST ===============================================
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct ContactListData {
+    #[serde(flatten)]
+    pub contacts: Vec<Account>
+}
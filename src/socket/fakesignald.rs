@@ -0,0 +1,468 @@
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{BufRead, BufReader, Write};
+use std::net::Shutdown;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use crate::signald::Signald;
+
+static SOCKET_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// A fault the fake daemon applies to the response to the next request
+#[derive(Clone, Debug, PartialEq)]
+pub enum Fault {
+    /// Never answer the request
+    DropResponse,
+    /// Wait before answering
+    Delay(Duration),
+    /// Write every response line in chunks of this many bytes
+    Split(usize),
+    /// End the response lines with "\r\n"
+    CrLf,
+    /// Write a line that isn't json before the response
+    Garbage(String),
+    /// Answer with an error of this type and message
+    Error(String, String),
+    /// Close the connection instead of answering
+    Disconnect,
+}
+
+type Handler = Box<dyn Fn(&Value) -> Vec<Value> + Send>;
+
+/// A group as the fake daemon keeps it
+#[derive(Clone, Debug, PartialEq)]
+struct FakeGroup {
+    id: String,
+    name: String,
+    members: Vec<String>,
+    /// The v2 revision, every v1 change adds one
+    revision: i32,
+}
+
+#[derive(Default)]
+struct FakeState {
+    version: String,
+    accounts: Vec<String>,
+    contacts: HashMap<String, Vec<Value>>,
+    groups: HashMap<String, Vec<FakeGroup>>,
+    unregistered: HashSet<String>,
+    handlers: HashMap<String, Handler>,
+    faults: VecDeque<Fault>,
+    requests: Vec<Value>,
+    clients: Vec<Arc<Client>>,
+    group_count: usize,
+}
+
+struct Client {
+    stream: Mutex<UnixStream>,
+    subscriptions: Mutex<HashSet<String>>,
+}
+impl Client {
+    /// Write a response line, applying the fault to how it is written
+    fn write(&self, response: &Value, fault: Option<&Fault>) {
+        let ending = if fault == Some(&Fault::CrLf) { "\r\n" } else { "\n" };
+        let line = format!("{}{}", response, ending);
+        let mut stream = self.stream.lock().unwrap();
+
+        let chunk_size = match fault {
+            Some(Fault::Split(size)) => (*size).max(1),
+            _ => line.len(),
+        };
+        for chunk in line.as_bytes().chunks(chunk_size) {
+            if stream.write_all(chunk).is_err() {
+                return;
+            }
+            if chunk_size < line.len() {
+                let _ = stream.flush();
+                thread::sleep(Duration::from_millis(5));
+            }
+        }
+    }
+}
+
+/// A fake signald daemon on a temporary unix socket
+/// It speaks enough of the signald protocol to test the real socket code without signald:
+/// version, subscribe, send, list_contacts, list_groups, v0 and v1 group updates, link, register and verify.
+/// Responses can be scripted per request type and faults can be injected into the next response.
+pub struct FakeSignald {
+    path: PathBuf,
+    state: Arc<Mutex<FakeState>>,
+}
+impl FakeSignald {
+    /// Start a fake daemon that reports signald 0.13.1
+    pub fn start() -> FakeSignald {
+        let path = std::env::temp_dir().join(format!("signald-rust-fake-{}-{}.sock",
+                                                    std::process::id(), SOCKET_COUNT.fetch_add(1, Ordering::SeqCst)));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        let state = Arc::new(Mutex::new(FakeState {
+            version: "0.13.1".to_string(),
+            ..Default::default()
+        }));
+
+        let accept_state = state.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().map_while(Result::ok) {
+                let client = Arc::new(Client {
+                    stream: Mutex::new(stream.try_clone().unwrap()),
+                    subscriptions: Mutex::new(HashSet::new()),
+                });
+                // The client is known before it's greeted, so a connected Signald gets every broadcast
+                let version = {
+                    let mut state = accept_state.lock().unwrap();
+                    state.clients.push(client.clone());
+                    state.version.clone()
+                };
+                client.write(&version_response(&version), None);
+
+                let client_state = accept_state.clone();
+                thread::spawn(move || serve(stream, client, client_state));
+            }
+        });
+
+        FakeSignald {
+            path,
+            state,
+        }
+    }
+
+    /// The path of the socket
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// Connect a Signald client to the daemon
+    pub fn connect(&self) -> Signald {
+        Signald::connect_path(self.path.to_str().unwrap())
+    }
+
+    /// Set the version reported to new connections
+    pub fn set_version(&self, version: &str) {
+        self.state.lock().unwrap().version = version.to_string();
+    }
+    /// Add an account the daemon manages
+    pub fn add_account(&self, username: &str) {
+        self.state.lock().unwrap().accounts.push(username.to_string());
+    }
+    /// Add a contact to an account
+    pub fn add_contact(&self, username: &str, number: &str, name: &str) {
+        self.state.lock().unwrap().contacts.entry(username.to_string()).or_default()
+            .push(json!({ "name": name, "number": number, "color": "blue" }));
+    }
+    /// Add a group to an account
+    pub fn add_group(&self, username: &str, group_id: &str, name: &str, members: &[&str]) {
+        self.state.lock().unwrap().groups.entry(username.to_string()).or_default().push(FakeGroup {
+            id: group_id.to_string(),
+            name: name.to_string(),
+            members: members.iter().map(|m| m.to_string()).collect(),
+            revision: 1,
+        });
+    }
+    /// Make sending to a number fail as if it isn't registered on Signal
+    pub fn set_unregistered(&self, number: &str) {
+        self.state.lock().unwrap().unregistered.insert(number.to_string());
+    }
+
+    /// Answer every request of a type with the responses the handler returns
+    /// Responses without an id get the id of the request
+    pub fn on<F: Fn(&Value) -> Vec<Value> + Send + 'static>(&self, typ: &str, handler: F) {
+        self.state.lock().unwrap().handlers.insert(typ.to_string(), Box::new(handler));
+    }
+    /// Apply a fault to the response to the next request
+    /// Faults are used in the order they were injected, one for every request
+    pub fn inject(&self, fault: Fault) {
+        self.state.lock().unwrap().faults.push_back(fault);
+    }
+
+    /// Send an incoming message to every client subscribed to the account
+    pub fn deliver(&self, username: &str, source: &str, body: &str) {
+        let timestamp = now_millis();
+        self.deliver_response(username, json!({
+            "type": "message",
+            "data": {
+                "username": username,
+                "source": source,
+                "sourceDevice": 1,
+                "timestamp": timestamp,
+                "serverTimestamp": timestamp,
+                "hasContent": true,
+                "dataMessage": { "timestamp": timestamp, "message": body }
+            }
+        }));
+    }
+    /// Send a response to every client subscribed to the account
+    pub fn deliver_response(&self, username: &str, response: Value) {
+        let clients = self.state.lock().unwrap().clients.clone();
+        for client in clients.iter().filter(|c| c.subscriptions.lock().unwrap().contains(username)) {
+            client.write(&response, None);
+        }
+    }
+    /// Send a response to every connected client
+    pub fn broadcast(&self, response: Value) {
+        let clients = self.state.lock().unwrap().clients.clone();
+        for client in clients {
+            client.write(&response, None);
+        }
+    }
+
+    /// Every request the daemon received
+    pub fn requests(&self) -> Vec<Value> {
+        self.state.lock().unwrap().requests.clone()
+    }
+    /// Wait for a request of a type
+    pub fn wait_for_request(&self, typ: &str, timeout: Duration) -> Option<Value> {
+        let end = Instant::now() + timeout;
+        while Instant::now() < end {
+            if let Some(request) = self.requests().into_iter().find(|r| r["type"] == typ) {
+                return Some(request);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        None
+    }
+}
+impl Drop for FakeSignald {
+    fn drop(&mut self) {
+        for client in &self.state.lock().unwrap().clients {
+            let _ = client.stream.lock().unwrap().shutdown(Shutdown::Both);
+        }
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Read the requests of a client and answer them
+fn serve(stream: UnixStream, client: Arc<Client>, state: Arc<Mutex<FakeState>>) {
+    for line in BufReader::new(stream).lines().map_while(Result::ok) {
+        let request: Value = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                client.write(&json!({ "type": "unexpected_error", "data": { "message": e.to_string() } }), None);
+                continue;
+            }
+        };
+
+        let (responses, fault) = {
+            let mut state = state.lock().unwrap();
+            state.requests.push(request.clone());
+            let fault = state.faults.pop_front();
+            (state.respond(&request, &client), fault)
+        };
+
+        let responses = match &fault {
+            Some(Fault::DropResponse) => continue,
+            Some(Fault::Disconnect) => {
+                let _ = client.stream.lock().unwrap().shutdown(Shutdown::Both);
+                return;
+            }
+            Some(Fault::Delay(delay)) => {
+                thread::sleep(*delay);
+                responses
+            }
+            Some(Fault::Garbage(line)) => {
+                let _ = client.stream.lock().unwrap().write_all(format!("{}\n", line).as_bytes());
+                responses
+            }
+            Some(Fault::Error(typ, message)) => vec![json!({ "type": typ, "data": { "message": message } })],
+            _ => responses,
+        };
+        for mut response in responses {
+            if response.get("id").is_none() && !request["id"].is_null() {
+                response["id"] = request["id"].clone();
+            }
+            client.write(&response, fault.as_ref());
+        }
+    }
+}
+
+impl FakeState {
+    /// The responses signald would send to a request
+    fn respond(&mut self, request: &Value, client: &Client) -> Vec<Value> {
+        let typ = request["type"].as_str().unwrap_or_default();
+        if let Some(handler) = self.handlers.get(typ) {
+            return handler(request);
+        }
+        let v1 = request["version"] == "v1";
+        let username = request["username"].as_str().or_else(|| request["account"].as_str()).unwrap_or_default().to_string();
+
+        match typ {
+            "version" => vec![version_response(&self.version)],
+            "subscribe" => {
+                client.subscriptions.lock().unwrap().insert(username);
+                vec![json!({ "type": "subscribed" })]
+            }
+            "unsubscribe" => {
+                client.subscriptions.lock().unwrap().remove(&username);
+                vec![json!({ "type": "unsubscribed" })]
+            }
+            "send" => {
+                let recipients: Vec<String> = match (request["recipientAddress"]["number"].as_str(), request["recipientGroupId"].as_str()) {
+                    (Some(number), _) => vec![number.to_string()],
+                    (None, Some(group_id)) => self.group(&username, group_id)
+                        .map(|g| g.members.iter().filter(|m| **m != username).cloned().collect())
+                        .unwrap_or_default(),
+                    (None, None) => Vec::new(),
+                };
+                // A group message can be sent to some of the members only
+                let recipients = match request["members"].as_array() {
+                    Some(members) => recipients.into_iter()
+                        .filter(|number| members.iter().any(|m| m["number"].as_str() == Some(number)))
+                        .collect(),
+                    None => recipients,
+                };
+                let results: Vec<Value> = recipients.iter()
+                    .map(|number| match self.unregistered.contains(number) {
+                        true => json!({ "address": { "number": number }, "networkFailure": false, "unregisteredFailure": true }),
+                        false => json!({
+                            "address": { "number": number },
+                            "success": { "unidentified": true, "needsSync": true },
+                            "networkFailure": false,
+                            "unregisteredFailure": false
+                        }),
+                    })
+                    .collect();
+                let data = json!({ "results": results, "timestamp": now_millis() });
+                match v1 {
+                    true => vec![json!({ "type": "send", "version": "v1", "data": data })],
+                    false => vec![json!({ "type": "send_results", "data": data })],
+                }
+            }
+            "list_contacts" => vec![json!({ "type": "contact_list", "data": self.contacts.get(&username).cloned().unwrap_or_default() })],
+            "list_groups" => {
+                let groups: Vec<Value> = self.groups.get(&username).map(|groups| groups.iter().map(group_value).collect()).unwrap_or_default();
+                vec![json!({ "type": "group_list", "data": { "groups": groups } })]
+            }
+            "list_accounts" => {
+                let accounts: Vec<Value> = self.accounts.iter()
+                    .map(|a| json!({ "username": a, "deviceId": 1, "filename": format!("/var/lib/signald/data/{}", a), "registered": true, "has_keys": true, "subscribed": false }))
+                    .collect();
+                vec![json!({ "type": "account_list", "data": { "accounts": accounts } })]
+            }
+            "update_group" | "leave_group" | "approve_membership" | "refuse_membership" if v1 => {
+                let group_id = request["groupID"].as_str().unwrap_or_default();
+                let group = match self.group_mut(&username, group_id) {
+                    Some(group) => group,
+                    None => return vec![unknown_group_v1(typ, group_id)],
+                };
+                group.revision += 1;
+                if let Some(title) = request["title"].as_str() {
+                    group.name = title.to_string();
+                }
+                let data = match typ {
+                    "update_group" | "leave_group" => json!({ "v2": group_v2_value(group) }),
+                    _ => group_v2_value(group),
+                };
+                if typ == "leave_group" {
+                    self.groups.get_mut(&username).unwrap().retain(|g| g.id != group_id);
+                }
+                vec![json!({ "type": typ, "version": "v1", "data": data })]
+            }
+            "update_group" => {
+                let members: Vec<String> = request["members"].as_array().map(|members| members.iter()
+                        .filter_map(|m| m["number"].as_str().or_else(|| m.as_str()).map(|m| m.to_string()))
+                        .collect())
+                    .unwrap_or_default();
+                let name = request["groupName"].as_str();
+                match request["recipientGroupId"].as_str() {
+                    Some(group_id) => match self.group_mut(&username, group_id) {
+                        Some(group) => {
+                            if let Some(name) = name {
+                                group.name = name.to_string();
+                            }
+                            group.members.extend(members);
+                            vec![json!({ "type": "group_updated", "data": group_value(group) })]
+                        }
+                        None => vec![unknown_group(group_id)],
+                    },
+                    None => {
+                        self.group_count += 1;
+                        let group = FakeGroup {
+                            id: format!("ZmFrZWdyb3Vw{}==", self.group_count),
+                            name: name.unwrap_or_default().to_string(),
+                            members: std::iter::once(username.clone()).chain(members).collect(),
+                            revision: 1,
+                        };
+                        let response = json!({ "type": "group_created", "data": group_value(&group) });
+                        self.groups.entry(username).or_default().push(group);
+                        vec![response]
+                    }
+                }
+            }
+            "leave_group" => {
+                let group_id = request["recipientGroupId"].as_str().unwrap_or_default();
+                match self.group(&username, group_id) {
+                    Some(_) => {
+                        self.groups.get_mut(&username).unwrap().retain(|g| g.id != group_id);
+                        vec![json!({ "type": "left_group" })]
+                    }
+                    None => vec![unknown_group(group_id)],
+                }
+            }
+            "get_group" | "accept_invitation" => {
+                let group_id = request["groupID"].as_str().unwrap_or_default();
+                match self.group(&username, group_id) {
+                    Some(group) => vec![json!({ "type": typ, "version": "v1", "data": group_v2_value(group) })],
+                    None => vec![unknown_group_v1(typ, group_id)],
+                }
+            }
+            "block" | "unblock" => vec![json!({ "type": typ, "version": "v1", "data": {} })],
+            "link" => vec![
+                json!({ "type": "linking_uri", "data": { "uri": "tsdevice:/?uuid=ZmFrZXNpZ25hbGRkZXZpY2U&pub_key=QmFzZTY0RmFrZVB1YmxpY0tleUZvclRlc3Rpbmc" } }),
+                json!({ "type": "linking_successful", "data": { "username": self.accounts.first().cloned().unwrap_or_default() } }),
+            ],
+            "register" => vec![json!({ "type": "verification_required", "data": { "username": username } })],
+            "verify" => {
+                self.accounts.push(username.clone());
+                vec![json!({ "type": "verification_succeeded", "data": { "username": username } })]
+            }
+            _ => vec![json!({ "type": "unexpected_error", "data": { "message": format!("unknown request type {}", typ) } })],
+        }
+    }
+
+    fn group(&self, username: &str, group_id: &str) -> Option<&FakeGroup> {
+        self.groups.get(username)?.iter().find(|g| g.id == group_id)
+    }
+    fn group_mut(&mut self, username: &str, group_id: &str) -> Option<&mut FakeGroup> {
+        self.groups.get_mut(username)?.iter_mut().find(|g| g.id == group_id)
+    }
+}
+
+fn version_response(version: &str) -> Value {
+    json!({ "type": "version", "data": { "name": "signald", "version": version, "branch": "fake", "commit": "0000000" } })
+}
+
+fn group_value(group: &FakeGroup) -> Value {
+    json!({
+        "groupId": group.id,
+        "name": group.name,
+        "members": group.members.iter().map(|m| json!({ "number": m })).collect::<Vec<Value>>()
+    })
+}
+
+fn group_v2_value(group: &FakeGroup) -> Value {
+    json!({
+        "id": group.id,
+        "title": group.name,
+        "revision": group.revision,
+        "members": group.members.iter().map(|m| json!({ "number": m })).collect::<Vec<Value>>()
+    })
+}
+
+fn unknown_group_v1(typ: &str, group_id: &str) -> Value {
+    json!({
+        "type": typ,
+        "version": "v1",
+        "error_type": "UnknownGroupError",
+        "error": { "message": format!("unknown group {}", group_id) }
+    })
+}
+
+fn unknown_group(group_id: &str) -> Value {
+    json!({ "type": "input_error", "data": { "message": format!("unknown group {}", group_id) } })
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64
+}
@@ -1,11 +1,31 @@
 use crate::signaldrequest::SignaldRequest;
 use crate::signaldresponse::SignaldResponse;
 use bus::BusReader;
+use std::io;
+use state::SocketState;
 
+#[cfg(any(test, feature = "test-support"))]
+pub mod fakesignald;
+pub mod recording;
+pub mod replay;
 pub mod signaldsocket;
+pub mod state;
 pub mod test;
 
+/// A function called with every raw line a socket receives
+pub type LineHook = Box<dyn FnMut(&str) + Send>;
+
 pub trait Socket {
-    fn send_request(&mut self, request: &SignaldRequest);
+    fn send_request(&mut self, request: &SignaldRequest) -> io::Result<()>;
     fn get_rx(&mut self) -> BusReader<SignaldResponse>;
+    /// The version and account states signald reported on this socket
+    fn state(&self) -> SocketState;
+    /// Change the state, such as the cached block list
+    /// Sockets that don't keep a state ignore the change
+    fn update_state(&mut self, _update: &mut dyn FnMut(&mut SocketState)) {}
+    /// Call a hook with every line received from now on, before it's parsed and broadcast
+    /// Returns false when the socket doesn't read lines, the hook is dropped then
+    fn set_line_hook(&mut self, _hook: LineHook) -> bool {
+        false
+    }
 }
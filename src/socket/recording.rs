@@ -0,0 +1,132 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{self, Write, BufRead, BufReader};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+use bus::BusReader;
+use crate::signaldrequest::SignaldRequest;
+use crate::signaldresponse::SignaldResponse;
+use crate::signaldresponse::ResponseType::{BusUpdate, Version};
+use crate::socket::Socket;
+use crate::socket::state::SocketState;
+
+/// Whether a recorded message was received from or sent to signald
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    #[serde(rename = "in")]
+    In,
+    #[serde(rename = "out")]
+    Out,
+}
+
+/// A single line of a recording
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Record {
+    /// Milliseconds since the recording started
+    pub time: u64,
+    pub direction: Direction,
+    /// The json signald sent or received
+    pub message: Value,
+}
+
+/// Read every record of a recording file
+pub fn read_recording<P: AsRef<Path>>(path: P) -> io::Result<Vec<Record>> {
+    let reader = BufReader::new(File::open(path)?);
+    reader.lines()
+        .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|line| serde_json::from_str(&line?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)))
+        .collect()
+}
+
+/// A socket that writes every message it sends and receives to a recording file
+/// The recording can be fed back into Signald with a ReplaySocket
+pub struct RecordingSocket<S: Socket> {
+    inner: S,
+    writer: Arc<Mutex<RecordWriter>>,
+}
+impl<S: Socket> RecordingSocket<S> {
+    /// Start recording the traffic of a socket to a new file
+    /// Received lines are recorded as signald sent them when the socket reads lines, such as a SignaldSocket
+    pub fn new<P: AsRef<Path>>(mut inner: S, path: P) -> io::Result<RecordingSocket<S>> {
+        let writer = Arc::new(Mutex::new(RecordWriter {
+            file: File::create(path)?,
+            start: Instant::now(),
+        }));
+
+        // The writer stays locked until the greeting is recorded, so a line the hook gets meanwhile comes after it
+        let writer_hook = writer.clone();
+        let mut greeting_writer = writer.lock().unwrap();
+        let hooked = inner.set_line_hook(Box::new(move |line| {
+            // Lines that aren't json are skipped, as the socket does
+            if let Ok(message) = serde_json::from_str(line) {
+                let _ = writer_hook.lock().unwrap().write(Direction::In, message);
+            }
+        }));
+
+        // The version greeting arrives before the recording starts, it's recorded from the state
+        if let Some(version) = inner.state().version {
+            let greeting = SignaldResponse {
+                id: None,
+                defaulted: Vec::new(),
+                data: Version(Some(version)),
+            };
+            greeting_writer.write(Direction::In, greeting.to_value())?;
+        }
+        drop(greeting_writer);
+
+        // Other sockets are recorded from the responses they broadcast
+        if !hooked {
+            let rx = inner.get_rx();
+            let writer_rx = writer.clone();
+            thread::spawn(move || {
+                for response in rx {
+                    if let BusUpdate = response.data { continue; }
+                    if writer_rx.lock().unwrap().write(Direction::In, response.to_value()).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        Ok(RecordingSocket {
+            inner,
+            writer,
+        })
+    }
+}
+impl<S: Socket> Socket for RecordingSocket<S> {
+    fn send_request(&mut self, request: &SignaldRequest) -> io::Result<()> {
+        self.writer.lock().unwrap().write(Direction::Out, serde_json::to_value(request).unwrap())?;
+        self.inner.send_request(request)
+    }
+
+    fn get_rx(&mut self) -> BusReader<SignaldResponse> {
+        self.inner.get_rx()
+    }
+
+    fn state(&self) -> SocketState {
+        self.inner.state()
+    }
+
+    fn update_state(&mut self, update: &mut dyn FnMut(&mut SocketState)) {
+        self.inner.update_state(update)
+    }
+}
+
+struct RecordWriter {
+    file: File,
+    start: Instant,
+}
+impl RecordWriter {
+    fn write(&mut self, direction: Direction, message: Value) -> io::Result<()> {
+        let record = Record {
+            time: self.start.elapsed().as_millis() as u64,
+            direction,
+            message,
+        };
+        writeln!(self.file, "{}", serde_json::to_string(&record).unwrap())
+    }
+}
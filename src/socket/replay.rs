@@ -0,0 +1,146 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use bus::{Bus, BusReader};
+use crate::signaldrequest::SignaldRequest;
+use crate::signaldresponse::{SignaldResponse, ParseMode};
+use crate::signaldresponse::ResponseType::BusUpdate;
+use crate::socket::Socket;
+use crate::socket::recording::{Record, Direction, read_recording};
+use crate::socket::state::SocketState;
+
+/// A socket that plays a recording back instead of talking to signald
+/// Received messages are sent in the recorded order, every recorded request waits until the client
+/// sends its next request. The ids of the recorded responses are changed to the ids the client uses.
+/// Nothing is broadcast before the client has a receiver, so no recorded message is missed.
+pub struct ReplaySocket {
+    bus: Arc<Mutex<Bus<SignaldResponse>>>,
+    state: Arc<Mutex<SocketState>>,
+    requests: mpsc::Sender<SignaldRequest>,
+    receivers: mpsc::Sender<()>,
+    sent: Arc<Mutex<Vec<SignaldRequest>>>,
+}
+impl ReplaySocket {
+    /// Replay a recording file
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<ReplaySocket> {
+        Ok(ReplaySocket::new(read_recording(path)?, ParseMode::default(), Duration::from_millis(100)))
+    }
+
+    /// Replay recorded messages
+    /// The recorded time between two received messages is kept, up to max_delay
+    pub fn new(records: Vec<Record>, parse_mode: ParseMode, max_delay: Duration) -> ReplaySocket {
+        let bus = Arc::new(Mutex::new(Bus::new(100)));
+        let state = Arc::new(Mutex::new(SocketState::default()));
+        let (requests, requests_rx) = mpsc::channel::<SignaldRequest>();
+        let (receivers, receivers_rx) = mpsc::channel::<()>();
+
+        // The version greeting is in the state at once, so a client waiting for it doesn't need a receiver
+        if let Some(record) = records.first().filter(|r| r.direction == Direction::In && r.message["type"] == "version") {
+            state.lock().unwrap().update(&SignaldResponse::from_value_with_mode(record.message.clone(), parse_mode));
+        }
+
+        let bus_tx = bus.clone();
+        let state_tx = state.clone();
+        thread::spawn(move || {
+            // The recorded ids mapped to the ids of the replayed requests
+            let mut ids: HashMap<String, String> = HashMap::new();
+            let mut last_time = None;
+            let mut listening = false;
+
+            for record in records {
+                match record.direction {
+                    Direction::Out => {
+                        let request = match requests_rx.recv() {
+                            Ok(request) => request,
+                            Err(_) => return,
+                        };
+                        if let (Some(recorded), Some(id)) = (record.message["id"].as_str(), request.id) {
+                            ids.insert(recorded.to_string(), id);
+                        }
+                        last_time = None;
+                    }
+                    Direction::In => {
+                        if let Some(last_time) = last_time {
+                            thread::sleep(Duration::from_millis(record.time.saturating_sub(last_time)).min(max_delay));
+                        }
+                        last_time = Some(record.time);
+
+                        let mut message = record.message;
+                        if let Some(id) = message["id"].as_str().and_then(|id| ids.get(id)) {
+                            message["id"] = Value::String(id.clone());
+                        }
+                        let response = SignaldResponse::from_value_with_mode(message, parse_mode);
+                        let blocked = {
+                            let mut state = state_tx.lock().unwrap();
+                            state.update(&response);
+                            state.drop_blocked && state.is_blocked(&response)
+                        };
+                        if !blocked {
+                            // The version greeting is already in the state, the other messages wait for a receiver
+                            if !listening {
+                                if receivers_rx.recv().is_err() {
+                                    return;
+                                }
+                                listening = true;
+                            }
+                            bus_tx.lock().unwrap().broadcast(response);
+                        }
+                    }
+                }
+            }
+        });
+
+        // An update message every second so receivers can stop waiting, as on the signald socket
+        let bus_tx_seconds = bus.clone();
+        let update_response = SignaldResponse {
+            id: None,
+            defaulted: Vec::new(),
+            data: BusUpdate
+        };
+        thread::spawn(move || {
+            loop {
+                thread::sleep(Duration::from_secs(1));
+                bus_tx_seconds.lock().unwrap().broadcast(update_response.clone());
+            }
+        });
+
+        ReplaySocket {
+            bus,
+            state,
+            requests,
+            receivers,
+            sent: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// A handle to every request the client sent, to compare them with the recorded requests
+    pub fn sent_requests(&self) -> Arc<Mutex<Vec<SignaldRequest>>> {
+        self.sent.clone()
+    }
+}
+impl Socket for ReplaySocket {
+    fn send_request(&mut self, request: &SignaldRequest) -> io::Result<()> {
+        self.sent.lock().unwrap().push(request.clone());
+        // The recording may have ended, further requests get no response
+        let _ = self.requests.send(request.clone());
+        Ok(())
+    }
+
+    fn get_rx(&mut self) -> BusReader<SignaldResponse> {
+        let rx = self.bus.lock().unwrap().add_rx();
+        let _ = self.receivers.send(());
+        rx
+    }
+
+    fn state(&self) -> SocketState {
+        self.state.lock().unwrap().clone()
+    }
+
+    fn update_state(&mut self, update: &mut dyn FnMut(&mut SocketState)) {
+        update(&mut self.state.lock().unwrap());
+    }
+}
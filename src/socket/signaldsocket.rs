@@ -1,25 +1,34 @@
 use std::sync::{Arc, Mutex};
 use std::os::unix::net::UnixStream;
 use std::thread;
-use std::io::{Write, BufReader, BufRead};
+use std::io::{self, Write, BufReader, BufRead};
 use bus::{Bus, BusReader};
 use std::time::Duration;
-use crate::signaldresponse::SignaldResponse;
+use crate::signaldresponse::{SignaldResponse, ParseMode};
 use crate::signaldresponse::ResponseType::BusUpdate;
 use crate::signaldrequest::SignaldRequest;
-use crate::socket::Socket;
+use crate::socket::{Socket, LineHook};
+use crate::socket::state::SocketState;
 
 #[allow(dead_code)]
 pub struct SignaldSocket {
     socket_path: String,
     socket: UnixStream,
     bus: Arc<Mutex<Bus<SignaldResponse>>>,
+    // The version and account states signald sent
+    state: Arc<Mutex<SocketState>>,
+    // Called with every received line, such as by a recording socket
+    line_hook: Arc<Mutex<Option<LineHook>>>,
 }
 impl SignaldSocket {
     pub fn connect(socket_path: String, bus_size: usize) -> SignaldSocket {
+        SignaldSocket::connect_with_mode(socket_path, bus_size, ParseMode::default())
+    }
+    /// Connect the socket and parse its responses with the given parse mode
+    pub fn connect_with_mode(socket_path: String, bus_size: usize, parse_mode: ParseMode) -> SignaldSocket {
 
         // Connect the socket
-        let socket = match UnixStream::connect(socket_path.to_string()) {
+        let socket = match UnixStream::connect(&socket_path) {
             Ok(stream) => {
                 stream
             }
@@ -32,18 +41,35 @@ impl SignaldSocket {
         // Create a bus
         let bus = Arc::new(Mutex::new(Bus::new(bus_size)));
 
+        // Signald greets every new connection with its version
+        // This is kept in the state because it arrives before any receiver is listening on the bus
+        let state = Arc::new(Mutex::new(SocketState::default()));
+        let line_hook: Arc<Mutex<Option<LineHook>>> = Arc::new(Mutex::new(None));
+
         // Broadcast on the bus in a new thread
         let bus_tx = bus.clone();
+        let state_tx = state.clone();
+        let line_hook_tx = line_hook.clone();
         thread::spawn(move || {
             let reader = BufReader::new(socket);
-            for line in reader.lines() {
-                match line {
-                    Ok(l) => {
-                        let val = serde_json::from_str(&l).unwrap();
-                        let res: SignaldResponse = SignaldResponse::from_value(val);
+            for l in reader.lines().map_while(Result::ok) {
+                // The hook stays locked until the state is updated, a new hook either sees the line or its state
+                let mut hook = line_hook_tx.lock().unwrap();
+                if let Some(hook) = hook.as_mut() {
+                    hook(&l);
+                }
+                // Skip lines that aren't json instead of stopping the reader
+                if let Ok(val) = serde_json::from_str(&l) {
+                    let res: SignaldResponse = SignaldResponse::from_value_with_mode(val, parse_mode);
+                    let blocked = {
+                        let mut state = state_tx.lock().unwrap();
+                        state.update(&res);
+                        state.drop_blocked && state.is_blocked(&res)
+                    };
+                    drop(hook);
+                    if !blocked {
                         bus_tx.lock().unwrap().broadcast(res);
-                    },
-                    Err(_) => {}
+                    }
                 }
             }
         });
@@ -54,6 +80,7 @@ impl SignaldSocket {
         let bus_tx_seconds = bus.clone();
         let update_response = SignaldResponse {
             id: None,
+            defaulted: Vec::new(),
             data: BusUpdate
         };
         thread::spawn(move || {
@@ -65,24 +92,34 @@ impl SignaldSocket {
 
 
         Self {
-            socket_path: socket_path,
+            socket_path,
             socket: socket_clone,
-            bus: bus,
+            bus,
+            state,
+            line_hook,
         }
     }
 }
 impl Socket for SignaldSocket {
-    fn send_request(&mut self, request: &SignaldRequest) {
+    fn send_request(&mut self, request: &SignaldRequest) -> io::Result<()> {
         let formatted_request = request.to_json_string() + "\n";
-        match self.socket.write_all(formatted_request.as_bytes()) {
-            Err(_) => panic!("Failed to send message"),
-            Ok(_) => {
-                //println!("mesg sent {}", formatted_request);
-            }
-        }
+        self.socket.write_all(formatted_request.as_bytes())
     }
 
     fn get_rx(&mut self) -> BusReader<SignaldResponse> {
         self.bus.lock().unwrap().add_rx()
     }
+
+    fn state(&self) -> SocketState {
+        self.state.lock().unwrap().clone()
+    }
+
+    fn update_state(&mut self, update: &mut dyn FnMut(&mut SocketState)) {
+        update(&mut self.state.lock().unwrap());
+    }
+
+    fn set_line_hook(&mut self, hook: LineHook) -> bool {
+        *self.line_hook.lock().unwrap() = Some(hook);
+        true
+    }
 }
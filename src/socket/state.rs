@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::time::Instant;
+use crate::signaladdress::SignalAddress;
+use crate::signaldresponse::{SignaldResponse, ResponseType, VersionData, WebSocketConnectionState, MessageData};
+
+/// What the socket learned from the events signald sent on it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SocketState {
+    /// The last version signald reported, starting with the greeting it sends on connect
+    pub version: Option<VersionData>,
+    /// The health of every account signald sent events about
+    pub accounts: HashMap<String, AccountHealth>,
+    /// The block list of every account, from sync messages and the block requests sent on this socket
+    pub blocked: HashMap<String, BlockList>,
+    /// Drop messages from blocked users and groups before they reach the receivers
+    pub drop_blocked: bool,
+}
+impl SocketState {
+    /// Apply a received response to the state
+    pub fn update(&mut self, response: &SignaldResponse) {
+        if let ResponseType::Version(Some(version)) = &response.data {
+            self.version = Some(version.clone());
+        }
+
+        if let Some(account) = response.data.account() {
+            self.accounts.entry(account.to_string())
+                .or_insert_with(|| AccountHealth::new(account.to_string()))
+                .update(&response.data);
+        }
+
+        // Another device of the account sends the whole block list when it changes
+        if let ResponseType::Message(Some(message)) = &response.data {
+            let blocked_list = message.sync_message.as_ref().and_then(|sync| sync.blocked_list.as_ref());
+            if let (Some(account), Some(blocked_list)) = (&message.username, blocked_list) {
+                let block_list = BlockList {
+                    addresses: blocked_list.numbers.clone(),
+                    group_ids: blocked_list.group_ids.clone(),
+                };
+                self.blocked.insert(account.clone(), block_list);
+            }
+        }
+    }
+
+    /// Check if a response is a message from a user or group its account blocked
+    pub fn is_blocked(&self, response: &SignaldResponse) -> bool {
+        match &response.data {
+            ResponseType::Message(Some(message)) => message.username.as_ref()
+                .and_then(|account| self.blocked.get(account))
+                .map(|block_list| block_list.is_blocked(message))
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+}
+
+/// The users and groups an account blocked
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BlockList {
+    pub addresses: Vec<SignalAddress>,
+    pub group_ids: Vec<String>,
+}
+impl BlockList {
+    /// Check if a message was sent by a blocked user or in a blocked group
+    pub fn is_blocked(&self, message: &MessageData) -> bool {
+        let sender_blocked = message.sender().map(|sender| self.addresses.contains(&sender)).unwrap_or(false);
+        let group_blocked = message.data_message.as_ref()
+            .and_then(|m| m.group_id())
+            .map(|group_id| self.group_ids.iter().any(|g| g == group_id))
+            .unwrap_or(false);
+        sender_blocked || group_blocked
+    }
+
+    pub fn block_address(&mut self, address: SignalAddress) {
+        if !self.addresses.contains(&address) {
+            self.addresses.push(address);
+        }
+    }
+    pub fn unblock_address(&mut self, address: &SignalAddress) {
+        self.addresses.retain(|a| a != address);
+    }
+    pub fn block_group(&mut self, group_id: String) {
+        if !self.group_ids.contains(&group_id) {
+            self.group_ids.push(group_id);
+        }
+    }
+    pub fn unblock_group(&mut self, group_id: &str) {
+        self.group_ids.retain(|g| g != group_id);
+    }
+}
+
+/// The connection health of an account
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AccountHealth {
+    pub username: String,
+    /// Signald is receiving messages for the account
+    pub listening: bool,
+    /// The last reported connection to the Signal server
+    pub connection: Option<WebSocketConnectionState>,
+    /// When signald last refreshed the account
+    pub last_refreshed: Option<Instant>,
+}
+impl AccountHealth {
+    pub fn new(username: String) -> AccountHealth {
+        AccountHealth {
+            username,
+            ..Default::default()
+        }
+    }
+
+    /// Apply an account state event
+    /// Returns false when the event isn't about this account
+    pub fn update(&mut self, response: &ResponseType) -> bool {
+        if response.account() != Some(self.username.as_str()) {
+            return false;
+        }
+
+        match response {
+            ResponseType::ListenStarted(_) => self.listening = true,
+            ResponseType::ListenStopped(_) => self.listening = false,
+            ResponseType::AccountRefreshed(_) => self.last_refreshed = Some(Instant::now()),
+            ResponseType::WebSocketConnectionState(state) => self.connection = state.clone(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Signald is receiving messages and the connection isn't reported to be down
+    pub fn is_healthy(&self) -> bool {
+        self.listening && self.connection.as_ref().map(|c| c.is_connected()).unwrap_or(true)
+    }
+}
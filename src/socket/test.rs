@@ -1,10 +1,12 @@
 use std::sync::{Arc, Mutex};
 use bus::{Bus, BusReader};
 use crate::signaldresponse::SignaldResponse;
+use crate::socket::state::SocketState;
 use crate::signaldrequest::SignaldRequest;
 use crate::socket::Socket;
 use crate::signaldresponse::ResponseType::BusUpdate;
 use std::thread;
+use std::io;
 use std::time::Duration;
 
 #[allow(dead_code)]
@@ -21,6 +23,7 @@ impl TestSignaldSocket {
         let bus_tx_seconds = bus.clone();
         let update_response = SignaldResponse {
             id: None,
+            defaulted: Vec::new(),
             data: BusUpdate
         };
         thread::spawn(move || {
@@ -40,11 +43,15 @@ impl TestSignaldSocket {
     }
 }
 impl Socket for TestSignaldSocket {
-    fn send_request(&mut self, _request: &SignaldRequest) {
+    fn send_request(&mut self, _request: &SignaldRequest) -> io::Result<()> {
         unimplemented!()
     }
 
     fn get_rx(&mut self) -> BusReader<SignaldResponse> {
         self.bus.lock().unwrap().add_rx()
     }
+
+    fn state(&self) -> SocketState {
+        SocketState::default()
+    }
 }
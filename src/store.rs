@@ -0,0 +1,449 @@
+use rusqlite::{Connection, Row, ToSql, params};
+use rusqlite::types::{Type, Value as SqlValue};
+use std::path::Path;
+use crate::conversation::Conversation;
+use crate::signaldrequest::Recipient;
+use crate::signaldresponse::{SignaldResponse, ResponseType, MessageData, Message, Receipt};
+use crate::signaladdress::SignalAddress;
+use crate::v1::JsonReaction;
+
+pub use rusqlite::Error;
+pub type Result<T> = rusqlite::Result<T>;
+
+static SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS messages (
+    id INTEGER PRIMARY KEY,
+    account TEXT NOT NULL,
+    group_id TEXT,
+    peer_number TEXT,
+    peer_uuid TEXT,
+    sender TEXT NOT NULL,
+    sender_number TEXT,
+    sender_uuid TEXT,
+    timestamp INTEGER NOT NULL,
+    body TEXT NOT NULL,
+    outgoing INTEGER NOT NULL,
+    deleted INTEGER NOT NULL DEFAULT 0,
+    edited_at INTEGER,
+    delivered_at INTEGER,
+    read_at INTEGER,
+    data TEXT NOT NULL,
+    UNIQUE(account, sender, timestamp)
+);
+CREATE INDEX IF NOT EXISTS messages_timestamp ON messages(account, timestamp);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(body, content='messages', content_rowid='id');
+CREATE TRIGGER IF NOT EXISTS messages_fts_insert AFTER INSERT ON messages BEGIN
+    INSERT INTO messages_fts(rowid, body) VALUES (new.id, new.body);
+END;
+CREATE TRIGGER IF NOT EXISTS messages_fts_delete AFTER DELETE ON messages BEGIN
+    INSERT INTO messages_fts(messages_fts, rowid, body) VALUES ('delete', old.id, old.body);
+END;
+CREATE TRIGGER IF NOT EXISTS messages_fts_update AFTER UPDATE OF body ON messages BEGIN
+    INSERT INTO messages_fts(messages_fts, rowid, body) VALUES ('delete', old.id, old.body);
+    INSERT INTO messages_fts(rowid, body) VALUES (new.id, new.body);
+END;
+
+CREATE TABLE IF NOT EXISTS reactions (
+    account TEXT NOT NULL,
+    target_author TEXT NOT NULL,
+    target_timestamp INTEGER NOT NULL,
+    reactor TEXT NOT NULL,
+    emoji TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    UNIQUE(account, target_author, target_timestamp, reactor)
+);
+
+CREATE TABLE IF NOT EXISTS edits (
+    account TEXT NOT NULL,
+    author TEXT NOT NULL,
+    target_timestamp INTEGER NOT NULL,
+    body TEXT NOT NULL,
+    edited_at INTEGER NOT NULL
+);
+";
+
+/// A message kept in the store
+#[derive(Clone, Debug, PartialEq)]
+pub struct StoredMessage {
+    pub id: i64,
+    pub account: String,
+    /// The group the message was sent in
+    pub group_id: Option<String>,
+    /// The other user of a 1:1 conversation
+    pub peer: Option<SignalAddress>,
+    pub sender: SignalAddress,
+    /// The timestamp the sender gave the message, reactions, receipts and deletes refer to it
+    pub timestamp: i64,
+    /// The current text, empty once the message is deleted
+    pub body: String,
+    /// The message was sent by the account
+    pub outgoing: bool,
+    pub deleted: bool,
+    pub edited_at: Option<i64>,
+    pub delivered_at: Option<i64>,
+    pub read_at: Option<i64>,
+    /// The message as signald sent it
+    pub data: MessageData,
+}
+impl StoredMessage {
+    /// The conversation the message belongs to
+    pub fn conversation(&self) -> Conversation {
+        let recipient = match (&self.group_id, &self.peer) {
+            (Some(group_id), _) => Recipient::Group(group_id.clone()),
+            (None, Some(peer)) => Recipient::Address(peer.clone()),
+            (None, None) => Recipient::Address(self.sender.clone()),
+        };
+        Conversation {
+            account: self.account.clone(),
+            recipient,
+            sender: Some(self.sender.clone()),
+        }
+    }
+
+    fn from_row(row: &Row) -> Result<StoredMessage> {
+        let data: String = row.get("data")?;
+        let sender_number: Option<String> = row.get("sender_number")?;
+        let sender_uuid: Option<String> = row.get("sender_uuid")?;
+        // Every message is stored with its sender, a row without one is corrupt
+        let sender = SignalAddress::new(sender_number.as_deref(), sender_uuid.as_deref())
+            .map_err(|e| Error::FromSqlConversionFailure(row.as_ref().column_index("sender_number").unwrap_or(0), Type::Null, Box::new(e)))?;
+        Ok(StoredMessage {
+            id: row.get("id")?,
+            account: row.get("account")?,
+            group_id: row.get("group_id")?,
+            peer: address(row.get("peer_number")?, row.get("peer_uuid")?),
+            sender,
+            timestamp: row.get("timestamp")?,
+            body: row.get("body")?,
+            outgoing: row.get("outgoing")?,
+            deleted: row.get("deleted")?,
+            edited_at: row.get("edited_at")?,
+            delivered_at: row.get("delivered_at")?,
+            read_at: row.get("read_at")?,
+            data: serde_json::from_str(&data).unwrap_or_default(),
+        })
+    }
+}
+
+/// A reaction to a stored message
+#[derive(Clone, Debug, PartialEq)]
+pub struct StoredReaction {
+    pub reactor: String,
+    pub emoji: String,
+    pub timestamp: i64,
+}
+
+/// Which messages to return from the store, every set field has to match
+#[derive(Clone, Debug, Default)]
+pub struct MessageQuery {
+    pub account: Option<String>,
+    pub conversation: Option<Conversation>,
+    pub sender: Option<SignalAddress>,
+    /// Only messages sent at or after this timestamp
+    pub since: Option<i64>,
+    /// Only messages sent before this timestamp
+    pub until: Option<i64>,
+    /// A full text search query in the sqlite FTS5 syntax
+    pub text: Option<String>,
+    pub limit: Option<u32>,
+}
+impl MessageQuery {
+    pub fn new() -> MessageQuery {
+        MessageQuery::default()
+    }
+    pub fn account(mut self, account: &str) -> MessageQuery {
+        self.account = Some(account.to_string());
+        self
+    }
+    pub fn conversation(mut self, conversation: &Conversation) -> MessageQuery {
+        self.conversation = Some(conversation.clone());
+        self
+    }
+    pub fn sender(mut self, sender: &SignalAddress) -> MessageQuery {
+        self.sender = Some(sender.clone());
+        self
+    }
+    pub fn between(mut self, since: i64, until: i64) -> MessageQuery {
+        self.since = Some(since);
+        self.until = Some(until);
+        self
+    }
+    pub fn text(mut self, text: &str) -> MessageQuery {
+        self.text = Some(text.to_string());
+        self
+    }
+    pub fn limit(mut self, limit: u32) -> MessageQuery {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Build the where clause and its parameters
+    fn to_sql(&self) -> (String, Vec<SqlValue>) {
+        let mut conditions = vec!["1 = 1".to_string()];
+        let mut values = Vec::new();
+
+        let account = self.account.clone().or_else(|| self.conversation.as_ref().map(|c| c.account.clone()));
+        if let Some(account) = account {
+            conditions.push("account = ?".to_string());
+            values.push(SqlValue::Text(account));
+        }
+        if let Some(conversation) = &self.conversation {
+            match &conversation.recipient {
+                Recipient::Group(group_id) => {
+                    conditions.push("group_id = ?".to_string());
+                    values.push(SqlValue::Text(group_id.clone()));
+                }
+                Recipient::Address(peer) => {
+                    conditions.push("group_id IS NULL".to_string());
+                    push_address(&mut conditions, &mut values, "peer", peer);
+                }
+            }
+            if let Some(sender) = &conversation.sender {
+                // The account's own messages are part of a 1:1 conversation as well
+                if let Recipient::Group(_) = conversation.recipient {
+                    push_address(&mut conditions, &mut values, "sender", sender);
+                }
+            }
+        }
+        if let Some(sender) = &self.sender {
+            push_address(&mut conditions, &mut values, "sender", sender);
+        }
+        if let Some(since) = self.since {
+            conditions.push("timestamp >= ?".to_string());
+            values.push(SqlValue::Integer(since));
+        }
+        if let Some(until) = self.until {
+            conditions.push("timestamp < ?".to_string());
+            values.push(SqlValue::Integer(until));
+        }
+        if let Some(text) = &self.text {
+            conditions.push("id IN (SELECT rowid FROM messages_fts WHERE messages_fts MATCH ?)".to_string());
+            values.push(SqlValue::Text(text.clone()));
+        }
+
+        let mut sql = format!("SELECT * FROM messages WHERE {} ORDER BY timestamp", conditions.join(" AND "));
+        if let Some(limit) = self.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+        (sql, values)
+    }
+}
+
+/// Match a stored address on its number or uuid
+fn push_address(conditions: &mut Vec<String>, values: &mut Vec<SqlValue>, column: &str, address: &SignalAddress) {
+    conditions.push(format!("({0}_number IS ? OR {0}_uuid IS ?)", column));
+    values.push(address.number().map(|n| SqlValue::Text(n.to_string())).unwrap_or(SqlValue::Text(String::new())));
+    values.push(address.uuid().map(|u| SqlValue::Text(u.to_string())).unwrap_or(SqlValue::Text(String::new())));
+}
+
+fn address(number: Option<String>, uuid: Option<String>) -> Option<SignalAddress> {
+    SignalAddress::new(number.as_deref(), uuid.as_deref()).ok()
+}
+
+/// Stores received and sent messages in a SQLite database
+pub struct MessageStore {
+    connection: Connection,
+}
+impl MessageStore {
+    /// Open or create a store at a path
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<MessageStore> {
+        MessageStore::init(Connection::open(path)?)
+    }
+    /// Create a store that only lives in memory
+    pub fn open_in_memory() -> Result<MessageStore> {
+        MessageStore::init(Connection::open_in_memory()?)
+    }
+    fn init(connection: Connection) -> Result<MessageStore> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(MessageStore {
+            connection,
+        })
+    }
+
+    /// Store everything a response carries
+    /// Messages are inserted, reactions, receipts, edits and deletes are applied to the stored messages
+    /// Responses that aren't messages are ignored
+    pub fn record(&self, response: &SignaldResponse) -> Result<()> {
+        let data = match &response.data {
+            ResponseType::Message(Some(data)) => data,
+            _ => return Ok(()),
+        };
+        let account = match &data.username {
+            Some(account) => account.as_str(),
+            None => return Ok(()),
+        };
+
+        if let Some(receipt) = &data.receipt {
+            self.apply_receipt(account, receipt)?;
+        }
+        if let (Some(message), Some(sender)) = (&data.data_message, data.sender()) {
+            if let Some(reaction) = &message.reaction {
+                self.apply_reaction(account, &sender, reaction, message.timestamp)?;
+            } else if let Some(delete) = &message.remote_delete {
+                self.apply_delete(account, &sender, delete.target_sent_timestamp)?;
+            } else {
+                let peer = if message.group_id().is_none() { Some(sender.clone()) } else { None };
+                self.insert(account, &sender, peer.as_ref(), message, false, data)?;
+            }
+        }
+        if let (Some(edit), Some(sender)) = (&data.edit_message, data.sender()) {
+            self.apply_edit(account, &sender, edit.target_sent_timestamp, &edit.data_message.message, edit.data_message.timestamp)?;
+        }
+        // A sent message is only stored when the account name is a valid address to store it under
+        let sent = data.sync_message.as_ref().and_then(|sync| sync.sent.as_ref());
+        if let (Some(sent), Ok(own)) = (sent, account.parse::<SignalAddress>()) {
+            match &sent.edit_message {
+                Some(edit) => self.apply_edit(account, &own, edit.target_sent_timestamp, &edit.data_message.message, edit.data_message.timestamp)?,
+                None => self.insert(account, &own, sent.destination.as_ref(), &sent.message, true, data)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn insert(&self, account: &str, sender: &SignalAddress, peer: Option<&SignalAddress>, message: &Message,
+              outgoing: bool, data: &MessageData) -> Result<()> {
+        self.connection.execute(
+            "INSERT OR IGNORE INTO messages (account, group_id, peer_number, peer_uuid, sender, sender_number, sender_uuid,
+                                             timestamp, body, outgoing, data)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                account,
+                message.group_id(),
+                peer.and_then(|p| p.number()),
+                peer.and_then(|p| p.uuid()),
+                sender.to_string(),
+                sender.number(),
+                sender.uuid(),
+                message.timestamp,
+                message.message,
+                outgoing,
+                serde_json::to_string(data).unwrap(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Mark the account's sent messages as delivered or read
+    pub fn apply_receipt(&self, account: &str, receipt: &Receipt) -> Result<()> {
+        let column = match receipt.typ.as_str() {
+            "DELIVERY" => "delivered_at",
+            "READ" => "read_at",
+            _ => return Ok(()),
+        };
+        let sql = format!("UPDATE messages SET {0} = COALESCE({0}, ?1) WHERE account = ?2 AND outgoing = 1 AND timestamp = ?3", column);
+        for timestamp in &receipt.timestamps {
+            self.connection.execute(&sql, params![receipt.when, account, timestamp])?;
+        }
+        Ok(())
+    }
+
+    /// Delete the text of a message its sender deleted
+    pub fn apply_delete(&self, account: &str, author: &SignalAddress, target_timestamp: i64) -> Result<()> {
+        self.update_message(account, author, target_timestamp, "deleted = 1, body = ''", &[])
+    }
+
+    /// Replace the text of an edited message, every edit is kept in the edit history
+    pub fn apply_edit(&self, account: &str, author: &SignalAddress, target_timestamp: i64, body: &str, edited_at: i64) -> Result<()> {
+        self.connection.execute(
+            "INSERT INTO edits (account, author, target_timestamp, body, edited_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![account, self.stored_sender(account, author, target_timestamp)?, target_timestamp, body, edited_at],
+        )?;
+        self.update_message(account, author, target_timestamp, "body = ?, edited_at = ?",
+                            &[SqlValue::Text(body.to_string()), SqlValue::Integer(edited_at)])
+    }
+
+    /// Add a reaction to a message, or remove it
+    /// A new reaction of the same user replaces the earlier one
+    pub fn apply_reaction(&self, account: &str, reactor: &SignalAddress, reaction: &JsonReaction, timestamp: i64) -> Result<()> {
+        // Without its author the message reacted to can't be found
+        let target_author = match &reaction.target_author {
+            Some(author) => self.stored_sender(account, author, reaction.target_sent_timestamp)?,
+            None => return Ok(()),
+        };
+        if reaction.remove {
+            self.connection.execute(
+                "DELETE FROM reactions WHERE account = ?1 AND target_author = ?2 AND target_timestamp = ?3 AND reactor = ?4",
+                params![account, target_author, reaction.target_sent_timestamp, reactor.to_string()],
+            )?;
+        } else {
+            self.connection.execute(
+                "INSERT OR REPLACE INTO reactions (account, target_author, target_timestamp, reactor, emoji, timestamp)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![account, target_author, reaction.target_sent_timestamp, reactor.to_string(), reaction.emoji, timestamp],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// The sender a message was stored with, the address may only have one of the number and uuid it was stored with
+    fn stored_sender(&self, account: &str, author: &SignalAddress, timestamp: i64) -> Result<String> {
+        let mut statement = self.connection.prepare(
+            "SELECT sender FROM messages WHERE account = ?1 AND timestamp = ?2 AND (sender_number IS ?3 OR sender_uuid IS ?4)")?;
+        let mut senders = statement.query_map(params![account, timestamp, author.number().unwrap_or_default(), author.uuid().unwrap_or_default()],
+                                              |row| row.get(0))?;
+        senders.next().unwrap_or_else(|| Ok(author.to_string()))
+    }
+
+    fn update_message(&self, account: &str, author: &SignalAddress, target_timestamp: i64, set: &str, values: &[SqlValue]) -> Result<()> {
+        let mut conditions = vec!["account = ?".to_string(), "timestamp = ?".to_string()];
+        let mut params: Vec<SqlValue> = values.to_vec();
+        params.push(SqlValue::Text(account.to_string()));
+        params.push(SqlValue::Integer(target_timestamp));
+        push_address(&mut conditions, &mut params, "sender", author);
+
+        let sql = format!("UPDATE messages SET {} WHERE {}", set, conditions.join(" AND "));
+        let params: Vec<&dyn ToSql> = params.iter().map(|p| p as &dyn ToSql).collect();
+        self.connection.execute(&sql, params.as_slice())?;
+        Ok(())
+    }
+
+    /// Get the stored messages matching a query, oldest first
+    pub fn query(&self, query: &MessageQuery) -> Result<Vec<StoredMessage>> {
+        let (sql, values) = query.to_sql();
+        let params: Vec<&dyn ToSql> = values.iter().map(|p| p as &dyn ToSql).collect();
+        let mut statement = self.connection.prepare(&sql)?;
+        let messages = statement.query_map(params.as_slice(), StoredMessage::from_row)?;
+        messages.collect()
+    }
+    /// Get the messages of a conversation
+    pub fn by_conversation(&self, conversation: &Conversation) -> Result<Vec<StoredMessage>> {
+        self.query(&MessageQuery::new().conversation(conversation))
+    }
+    /// Get the messages an account received from a sender
+    pub fn by_sender(&self, account: &str, sender: &SignalAddress) -> Result<Vec<StoredMessage>> {
+        self.query(&MessageQuery::new().account(account).sender(sender))
+    }
+    /// Get the messages of an account sent in a time range
+    pub fn between(&self, account: &str, since: i64, until: i64) -> Result<Vec<StoredMessage>> {
+        self.query(&MessageQuery::new().account(account).between(since, until))
+    }
+    /// Search the text of the messages of an account
+    pub fn search(&self, account: &str, text: &str) -> Result<Vec<StoredMessage>> {
+        self.query(&MessageQuery::new().account(account).text(text))
+    }
+
+    /// Get the reactions to a message
+    pub fn reactions(&self, message: &StoredMessage) -> Result<Vec<StoredReaction>> {
+        let mut statement = self.connection.prepare(
+            "SELECT reactor, emoji, timestamp FROM reactions WHERE account = ?1 AND target_author = ?2 AND target_timestamp = ?3
+             ORDER BY timestamp")?;
+        let reactions = statement.query_map(params![message.account, message.sender.to_string(), message.timestamp], |row| {
+            Ok(StoredReaction {
+                reactor: row.get(0)?,
+                emoji: row.get(1)?,
+                timestamp: row.get(2)?,
+            })
+        })?;
+        reactions.collect()
+    }
+    /// Get the edit history of a message as the time and text of every edit, oldest first
+    pub fn edits(&self, message: &StoredMessage) -> Result<Vec<(i64, String)>> {
+        let mut statement = self.connection.prepare(
+            "SELECT edited_at, body FROM edits WHERE account = ?1 AND author = ?2 AND target_timestamp = ?3 ORDER BY edited_at")?;
+        let edits = statement.query_map(params![message.account, message.sender.to_string(), message.timestamp], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+        edits.collect()
+    }
+}
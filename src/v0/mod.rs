@@ -0,0 +1,7 @@
+//! Version 0 of the signald protocol
+//! The legacy, unversioned protocol, requests and responses carry no "version" field
+
+/// The types of the v0 protocol, generated from protocol/protocol.json
+pub mod types;
+
+pub use types::*;
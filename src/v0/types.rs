@@ -0,0 +1,688 @@
+// Generated by `cargo run -p xtask -- codegen` from protocol/protocol.json, do not edit
+use serde::{Serialize, Deserialize};
+use serde_json::{Value, Map};
+use crate::signaladdress::SignalAddress;
+use crate::v1;
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Account {
+    #[serde(rename = "color")]
+    pub color: String,
+    #[serde(rename = "name", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "number", skip_serializing_if = "Option::is_none")]
+    pub number: Option<SignalAddress>,
+    #[serde(rename = "profileKey", skip_serializing_if = "Option::is_none")]
+    pub profile_key: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// An account signald manages
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct AccountInfo {
+    #[serde(rename = "deviceId", skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<i32>,
+    #[serde(rename = "filename", skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    #[serde(rename = "has_keys", skip_serializing_if = "Option::is_none")]
+    pub has_keys: Option<bool>,
+    #[serde(rename = "registered")]
+    pub registered: bool,
+    #[serde(rename = "subscribed", skip_serializing_if = "Option::is_none")]
+    pub subscribed: Option<bool>,
+    #[serde(rename = "username")]
+    pub username: String,
+    #[serde(rename = "uuid", skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct AccountListData {
+    #[serde(rename = "accounts")]
+    pub accounts: Vec<AccountInfo>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A request that only needs the account it applies to
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct AccountRequest {
+    #[serde(rename = "username")]
+    pub username: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct AddDeviceRequest {
+    #[serde(rename = "uri")]
+    pub uri: String,
+    #[serde(rename = "username")]
+    pub username: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Attachment {
+    #[serde(rename = "blurhash", skip_serializing_if = "Option::is_none")]
+    pub blurhash: Option<String>,
+    #[serde(rename = "caption", skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+    #[serde(rename = "contentType", skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(rename = "customFilename", skip_serializing_if = "Option::is_none")]
+    pub custom_filename: Option<String>,
+    #[serde(rename = "digest", skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    #[serde(rename = "filename", skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    #[serde(rename = "height", skip_serializing_if = "Option::is_none")]
+    pub height: Option<i32>,
+    #[serde(rename = "id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(rename = "key", skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(rename = "preview", skip_serializing_if = "Option::is_none")]
+    pub preview: Option<String>,
+    #[serde(rename = "size", skip_serializing_if = "Option::is_none")]
+    pub size: Option<i64>,
+    #[serde(rename = "storedFilename", skip_serializing_if = "Option::is_none")]
+    pub stored_filename: Option<String>,
+    #[serde(rename = "voiceNote", skip_serializing_if = "Option::is_none")]
+    pub voice_note: Option<bool>,
+    #[serde(rename = "width", skip_serializing_if = "Option::is_none")]
+    pub width: Option<i32>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct BlockedList {
+    #[serde(rename = "groupIds")]
+    pub group_ids: Vec<String>,
+    #[serde(rename = "numbers")]
+    pub numbers: Vec<SignalAddress>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Configuration {
+    #[serde(rename = "linkPreviews", skip_serializing_if = "Option::is_none")]
+    pub link_previews: Option<bool>,
+    #[serde(rename = "readReceipts", skip_serializing_if = "Option::is_none")]
+    pub read_receipts: Option<bool>,
+    #[serde(rename = "typingIndicators", skip_serializing_if = "Option::is_none")]
+    pub typing_indicators: Option<bool>,
+    #[serde(rename = "unidentifiedDeliveryIndicators", skip_serializing_if = "Option::is_none")]
+    pub unidentified_delivery_indicators: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A request about a single contact of an account
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ContactRequest {
+    #[serde(rename = "recipientAddress", skip_serializing_if = "Option::is_none")]
+    pub recipient_address: Option<SignalAddress>,
+    #[serde(rename = "username")]
+    pub username: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ContactUpdate {
+    #[serde(rename = "address", skip_serializing_if = "Option::is_none")]
+    pub address: Option<SignalAddress>,
+    #[serde(rename = "color", skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(rename = "name", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The sender replaced the text of one of their earlier messages
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct EditMessage {
+    /// The new version of the message
+    #[serde(rename = "dataMessage")]
+    pub data_message: Message,
+    /// The timestamp of the original message
+    #[serde(rename = "targetSentTimestamp")]
+    pub target_sent_timestamp: i64,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ErrorData {
+    #[serde(rename = "message")]
+    pub message: String,
+    #[serde(rename = "request", skip_serializing_if = "Option::is_none")]
+    pub request: Option<Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A legacy group a message was sent in
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct GroupInfo {
+    #[serde(rename = "groupId")]
+    pub group_id: String,
+    #[serde(rename = "members", skip_serializing_if = "Option::is_none")]
+    pub members: Option<Vec<SignalAddress>>,
+    #[serde(rename = "name", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// One of DELIVER, UPDATE, QUIT or REQUEST_INFO
+    #[serde(rename = "type")]
+    pub typ: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A v2 group a message was sent in
+/// Messages that change the group carry the new state of the group
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct GroupV2Info {
+    #[serde(rename = "accessControl", skip_serializing_if = "Option::is_none")]
+    pub access_control: Option<v1::GroupAccessControl>,
+    #[serde(rename = "id")]
+    pub id: String,
+    #[serde(rename = "inviteLink", skip_serializing_if = "Option::is_none")]
+    pub invite_link: Option<String>,
+    #[serde(rename = "memberDetail", skip_serializing_if = "Option::is_none")]
+    pub member_detail: Option<Vec<v1::GroupMember>>,
+    #[serde(rename = "members", skip_serializing_if = "Option::is_none")]
+    pub members: Option<Vec<SignalAddress>>,
+    #[serde(rename = "pendingMembers", skip_serializing_if = "Option::is_none")]
+    pub pending_members: Option<Vec<SignalAddress>>,
+    #[serde(rename = "requestingMembers", skip_serializing_if = "Option::is_none")]
+    pub requesting_members: Option<Vec<SignalAddress>>,
+    #[serde(rename = "revision")]
+    pub revision: i32,
+    #[serde(rename = "title", skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct LeaveGroupRequest {
+    #[serde(rename = "recipientGroupId")]
+    pub recipient_group_id: String,
+    #[serde(rename = "username")]
+    pub username: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct LinkRequest {
+    #[serde(rename = "deviceName", skip_serializing_if = "Option::is_none")]
+    pub device_name: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct LinkingError {
+    #[serde(rename = "error")]
+    pub error: bool,
+    #[serde(rename = "message")]
+    pub message: String,
+    #[serde(rename = "msg_number")]
+    pub msg_number: i32,
+    #[serde(rename = "request")]
+    pub request: Request,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct LinkingUri {
+    #[serde(rename = "uri")]
+    pub uri: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Message {
+    #[serde(rename = "attachments", skip_serializing_if = "Option::is_none")]
+    pub attachments: Option<Vec<Attachment>>,
+    #[serde(rename = "expiresInSeconds")]
+    pub expires_in_seconds: i32,
+    #[serde(rename = "groupInfo", skip_serializing_if = "Option::is_none")]
+    pub group_info: Option<GroupInfo>,
+    #[serde(rename = "groupV2", skip_serializing_if = "Option::is_none")]
+    pub group_v2: Option<GroupV2Info>,
+    #[serde(rename = "message")]
+    pub message: String,
+    #[serde(rename = "reaction", skip_serializing_if = "Option::is_none")]
+    pub reaction: Option<v1::JsonReaction>,
+    #[serde(rename = "remoteDelete", skip_serializing_if = "Option::is_none")]
+    pub remote_delete: Option<RemoteDelete>,
+    #[serde(rename = "timestamp")]
+    pub timestamp: i64,
+    /// The attachments can only be viewed once
+    #[serde(rename = "viewOnce", skip_serializing_if = "Option::is_none")]
+    pub view_once: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct MessageData {
+    #[serde(rename = "dataMessage", skip_serializing_if = "Option::is_none")]
+    pub data_message: Option<Message>,
+    #[serde(rename = "editMessage", skip_serializing_if = "Option::is_none")]
+    pub edit_message: Option<EditMessage>,
+    #[serde(rename = "hasContent")]
+    pub has_content: bool,
+    #[serde(rename = "hasLegacyMessage")]
+    pub has_legacy_message: bool,
+    #[serde(rename = "isPrekeySignalMessage", skip_serializing_if = "Option::is_none")]
+    pub is_prekey_signal_message: Option<bool>,
+    #[serde(rename = "isReceipt")]
+    pub is_receipt: bool,
+    #[serde(rename = "isSignalMessage", skip_serializing_if = "Option::is_none")]
+    pub is_signal_message: Option<bool>,
+    #[serde(rename = "isUnidentifiedSender")]
+    pub is_unidentified_sender: bool,
+    #[serde(rename = "receipt", skip_serializing_if = "Option::is_none")]
+    pub receipt: Option<Receipt>,
+    #[serde(rename = "serverTimestamp")]
+    pub server_timestamp: i64,
+    #[serde(rename = "source", skip_serializing_if = "Option::is_none")]
+    pub source: Option<SignalAddress>,
+    #[serde(rename = "sourceDevice", skip_serializing_if = "Option::is_none")]
+    pub source_device: Option<i32>,
+    #[serde(rename = "syncMessage", skip_serializing_if = "Option::is_none")]
+    pub sync_message: Option<SyncMessage>,
+    #[serde(rename = "timestamp")]
+    pub timestamp: i64,
+    #[serde(rename = "timestampISO")]
+    pub timestamp_iso: String,
+    #[serde(rename = "type")]
+    pub typ: i32,
+    #[serde(rename = "typing", skip_serializing_if = "Option::is_none")]
+    pub typing: Option<Typing>,
+    #[serde(rename = "username", skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(rename = "uuid", skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<SignalAddress>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct MessageRequestResponse {
+    #[serde(rename = "groupId", skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
+    #[serde(rename = "person", skip_serializing_if = "Option::is_none")]
+    pub person: Option<SignalAddress>,
+    #[serde(rename = "type")]
+    pub typ: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A file on the signald host to attach to a message
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct OutgoingAttachment {
+    #[serde(rename = "caption", skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+    #[serde(rename = "filename")]
+    pub filename: String,
+    #[serde(rename = "voiceNote", skip_serializing_if = "Option::is_none")]
+    pub voice_note: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A reference to an earlier message
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Quote {
+    #[serde(rename = "author", skip_serializing_if = "Option::is_none")]
+    pub author: Option<SignalAddress>,
+    /// The timestamp of the quoted message
+    #[serde(rename = "id")]
+    pub id: i64,
+    #[serde(rename = "text", skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ReadMessage {
+    #[serde(rename = "sender", skip_serializing_if = "Option::is_none")]
+    pub sender: Option<SignalAddress>,
+    #[serde(rename = "timestamp")]
+    pub timestamp: i64,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Receipt {
+    #[serde(rename = "timestamps")]
+    pub timestamps: Vec<i64>,
+    #[serde(rename = "type")]
+    pub typ: String,
+    #[serde(rename = "when")]
+    pub when: i64,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct RegisterRequest {
+    #[serde(rename = "captcha", skip_serializing_if = "Option::is_none")]
+    pub captcha: Option<String>,
+    #[serde(rename = "username")]
+    pub username: String,
+    #[serde(rename = "voice", skip_serializing_if = "Option::is_none")]
+    pub voice: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The sender deleted one of their earlier messages
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct RemoteDelete {
+    #[serde(rename = "targetSentTimestamp")]
+    pub target_sent_timestamp: i64,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Request {
+    #[serde(rename = "expiresInSeconds")]
+    pub expires_in_seconds: i32,
+    #[serde(rename = "type")]
+    pub typ: String,
+    #[serde(rename = "when")]
+    pub when: i64,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct SendRequest {
+    #[serde(rename = "attachments", skip_serializing_if = "Option::is_none")]
+    pub attachments: Option<Vec<OutgoingAttachment>>,
+    #[serde(rename = "messageBody", skip_serializing_if = "Option::is_none")]
+    pub message_body: Option<String>,
+    #[serde(rename = "quote", skip_serializing_if = "Option::is_none")]
+    pub quote: Option<Quote>,
+    /// Set either the address or the group id
+    #[serde(rename = "recipientAddress", skip_serializing_if = "Option::is_none")]
+    pub recipient_address: Option<SignalAddress>,
+    #[serde(rename = "recipientGroupId", skip_serializing_if = "Option::is_none")]
+    pub recipient_group_id: Option<String>,
+    #[serde(rename = "username")]
+    pub username: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct SentMessage {
+    #[serde(rename = "destination", skip_serializing_if = "Option::is_none")]
+    pub destination: Option<SignalAddress>,
+    /// An edit sent from another device of the account, the message is empty then
+    #[serde(rename = "editMessage", skip_serializing_if = "Option::is_none")]
+    pub edit_message: Option<EditMessage>,
+    #[serde(rename = "expirationStartTimestamp")]
+    pub expiration_start_timestamp: i64,
+    #[serde(rename = "isRecipientUpdate")]
+    pub is_recipient_update: bool,
+    #[serde(rename = "message")]
+    pub message: Message,
+    #[serde(rename = "timestamp")]
+    pub timestamp: i64,
+    #[serde(rename = "unidentifiedStatus")]
+    pub unidentified_status: Map<String, Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct SetExpirationRequest {
+    #[serde(rename = "expiresInSeconds")]
+    pub expires_in_seconds: i32,
+    /// Set either the address or the group id
+    #[serde(rename = "recipientAddress", skip_serializing_if = "Option::is_none")]
+    pub recipient_address: Option<SignalAddress>,
+    #[serde(rename = "recipientGroupId", skip_serializing_if = "Option::is_none")]
+    pub recipient_group_id: Option<String>,
+    #[serde(rename = "username")]
+    pub username: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct StickerPackOperation {
+    #[serde(rename = "packID", skip_serializing_if = "Option::is_none")]
+    pub pack_id: Option<String>,
+    #[serde(rename = "packKey", skip_serializing_if = "Option::is_none")]
+    pub pack_key: Option<String>,
+    #[serde(rename = "type")]
+    pub typ: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct SyncMessage {
+    #[serde(rename = "blockedList", skip_serializing_if = "Option::is_none")]
+    pub blocked_list: Option<BlockedList>,
+    #[serde(rename = "configuration", skip_serializing_if = "Option::is_none")]
+    pub configuration: Option<Configuration>,
+    #[serde(rename = "contacts", skip_serializing_if = "Option::is_none")]
+    pub contacts: Option<Attachment>,
+    #[serde(rename = "contactsComplete")]
+    pub contacts_complete: bool,
+    #[serde(rename = "fetchType", skip_serializing_if = "Option::is_none")]
+    pub fetch_type: Option<String>,
+    #[serde(rename = "groups", skip_serializing_if = "Option::is_none")]
+    pub groups: Option<Attachment>,
+    #[serde(rename = "messageRequestResponse", skip_serializing_if = "Option::is_none")]
+    pub message_request_response: Option<MessageRequestResponse>,
+    #[serde(rename = "readMessages", skip_serializing_if = "Option::is_none")]
+    pub read_messages: Option<Vec<ReadMessage>>,
+    #[serde(rename = "request", skip_serializing_if = "Option::is_none")]
+    pub request: Option<String>,
+    #[serde(rename = "sent", skip_serializing_if = "Option::is_none")]
+    pub sent: Option<SentMessage>,
+    #[serde(rename = "stickerPackOperations", skip_serializing_if = "Option::is_none")]
+    pub sticker_pack_operations: Option<Vec<StickerPackOperation>>,
+    #[serde(rename = "unidentifiedStatus", skip_serializing_if = "Option::is_none")]
+    pub unidentified_status: Option<Map<String, Value>>,
+    #[serde(rename = "verified", skip_serializing_if = "Option::is_none")]
+    pub verified: Option<Verified>,
+    #[serde(rename = "viewOnceOpen", skip_serializing_if = "Option::is_none")]
+    pub view_once_open: Option<ViewOnceOpen>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct TrustRequest {
+    #[serde(rename = "fingerprint")]
+    pub fingerprint: String,
+    #[serde(rename = "recipientAddress", skip_serializing_if = "Option::is_none")]
+    pub recipient_address: Option<SignalAddress>,
+    #[serde(rename = "username")]
+    pub username: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Typing {
+    #[serde(rename = "action")]
+    pub action: String,
+    #[serde(rename = "timestamp")]
+    pub timestamp: i64,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct UntrustedIdentity {
+    #[serde(rename = "fingerprint", skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    #[serde(rename = "number", skip_serializing_if = "Option::is_none")]
+    pub number: Option<SignalAddress>,
+    #[serde(rename = "request", skip_serializing_if = "Option::is_none")]
+    pub request: Option<Value>,
+    #[serde(rename = "safety_number", skip_serializing_if = "Option::is_none")]
+    pub safety_number: Option<String>,
+    #[serde(rename = "username", skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct UpdateContactRequest {
+    #[serde(rename = "contact")]
+    pub contact: ContactUpdate,
+    #[serde(rename = "username")]
+    pub username: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Create a group, or update it when a group id is given
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct UpdateGroupRequest {
+    #[serde(rename = "avatar", skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<String>,
+    #[serde(rename = "groupName", skip_serializing_if = "Option::is_none")]
+    pub group_name: Option<String>,
+    #[serde(rename = "members", skip_serializing_if = "Option::is_none")]
+    pub members: Option<Vec<SignalAddress>>,
+    #[serde(rename = "recipientGroupId", skip_serializing_if = "Option::is_none")]
+    pub recipient_group_id: Option<String>,
+    #[serde(rename = "username")]
+    pub username: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Verified {
+    #[serde(rename = "destination", skip_serializing_if = "Option::is_none")]
+    pub destination: Option<SignalAddress>,
+    #[serde(rename = "identityKey")]
+    pub identity_key: String,
+    #[serde(rename = "timestamp")]
+    pub timestamp: i64,
+    #[serde(rename = "verified")]
+    pub verified: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct VerifyRequest {
+    #[serde(rename = "code")]
+    pub code: String,
+    #[serde(rename = "username")]
+    pub username: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct VersionData {
+    #[serde(rename = "branch")]
+    pub branch: String,
+    #[serde(rename = "commit")]
+    pub commit: String,
+    #[serde(rename = "name")]
+    pub name: String,
+    #[serde(rename = "version")]
+    pub version: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ViewOnceOpen {
+    #[serde(rename = "sender", skip_serializing_if = "Option::is_none")]
+    pub sender: Option<SignalAddress>,
+    #[serde(rename = "timestamp")]
+    pub timestamp: i64,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The state of the connection between signald and the Signal server for an account
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct WebSocketConnectionState {
+    #[serde(rename = "account")]
+    pub account: String,
+    /// The socket this state is about, IDENTIFIED or UNIDENTIFIED
+    #[serde(rename = "socket", skip_serializing_if = "Option::is_none")]
+    pub socket: Option<String>,
+    /// One of DISCONNECTED, CONNECTING, CONNECTED, RECONNECTING, DISCONNECTED_REMOTELY, AUTHENTICATION_FAILED or FAILED
+    #[serde(rename = "state")]
+    pub state: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
@@ -0,0 +1,73 @@
+//! Version 1 of the signald protocol
+//! Requests and responses carry a "version": "v1" field and errors are sent as an
+//! "error_type" with a structured "error" instead of a separate error response
+
+use serde::{Serialize, Serializer, Deserialize};
+use serde::de::DeserializeOwned;
+use serde_json::{Value, Map};
+use crate::signalderror::SignaldError;
+
+/// The types of the v1 protocol, generated from protocol/protocol.json
+pub mod types;
+
+pub use types::*;
+
+/// The protocol version a Signald instance speaks
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Protocol {
+    /// The legacy, unversioned protocol
+    #[default]
+    V0,
+    /// The versioned v1 protocol
+    V1,
+}
+
+impl Serialize for Request {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = match self.data_value().map_err(serde::ser::Error::custom)? {
+            Value::Object(map) => map,
+            _ => Map::new(),
+        };
+        map.insert("type".to_string(), Value::String(self.type_name().to_string()));
+        map.insert("version".to_string(), Value::String("v1".to_string()));
+        map.serialize(serializer)
+    }
+}
+
+/// A v1 response
+/// Contains either the data of a successful request or the error signald sent
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Response {
+    #[serde(rename = "type")]
+    pub typ: String,
+    #[serde(rename = "version")]
+    pub version: String,
+    #[serde(rename = "data", skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+    #[serde(rename = "error_type", skip_serializing_if = "Option::is_none")]
+    pub error_type: Option<String>,
+    #[serde(rename = "error", skip_serializing_if = "Option::is_none")]
+    pub error: Option<Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+impl Response {
+    /// The error of this response, if signald rejected the request
+    pub fn error(&self) -> Option<SignaldError> {
+        let error_type = self.error_type.as_ref()?;
+        Some(SignaldError::from_v1(error_type, self.error.clone().unwrap_or_default()))
+    }
+
+    /// Parse the data of this response into its typed structure
+    pub fn parse<T: DeserializeOwned>(&self) -> Result<T, SignaldError> {
+        if let Some(error) = self.error() {
+            return Err(error);
+        }
+
+        let data = self.data.clone().unwrap_or_default();
+        serde_json::from_value(data).map_err(|e| SignaldError::from_v1("ParseError", serde_json::json!({
+            "message": e.to_string()
+        })))
+    }
+}
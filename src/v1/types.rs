@@ -0,0 +1,474 @@
+// Generated by `cargo run -p xtask -- codegen` from protocol/protocol.json, do not edit
+use serde::{Serialize, Deserialize};
+use serde_json::{Value, Map};
+use crate::signaladdress::SignalAddress;
+
+/// Every v1 command
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    AcceptInvitation(AcceptInvitationRequest),
+    ApproveMembership(ApproveMembershipRequest),
+    Block(BlockRequest),
+    GetGroup(GetGroupRequest),
+    GetProfile(GetProfileRequest),
+    JoinGroup(JoinGroupRequest),
+    LeaveGroup(LeaveGroupRequest),
+    React(ReactRequest),
+    RefuseMembership(RefuseMembershipRequest),
+    Send(SendRequest),
+    Unblock(UnblockRequest),
+    UpdateGroup(UpdateGroupRequest),
+}
+impl Request {
+    /// The signald type of this request
+    pub fn type_name(&self) -> &str {
+        match self {
+            Request::AcceptInvitation(_) => "accept_invitation",
+            Request::ApproveMembership(_) => "approve_membership",
+            Request::Block(_) => "block",
+            Request::GetGroup(_) => "get_group",
+            Request::GetProfile(_) => "get_profile",
+            Request::JoinGroup(_) => "join_group",
+            Request::LeaveGroup(_) => "leave_group",
+            Request::React(_) => "react",
+            Request::RefuseMembership(_) => "refuse_membership",
+            Request::Send(_) => "send",
+            Request::Unblock(_) => "unblock",
+            Request::UpdateGroup(_) => "update_group",
+        }
+    }
+    /// The fields of this request as json
+    pub fn data_value(&self) -> serde_json::Result<Value> {
+        match self {
+            Request::AcceptInvitation(request) => serde_json::to_value(request),
+            Request::ApproveMembership(request) => serde_json::to_value(request),
+            Request::Block(request) => serde_json::to_value(request),
+            Request::GetGroup(request) => serde_json::to_value(request),
+            Request::GetProfile(request) => serde_json::to_value(request),
+            Request::JoinGroup(request) => serde_json::to_value(request),
+            Request::LeaveGroup(request) => serde_json::to_value(request),
+            Request::React(request) => serde_json::to_value(request),
+            Request::RefuseMembership(request) => serde_json::to_value(request),
+            Request::Send(request) => serde_json::to_value(request),
+            Request::Unblock(request) => serde_json::to_value(request),
+            Request::UpdateGroup(request) => serde_json::to_value(request),
+        }
+    }
+}
+
+/// Accept a v2 group invitation
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct AcceptInvitationRequest {
+    #[serde(rename = "account")]
+    pub account: String,
+    #[serde(rename = "groupID")]
+    pub group_id: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Approve requests to join a group
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ApproveMembershipRequest {
+    #[serde(rename = "account")]
+    pub account: String,
+    #[serde(rename = "groupID")]
+    pub group_id: String,
+    /// the users whose requests are approved
+    #[serde(rename = "members")]
+    pub members: Vec<SignalAddress>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Block a user or a group, set either the address or the group id
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct BlockRequest {
+    #[serde(rename = "account")]
+    pub account: String,
+    #[serde(rename = "address", skip_serializing_if = "Option::is_none")]
+    pub address: Option<SignalAddress>,
+    #[serde(rename = "groupID", skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The response of a request that returns no data
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Empty {
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Query the server for the latest state of a known group
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct GetGroupRequest {
+    #[serde(rename = "account")]
+    pub account: String,
+    #[serde(rename = "groupID")]
+    pub group_id: String,
+    #[serde(rename = "revision", skip_serializing_if = "Option::is_none")]
+    pub revision: Option<i32>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Get all information available about a user
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct GetProfileRequest {
+    #[serde(rename = "account")]
+    pub account: String,
+    #[serde(rename = "address", skip_serializing_if = "Option::is_none")]
+    pub address: Option<SignalAddress>,
+    /// return results from local store immediately, refreshing from server in the background
+    #[serde(rename = "async", skip_serializing_if = "Option::is_none")]
+    pub r#async: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Who can make changes to a group
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct GroupAccessControl {
+    #[serde(rename = "attributes", skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<String>,
+    #[serde(rename = "link", skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    #[serde(rename = "members", skip_serializing_if = "Option::is_none")]
+    pub members: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A generic type that is used when the group version is not known
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct GroupInfo {
+    #[serde(rename = "v2", skip_serializing_if = "Option::is_none")]
+    pub v2: Option<JsonGroupV2Info>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct GroupMember {
+    /// the group revision the member joined at
+    #[serde(rename = "joined_revision", skip_serializing_if = "Option::is_none")]
+    pub joined_revision: Option<i32>,
+    /// UNKNOWN, DEFAULT or ADMINISTRATOR
+    #[serde(rename = "role", skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(rename = "uuid", skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Join a group using an invitation link
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct JoinGroupRequest {
+    #[serde(rename = "account")]
+    pub account: String,
+    /// a https://signal.group/ link
+    #[serde(rename = "uri")]
+    pub uri: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct JsonAttachment {
+    #[serde(rename = "caption", skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+    #[serde(rename = "filename")]
+    pub filename: String,
+    #[serde(rename = "voiceNote", skip_serializing_if = "Option::is_none")]
+    pub voice_note: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The state of a group joined through an invitation link
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct JsonGroupJoinInfo {
+    #[serde(rename = "addFromInviteLink", skip_serializing_if = "Option::is_none")]
+    pub add_from_invite_link: Option<i32>,
+    #[serde(rename = "description", skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "groupID")]
+    pub group_id: String,
+    #[serde(rename = "memberCount", skip_serializing_if = "Option::is_none")]
+    pub member_count: Option<i32>,
+    /// an admin has to approve the join request
+    #[serde(rename = "pendingAdminApproval", skip_serializing_if = "Option::is_none")]
+    pub pending_admin_approval: Option<bool>,
+    #[serde(rename = "revision", skip_serializing_if = "Option::is_none")]
+    pub revision: Option<i32>,
+    #[serde(rename = "title", skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Information about a Signal group
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct JsonGroupV2Info {
+    #[serde(rename = "accessControl", skip_serializing_if = "Option::is_none")]
+    pub access_control: Option<GroupAccessControl>,
+    #[serde(rename = "avatar", skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<String>,
+    #[serde(rename = "description", skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "id")]
+    pub id: String,
+    #[serde(rename = "inviteLink", skip_serializing_if = "Option::is_none")]
+    pub invite_link: Option<String>,
+    #[serde(rename = "memberDetail", skip_serializing_if = "Option::is_none")]
+    pub member_detail: Option<Vec<GroupMember>>,
+    #[serde(rename = "members", skip_serializing_if = "Option::is_none")]
+    pub members: Option<Vec<SignalAddress>>,
+    #[serde(rename = "pendingMembers", skip_serializing_if = "Option::is_none")]
+    pub pending_members: Option<Vec<SignalAddress>>,
+    #[serde(rename = "requestingMembers", skip_serializing_if = "Option::is_none")]
+    pub requesting_members: Option<Vec<SignalAddress>>,
+    #[serde(rename = "revision")]
+    pub revision: i32,
+    #[serde(rename = "timer", skip_serializing_if = "Option::is_none")]
+    pub timer: Option<i32>,
+    #[serde(rename = "title", skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A quote is a reply to a previous message
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct JsonQuote {
+    #[serde(rename = "author", skip_serializing_if = "Option::is_none")]
+    pub author: Option<SignalAddress>,
+    /// the client timestamp of the message being quoted
+    #[serde(rename = "id")]
+    pub id: i64,
+    #[serde(rename = "text", skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct JsonReaction {
+    #[serde(rename = "emoji")]
+    pub emoji: String,
+    #[serde(rename = "remove")]
+    pub remove: bool,
+    #[serde(rename = "targetAuthor", skip_serializing_if = "Option::is_none")]
+    pub target_author: Option<SignalAddress>,
+    #[serde(rename = "targetSentTimestamp")]
+    pub target_sent_timestamp: i64,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Leave a group, or decline an invitation to it
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct LeaveGroupRequest {
+    #[serde(rename = "account")]
+    pub account: String,
+    #[serde(rename = "groupID")]
+    pub group_id: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Information about a Signal user
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Profile {
+    #[serde(rename = "about", skip_serializing_if = "Option::is_none")]
+    pub about: Option<String>,
+    #[serde(rename = "address", skip_serializing_if = "Option::is_none")]
+    pub address: Option<SignalAddress>,
+    #[serde(rename = "avatar", skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<String>,
+    #[serde(rename = "color", skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(rename = "contact_name", skip_serializing_if = "Option::is_none")]
+    pub contact_name: Option<String>,
+    #[serde(rename = "emoji", skip_serializing_if = "Option::is_none")]
+    pub emoji: Option<String>,
+    #[serde(rename = "expiration_time", skip_serializing_if = "Option::is_none")]
+    pub expiration_time: Option<i32>,
+    #[serde(rename = "name", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "profile_name", skip_serializing_if = "Option::is_none")]
+    pub profile_name: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// React to a previous message
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ReactRequest {
+    #[serde(rename = "reaction")]
+    pub reaction: JsonReaction,
+    #[serde(rename = "recipientAddress", skip_serializing_if = "Option::is_none")]
+    pub recipient_address: Option<SignalAddress>,
+    #[serde(rename = "recipientGroupId", skip_serializing_if = "Option::is_none")]
+    pub recipient_group_id: Option<String>,
+    #[serde(rename = "timestamp", skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+    #[serde(rename = "username")]
+    pub username: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Deny requests to join a group
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct RefuseMembershipRequest {
+    #[serde(rename = "account")]
+    pub account: String,
+    /// also keep the users from requesting to join again
+    #[serde(rename = "also_ban", skip_serializing_if = "Option::is_none")]
+    pub also_ban: Option<bool>,
+    #[serde(rename = "groupID")]
+    pub group_id: String,
+    #[serde(rename = "members")]
+    pub members: Vec<SignalAddress>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct SendMessageResult {
+    #[serde(rename = "address", skip_serializing_if = "Option::is_none")]
+    pub address: Option<SignalAddress>,
+    #[serde(rename = "identityFailure", skip_serializing_if = "Option::is_none")]
+    pub identity_failure: Option<String>,
+    #[serde(rename = "networkFailure")]
+    pub network_failure: bool,
+    #[serde(rename = "success", skip_serializing_if = "Option::is_none")]
+    pub success: Option<SendSuccess>,
+    #[serde(rename = "unregisteredFailure")]
+    pub unregistered_failure: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct SendRequest {
+    #[serde(rename = "attachments", skip_serializing_if = "Option::is_none")]
+    pub attachments: Option<Vec<JsonAttachment>>,
+    /// the timestamp of an earlier message this message replaces
+    #[serde(rename = "editTimestamp", skip_serializing_if = "Option::is_none")]
+    pub edit_timestamp: Option<i64>,
+    /// send to these group members only, ignored without recipientGroupId
+    #[serde(rename = "members", skip_serializing_if = "Option::is_none")]
+    pub members: Option<Vec<SignalAddress>>,
+    #[serde(rename = "messageBody", skip_serializing_if = "Option::is_none")]
+    pub message_body: Option<String>,
+    #[serde(rename = "quote", skip_serializing_if = "Option::is_none")]
+    pub quote: Option<JsonQuote>,
+    #[serde(rename = "recipientAddress", skip_serializing_if = "Option::is_none")]
+    pub recipient_address: Option<SignalAddress>,
+    #[serde(rename = "recipientGroupId", skip_serializing_if = "Option::is_none")]
+    pub recipient_group_id: Option<String>,
+    #[serde(rename = "timestamp", skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+    #[serde(rename = "username")]
+    pub username: String,
+    /// the attachments can only be viewed once
+    #[serde(rename = "viewOnce", skip_serializing_if = "Option::is_none")]
+    pub view_once: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct SendResponse {
+    #[serde(rename = "results")]
+    pub results: Vec<SendMessageResult>,
+    #[serde(rename = "timestamp")]
+    pub timestamp: i64,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct SendSuccess {
+    #[serde(rename = "needsSync")]
+    pub needs_sync: bool,
+    #[serde(rename = "unidentified")]
+    pub unidentified: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Unblock a user or a group, set either the address or the group id
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct UnblockRequest {
+    #[serde(rename = "account")]
+    pub account: String,
+    #[serde(rename = "address", skip_serializing_if = "Option::is_none")]
+    pub address: Option<SignalAddress>,
+    #[serde(rename = "groupID", skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Modify a group, only the set fields are changed
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct UpdateGroupRequest {
+    #[serde(rename = "account")]
+    pub account: String,
+    #[serde(rename = "addMembers", skip_serializing_if = "Option::is_none")]
+    pub add_members: Option<Vec<SignalAddress>>,
+    #[serde(rename = "avatar", skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<String>,
+    #[serde(rename = "description", skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "groupID")]
+    pub group_id: String,
+    #[serde(rename = "removeMembers", skip_serializing_if = "Option::is_none")]
+    pub remove_members: Option<Vec<SignalAddress>>,
+    /// regenerate the invitation link of the group
+    #[serde(rename = "resetLink", skip_serializing_if = "Option::is_none")]
+    pub reset_link: Option<bool>,
+    #[serde(rename = "title", skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(rename = "updateAccessControl", skip_serializing_if = "Option::is_none")]
+    pub update_access_control: Option<GroupAccessControl>,
+    /// change the role of a member
+    #[serde(rename = "updateRole", skip_serializing_if = "Option::is_none")]
+    pub update_role: Option<GroupMember>,
+    #[serde(rename = "updateTimer", skip_serializing_if = "Option::is_none")]
+    pub update_timer: Option<i32>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
@@ -0,0 +1,53 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::signaldresponse::Attachment;
+
+/// The downloaded file of a view-once attachment
+/// The file is only deleted once it's consumed or discarded, dropping the attachment leaves it in place
+#[derive(Debug)]
+pub struct ViewOnceAttachment {
+    attachment: Attachment,
+    path: PathBuf,
+}
+impl ViewOnceAttachment {
+    /// Wrap the file signald downloaded for an attachment
+    /// Returns None when the attachment wasn't downloaded
+    pub fn new(attachment: Attachment) -> Option<ViewOnceAttachment> {
+        let path = PathBuf::from(attachment.stored_filename.as_ref()?);
+        Some(ViewOnceAttachment {
+            attachment,
+            path,
+        })
+    }
+    /// The attachment as signald sent it
+    pub fn attachment(&self) -> &Attachment {
+        &self.attachment
+    }
+    /// The path of the downloaded file on the signald host
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Pass the file to a consumer once, then delete it
+    /// The file is only deleted when the consumer succeeds, its error is returned with the file left in place
+    /// Returns an error when the file couldn't be deleted
+    pub fn consume<T, F: FnOnce(&Path) -> io::Result<T>>(self, consumer: F) -> io::Result<T> {
+        let result = consumer(&self.path)?;
+        self.discard()?;
+        Ok(result)
+    }
+    /// Read the file once, then delete it
+    /// The file isn't deleted when it can't be read
+    pub fn read(self) -> io::Result<Vec<u8>> {
+        self.consume(|path| fs::read(path))
+    }
+    /// Delete the file without using it
+    /// A file that is already gone isn't an error
+    pub fn discard(self) -> io::Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use signald_rust::signaldresponse::{ResponseType, RecipientStatus};
+use signald_rust::signaladdress::SignalAddress;
+use signald_rust::socket::fakesignald::FakeSignald;
+use signald_rust::v1::Protocol;
+
+static ACCOUNT: &str = "+32000000000";
+static OTHER: &str = "+32111111111";
+
+#[tokio::test]
+async fn test_account_requests_carry_username() {
+    let fake = FakeSignald::start();
+    let mut signald = fake.connect();
+
+    let mut account = signald.account(ACCOUNT);
+    assert_eq!(account.username(), ACCOUNT);
+    assert_eq!(account.subscribe().await.unwrap().data, ResponseType::Subscribed);
+
+    let request = &fake.requests()[0];
+    assert_eq!(request["type"], "subscribe");
+    assert_eq!(request["username"], ACCOUNT);
+}
+
+#[tokio::test]
+async fn test_account_messages() {
+    let fake = FakeSignald::start();
+    let mut signald = fake.connect();
+    signald.subscribe(OTHER.to_string()).await.unwrap();
+
+    let mut account = signald.account(ACCOUNT);
+    account.subscribe().await.unwrap();
+    let messages = account.messages();
+    fake.deliver(OTHER, "+32222222222", "other account");
+    fake.deliver(ACCOUNT, "+32222222222", "first");
+    fake.deliver(OTHER, "+32222222222", "other account");
+    fake.deliver(ACCOUNT, "+32222222222", "second");
+    let bodies: Vec<String> = messages.take(2)
+        .map(|response| match response.data {
+            ResponseType::Message(Some(message)) => message.data_message.unwrap().message,
+            _ => panic!("Expected a message"),
+        })
+        .collect();
+    assert_eq!(bodies, vec!["first", "second"]);
+}
+
+#[tokio::test]
+async fn test_account_send_returns_result() {
+    let fake = FakeSignald::start();
+    let mut signald = fake.connect();
+    signald.set_protocol(Protocol::V1).unwrap();
+
+    let recipient = SignalAddress::from_number(OTHER).unwrap();
+    let result = signald.account(ACCOUNT).send(recipient.clone(), Some("hello".to_string())).await.unwrap();
+    assert!(result.is_success());
+    assert!(result.timestamp > 0);
+    assert_eq!(result.get(&recipient), Some(&RecipientStatus::Success { unidentified: true, needs_sync: true }));
+
+    let request = &fake.requests()[0];
+    assert_eq!(request["id"], "0");
+    assert_eq!(request["messageBody"], "hello");
+}
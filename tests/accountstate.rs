@@ -0,0 +1,49 @@
+use signald_rust::socket::fakesignald::FakeSignald;
+use serde_json::{json, Value};
+
+static ACCOUNT: &str = "+32000000000";
+
+fn listen(typ: &str, account: &str) -> Value {
+    json!({ "type": typ, "data": account })
+}
+
+fn connection_state(state: &str) -> Value {
+    json!({ "type": "websocket_connection_state", "data": { "account": ACCOUNT, "state": state, "socket": "IDENTIFIED" } })
+}
+
+#[test]
+fn test_account_health() {
+    let fake = FakeSignald::start();
+    let mut signald = fake.connect();
+
+    let mut rx = signald.get_rx();
+    fake.broadcast(listen("listen_started", ACCOUNT));
+    fake.broadcast(connection_state("CONNECTED"));
+    // The state is updated before the event reaches the receivers
+    rx.iter().find(|response| response.data.type_name() == "websocket_connection_state").unwrap();
+
+    let health = signald.account_health(ACCOUNT).unwrap();
+    assert!(health.listening);
+    assert!(health.is_healthy());
+    assert!(signald.account_health("+32111111111").is_none());
+}
+
+#[test]
+fn test_watch_account_health() {
+    let fake = FakeSignald::start();
+    let mut signald = fake.connect();
+
+    let states = signald.watch_account_health(ACCOUNT.to_string());
+    fake.broadcast(listen("listen_started", ACCOUNT));
+    // Events about other accounts are skipped
+    fake.broadcast(listen("listen_started", "+32111111111"));
+    fake.broadcast(connection_state("RECONNECTING"));
+    fake.broadcast(connection_state("CONNECTED"));
+    fake.broadcast(listen("listen_stopped", ACCOUNT));
+
+    let states: Vec<bool> = states
+        .take(4)
+        .map(|health| health.is_healthy())
+        .collect();
+    assert_eq!(states, vec![true, false, true, false]);
+}
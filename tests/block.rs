@@ -0,0 +1,81 @@
+use signald_rust::signaladdress::SignalAddress;
+use signald_rust::signaldresponse::ResponseType;
+use signald_rust::socket::fakesignald::FakeSignald;
+use signald_rust::v1::Protocol;
+use serde_json::json;
+
+static ACCOUNT: &str = "+32000000000";
+static ALICE: &str = "+32111111111";
+static BOB: &str = "+32222222222";
+static GROUP_ID: &str = "Z3JvdXBpZA==";
+
+fn address(number: &str) -> SignalAddress {
+    SignalAddress::from_number(number).unwrap()
+}
+
+#[tokio::test]
+async fn test_block_and_unblock() {
+    let fake = FakeSignald::start();
+    let mut signald = fake.connect();
+    signald.set_protocol(Protocol::V1).unwrap();
+    let mut account = signald.account(ACCOUNT);
+
+    account.block(address(ALICE)).await.unwrap();
+    account.block_group(GROUP_ID.to_string()).await.unwrap();
+    let requests = fake.requests();
+    assert_eq!(requests[0]["type"], "block");
+    assert_eq!(requests[0]["address"], json!({ "number": ALICE }));
+    assert_eq!(requests[1]["groupID"], GROUP_ID);
+
+    let blocked = account.blocked();
+    assert_eq!(blocked.addresses, vec![address(ALICE)]);
+    assert_eq!(blocked.group_ids, vec![GROUP_ID.to_string()]);
+
+    account.unblock(address(ALICE)).await.unwrap();
+    account.unblock_group(GROUP_ID.to_string()).await.unwrap();
+    assert_eq!(fake.requests()[2]["type"], "unblock");
+    assert_eq!(account.blocked(), Default::default());
+}
+
+#[tokio::test]
+async fn test_drop_blocked_messages() {
+    let fake = FakeSignald::start();
+    let mut signald = fake.connect();
+    signald.subscribe(ACCOUNT.to_string()).await.unwrap();
+    signald.set_drop_blocked(true);
+
+    // Another device of the account syncs its block list
+    let rx = signald.get_rx();
+    fake.deliver_response(ACCOUNT, json!({
+        "type": "message",
+        "data": {
+            "username": ACCOUNT,
+            "source": ACCOUNT,
+            "syncMessage": { "blockedList": { "numbers": [ALICE], "groupIds": [] } }
+        }
+    }));
+    fake.deliver(ACCOUNT, ALICE, "spam");
+    fake.deliver(ACCOUNT, BOB, "hello");
+
+    let bodies: Vec<String> = rx.into_iter()
+        .filter_map(|response| match response.data {
+            ResponseType::Message(Some(message)) => message.data_message.map(|m| m.message),
+            _ => None,
+        })
+        .take(1)
+        .collect();
+    assert_eq!(bodies, vec!["hello"]);
+    assert_eq!(signald.blocked(ACCOUNT).addresses, vec![address(ALICE)]);
+
+    // Without the filter blocked messages still arrive
+    signald.set_drop_blocked(false);
+    let rx = signald.get_rx();
+    fake.deliver(ACCOUNT, ALICE, "spam again");
+    let message = rx.into_iter()
+        .find_map(|response| match response.data {
+            ResponseType::Message(Some(message)) => message.data_message,
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(message.message, "spam again");
+}
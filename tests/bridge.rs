@@ -0,0 +1,202 @@
+#![cfg(feature = "bridge")]
+use signald_rust::bridge::{Bridge, BridgeConfig, Webhooks, sign, SIGNATURE_HEADER};
+use signald_rust::socket::fakesignald::FakeSignald;
+use signald_rust::v1::Protocol;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+static ACCOUNT: &str = "+32000000000";
+static SECRET: &str = "webhook secret";
+
+// Start an HTTP server standing in for a webhook
+// The first `failures` requests get a 500, every request is passed on with its signature header
+fn webhook_stand_in(failures: usize) -> (String, mpsc::Receiver<(Option<String>, String)>) {
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", server.server_addr().to_ip().unwrap());
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        for (count, mut request) in server.incoming_requests().enumerate() {
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body).unwrap();
+            let signature = request.headers().iter()
+                .find(|h| h.field.equiv(SIGNATURE_HEADER))
+                .map(|h| h.value.to_string());
+            let _ = tx.send((signature, body));
+
+            let status = if count < failures { 500 } else { 200 };
+            request.respond(tiny_http::Response::empty(status)).unwrap();
+        }
+    });
+    (url, rx)
+}
+
+fn config(webhook: String) -> BridgeConfig {
+    BridgeConfig {
+        listen: "127.0.0.1:0".to_string(),
+        webhooks: vec![webhook],
+        webhook_secret: Some(SECRET.to_string()),
+        webhook_backoff: Duration::from_millis(10),
+        ..BridgeConfig::new(ACCOUNT)
+    }
+}
+
+// Start a bridge on a daemon with one contact, the urls of dropped messages are passed on
+fn start_bridge(config: BridgeConfig) -> (FakeSignald, SocketAddr, mpsc::Receiver<String>) {
+    let fake = FakeSignald::start();
+    fake.add_contact(ACCOUNT, "+32111111111", "Alice");
+    fake.on("send", |request| vec![json!({
+        "type": "send",
+        "version": "v1",
+        "data": {
+            "results": [{ "address": request["recipientAddress"], "success": { "unidentified": false, "needsSync": true } }],
+            "timestamp": 1583863426832u64
+        }
+    })]);
+    let mut signald = fake.connect();
+    signald.set_protocol(Protocol::V1).unwrap();
+
+    let (tx, dropped) = mpsc::channel();
+    let mut bridge = Bridge::new(signald, config).unwrap();
+    bridge.on_dropped(move |url, _| tx.send(url.to_string()).unwrap());
+    let addr = bridge.local_addr().unwrap();
+    thread::spawn(move || bridge.run().unwrap());
+    (fake, addr, dropped)
+}
+
+fn read_json(response: ureq::Response) -> Value {
+    serde_json::from_str(&response.into_string().unwrap()).unwrap()
+}
+
+#[test]
+fn test_http_api() {
+    let (webhook, _) = webhook_stand_in(0);
+    let (_fake, addr, _) = start_bridge(config(webhook));
+
+    let response = ureq::post(&format!("http://{}/send", addr))
+        .send_string(&json!({ "recipient": "+32111111111", "message": "green" }).to_string())
+        .unwrap();
+    assert_eq!(read_json(response), json!({
+        "timestamp": 1583863426832u64,
+        "results": [{ "address": "+32111111111", "status": "success" }]
+    }));
+
+    let contacts = read_json(ureq::get(&format!("http://{}/contacts", addr)).call().unwrap());
+    assert_eq!(contacts[0]["name"], "Alice");
+
+    match ureq::post(&format!("http://{}/send", addr)).send_string(&json!({ "message": "nobody" }).to_string()) {
+        Err(ureq::Error::Status(400, response)) => assert_eq!(read_json(response)["error"], "a recipient or groupId is required"),
+        other => panic!("Expected a bad request, got {:?}", other),
+    }
+    match ureq::get(&format!("http://{}/missing", addr)).call() {
+        Err(ureq::Error::Status(404, _)) => {}
+        other => panic!("Expected not found, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_webhook_receives_signed_messages() {
+    let (webhook, requests) = webhook_stand_in(2);
+    let (fake, _, _) = start_bridge(config(webhook));
+    fake.wait_for_request("subscribe", Duration::from_secs(5)).unwrap();
+    fake.deliver(ACCOUNT, "+32111111111", "is the build green?");
+
+    // The first two attempts fail and are retried
+    let attempts: Vec<(Option<String>, String)> = (0..3)
+        .map(|_| requests.recv_timeout(Duration::from_secs(5)).unwrap())
+        .collect();
+    let (signature, body) = &attempts[2];
+    assert_eq!(signature.as_deref(), Some(sign(SECRET, body).as_str()));
+    assert!(attempts.iter().all(|attempt| attempt == &attempts[2]));
+
+    let message: Value = serde_json::from_str(body).unwrap();
+    assert_eq!(message["type"], "message");
+    assert_eq!(message["data"]["dataMessage"]["message"], "is the build green?");
+}
+
+#[test]
+fn test_api_token() {
+    let (webhook, _) = webhook_stand_in(0);
+    let (_fake, addr, _) = start_bridge(BridgeConfig {
+        api_token: Some("api token".to_string()),
+        ..config(webhook.clone())
+    });
+
+    match ureq::get(&format!("http://{}/contacts", addr)).set("Authorization", "Bearer wrong token").call() {
+        Err(ureq::Error::Status(401, _)) => {}
+        other => panic!("Expected unauthorized, got {:?}", other),
+    }
+    let contacts = read_json(ureq::get(&format!("http://{}/contacts", addr)).set("Authorization", "Bearer api token").call().unwrap());
+    assert_eq!(contacts[0]["name"], "Alice");
+
+    // Listening beyond localhost without a token isn't allowed
+    let fake = FakeSignald::start();
+    let result = Bridge::new(fake.connect(), BridgeConfig {
+        listen: "0.0.0.0:0".to_string(),
+        ..config(webhook)
+    });
+    assert_eq!(result.err().unwrap().kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_dropped_messages_are_reported() {
+    let (webhook, _) = webhook_stand_in(usize::MAX);
+    let (fake, _, dropped) = start_bridge(BridgeConfig {
+        webhook_attempts: 1,
+        ..config(webhook.clone())
+    });
+    fake.wait_for_request("subscribe", Duration::from_secs(5)).unwrap();
+    fake.deliver(ACCOUNT, "+32111111111", "is the build green?");
+    assert_eq!(dropped.recv_timeout(Duration::from_secs(5)).unwrap(), webhook);
+}
+
+#[test]
+fn test_slow_webhook_holds_up_nothing() {
+    let (slow, _) = webhook_stand_in(usize::MAX);
+    let (fast, requests) = webhook_stand_in(0);
+    let (fake, _, dropped) = start_bridge(BridgeConfig {
+        webhooks: vec![slow.clone(), fast],
+        webhook_attempts: 3,
+        webhook_backoff: Duration::from_secs(5),
+        webhook_queue: 1,
+        ..config(String::new())
+    });
+    fake.wait_for_request("subscribe", Duration::from_secs(5)).unwrap();
+
+    // The fast webhook gets every message while the slow one is still retrying the first
+    for body in ["one", "two", "three"] {
+        fake.deliver(ACCOUNT, "+32111111111", body);
+        let (_, message) = requests.recv_timeout(Duration::from_secs(2)).unwrap();
+        let message: Value = serde_json::from_str(&message).unwrap();
+        assert_eq!(message["data"]["dataMessage"]["message"], body);
+    }
+    // The slow webhook has room for one waiting message, the third is dropped
+    assert_eq!(dropped.recv_timeout(Duration::from_secs(2)).unwrap(), slow);
+}
+
+#[test]
+fn test_webhook_gives_up() {
+    let (url, requests) = webhook_stand_in(usize::MAX);
+    let config = BridgeConfig {
+        webhooks: vec![url.clone()],
+        webhook_attempts: 3,
+        webhook_backoff: Duration::from_millis(10),
+        ..BridgeConfig::new(ACCOUNT)
+    };
+
+    assert_eq!(Webhooks::new(&config).deliver("{}"), vec![url]);
+    let attempts: Vec<(Option<String>, String)> = requests.try_iter().collect();
+    assert_eq!(attempts.len(), 3);
+    // Bodies aren't signed without a secret
+    assert_eq!(attempts[0].0, None);
+}
+
+#[test]
+fn test_sign() {
+    // Test vector from RFC 4231
+    assert_eq!(sign("Jefe", "what do ya want for nothing?"),
+               "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+}
@@ -0,0 +1,67 @@
+use signald_rust::signalderror::SignaldError;
+use signald_rust::capabilities::{Feature, SignaldVersion};
+use signald_rust::signaldrequest::Recipient;
+use signald_rust::signaladdress::SignalAddress;
+use signald_rust::v1::{self, Protocol};
+use signald_rust::socket::fakesignald::FakeSignald;
+use std::time::{Duration, Instant};
+
+// A daemon that greets every connection with the given version
+fn daemon(version: &str) -> FakeSignald {
+    let fake = FakeSignald::start();
+    fake.set_version(version);
+    fake
+}
+
+#[test]
+fn test_capabilities_from_greeting() {
+    let fake = daemon("0.13.1+git2021-01-12r6fca6c8c.15");
+    let signald = fake.connect();
+
+    let capabilities = signald.capabilities();
+    assert_eq!(capabilities.version, Some(SignaldVersion::new(0, 13, 1)));
+    assert_eq!(capabilities.version_data.as_ref().unwrap().version, "0.13.1+git2021-01-12r6fca6c8c.15");
+    assert!(capabilities.supports(Feature::ProtocolV1));
+}
+
+#[tokio::test]
+async fn test_unsupported_on_old_version() {
+    let fake = daemon("0.9.0+git2020-03-08r1a9be52a.5");
+    let mut signald = fake.connect();
+
+    assert!(!signald.capabilities().supports(Feature::Reactions));
+    let result = signald.react("+32000000000".to_string(), Recipient::Address(SignalAddress::from_number("+32111111111").unwrap()),
+                               v1::JsonReaction::default()).await;
+    assert_eq!(result, Err(SignaldError::Unsupported {
+        feature: Feature::Reactions,
+        version: "0.9.0+git2020-03-08r1a9be52a.5".to_string(),
+    }));
+
+    assert!(signald.set_protocol(Protocol::V1).is_err());
+    assert_eq!(signald.protocol(), Protocol::V0);
+}
+
+#[test]
+fn test_lenient_version_from_greeting() {
+    let fake = daemon("0.13.x");
+    let mut signald = fake.connect();
+
+    assert_eq!(signald.capabilities().version, Some(SignaldVersion::new(0, 13, 0)));
+    assert!(signald.set_protocol(Protocol::V1).is_ok());
+}
+
+#[test]
+fn test_unknown_version_is_unsupported() {
+    let fake = daemon("unknown");
+    let start = Instant::now();
+    let mut signald = fake.connect();
+    // The greeting ends the wait, not the timeout
+    assert!(start.elapsed() < Duration::from_millis(900));
+
+    assert_eq!(signald.capabilities().version, None);
+    assert!(!signald.capabilities().supports(Feature::Reactions));
+    assert_eq!(signald.set_protocol(Protocol::V1), Err(SignaldError::Unsupported {
+        feature: Feature::ProtocolV1,
+        version: "unknown".to_string(),
+    }));
+}
@@ -0,0 +1,114 @@
+#![cfg(feature = "cli")]
+use signald_rust::socket::fakesignald::FakeSignald;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+static ACCOUNT: &str = "+32000000000";
+
+fn signald_rs(socket: &Path, args: &[&str], stdin: Option<&str>) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_signald-rs"))
+        .arg("--socket").arg(socket)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.unwrap_or_default().as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn send_response(request: &Value, unregistered: bool) -> Value {
+    json!({
+        "type": "send",
+        "version": "v1",
+        "data": {
+            "results": [{
+                "address": request["recipientAddress"],
+                "success": if unregistered { Value::Null } else { json!({ "unidentified": true, "needsSync": true }) },
+                "unregisteredFailure": unregistered
+            }],
+            "timestamp": 1583863426832u64
+        }
+    })
+}
+
+#[test]
+fn test_version() {
+    let fake = FakeSignald::start();
+
+    let output = signald_rs(fake.path(), &["version"], None);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "signald 0.13.1\n");
+}
+
+#[test]
+fn test_send_message() {
+    let fake = FakeSignald::start();
+    fake.on("send", |request| vec![send_response(request, false)]);
+
+    let output = signald_rs(fake.path(), &["--v1", "-a", ACCOUNT, "send", "+32111111111", "deploy finished",
+                                           "--attachment", "/tmp/report.pdf"], None);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "Sent at 1583863426832\n");
+    let request = &fake.requests()[0];
+    assert_eq!(request["username"], ACCOUNT);
+    assert_eq!(request["messageBody"], "deploy finished");
+    assert_eq!(request["attachments"][0]["filename"], "/tmp/report.pdf");
+}
+
+#[test]
+fn test_send_message_from_stdin_as_json() {
+    let fake = FakeSignald::start();
+    fake.on("send", |request| vec![send_response(request, true)]);
+
+    let output = signald_rs(fake.path(), &["--v1", "--json", "-a", ACCOUNT, "send", "+32111111111"], Some("backup failed\n"));
+    assert_eq!(fake.requests()[0]["messageBody"], "backup failed");
+    // A message that didn't reach every recipient makes the command fail
+    assert_eq!(output.status.code(), Some(2));
+    let result: Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(result, json!({
+        "timestamp": 1583863426832u64,
+        "results": [{ "address": "+32111111111", "status": "unregistered" }]
+    }));
+}
+
+#[test]
+fn test_receive() {
+    let fake = FakeSignald::start();
+    fake.on("subscribe", |_| {
+        let message = |body: &str| json!({
+            "type": "message",
+            "id": Value::Null,
+            "data": {
+                "username": ACCOUNT,
+                "source": "+32111111111",
+                "timestamp": 1000,
+                "dataMessage": { "timestamp": 1000, "message": body }
+            }
+        });
+        vec![json!({ "type": "subscribed" }), message("first"), message("second")]
+    });
+
+    let output = signald_rs(fake.path(), &["-a", ACCOUNT, "receive", "-n", "2"], None);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "1000 +32111111111: first\n1000 +32111111111: second\n");
+}
+
+#[test]
+fn test_missing_account_and_socket() {
+    let fake = FakeSignald::start();
+    let output = signald_rs(fake.path(), &["list-contacts"], None);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("an account is required"));
+
+    let output = signald_rs(Path::new("/nonexistent/signald.sock"), &["version"], None);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no signald socket"));
+}
@@ -0,0 +1,49 @@
+use signald_rust::signalderror::SignaldError;
+use signald_rust::signaladdress::SignalAddress;
+use signald_rust::conversation::Conversation;
+use signald_rust::filter::Filter;
+use signald_rust::socket::fakesignald::FakeSignald;
+use std::thread;
+use std::time::Duration;
+
+static ACCOUNT: &str = "+32000000000";
+static ALICE: &str = "+32111111111";
+static BOB: &str = "+32222222222";
+
+#[tokio::test]
+async fn test_next_message_from() {
+    let fake = FakeSignald::start();
+    let mut signald = fake.connect();
+    signald.subscribe(ACCOUNT.to_string()).await.unwrap();
+    let conversation = Conversation::direct(ACCOUNT, SignalAddress::from_number(BOB).unwrap());
+
+    // A message that arrived before the call isn't returned
+    let mut rx = signald.get_rx();
+    fake.deliver(ACCOUNT, BOB, "staging");
+    assert!(rx.iter().any(|response| Filter::Conversation(conversation.clone()).matches(&response)));
+
+    let mut rx = signald.get_rx();
+    let message = thread::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(Duration::from_millis(200));
+            fake.deliver(ACCOUNT, ALICE, "from alice");
+            fake.deliver(ACCOUNT, BOB, "production");
+        });
+        futures::executor::block_on(signald.next_message_from(&conversation, Duration::from_secs(3)))
+    }).unwrap();
+    assert_eq!(message.data_message.unwrap().message, "production");
+
+    // The other sender's message is still on the bus for other receivers
+    let first = rx.iter().find(|response| response.data.type_name() == "message").unwrap();
+    assert_eq!(first.to_value()["data"]["source"], ALICE);
+}
+
+#[tokio::test]
+async fn test_next_message_from_timeout() {
+    let fake = FakeSignald::start();
+    let mut signald = fake.connect();
+
+    let conversation = Conversation::group(ACCOUNT, "Z3JvdXBpZA==");
+    let result = signald.next_message_from(&conversation, Duration::from_millis(500)).await;
+    assert_eq!(result, Err(SignaldError::Timeout));
+}
@@ -0,0 +1,198 @@
+use signald_rust::signaladdress::SignalAddress;
+use signald_rust::signaldresponse::{ResponseType, RecipientStatus};
+use signald_rust::signalderror::SignaldError;
+use signald_rust::signaldrequest::Recipient;
+use signald_rust::socket::fakesignald::{FakeSignald, Fault};
+use signald_rust::v1::Protocol;
+use serde_json::json;
+use std::time::Duration;
+
+static ACCOUNT: &str = "+32000000000";
+static ALICE: &str = "+32111111111";
+static BOB: &str = "+32222222222";
+
+fn address(number: &str) -> SignalAddress {
+    SignalAddress::from_number(number).unwrap()
+}
+
+#[tokio::test]
+async fn test_subscribe_and_receive() {
+    let fake = FakeSignald::start();
+    let mut signald = fake.connect();
+    let mut other = fake.connect();
+
+    assert_eq!(signald.subscribe(ACCOUNT.to_string()).await.unwrap().data, ResponseType::Subscribed);
+    other.subscribe(BOB.to_string()).await.unwrap();
+    let mut other_rx = other.get_rx();
+
+    let rx = signald.get_rx();
+    fake.deliver(ACCOUNT, ALICE, "first");
+    fake.deliver(BOB, ALICE, "for bob");
+    fake.deliver(ACCOUNT, ALICE, "second");
+    let bodies: Vec<String> = rx.into_iter()
+        .filter_map(|response| match response.data {
+            ResponseType::Message(Some(message)) => Some(message.data_message.unwrap().message),
+            _ => None,
+        })
+        .take(2)
+        .collect();
+    assert_eq!(bodies, vec!["first", "second"]);
+
+    // Messages only reach the clients subscribed to their account
+    let other_message = other_rx.iter()
+        .find_map(|response| match response.data {
+            ResponseType::Message(Some(message)) => Some(message),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(other_message.username.as_deref(), Some(BOB));
+    assert_eq!(other_message.data_message.unwrap().message, "for bob");
+}
+
+#[tokio::test]
+async fn test_send_results() {
+    let fake = FakeSignald::start();
+    fake.add_group(ACCOUNT, "Z3JvdXBpZA==", "Ops", &[ACCOUNT, ALICE, BOB]);
+    fake.set_unregistered(BOB);
+    let mut signald = fake.connect();
+
+    let result = signald.send(ACCOUNT.to_string(), address(ALICE), Some("v0".to_string())).await.unwrap();
+    assert!(result.is_success());
+    assert_eq!(fake.requests()[0]["messageBody"], "v0");
+
+    signald.set_protocol(Protocol::V1).unwrap();
+    let result = signald.send_group(ACCOUNT.to_string(), "Z3JvdXBpZA==".to_string(), Some("v1".to_string())).await.unwrap();
+    assert_eq!(result.results.len(), 2);
+    assert!(result.get(&address(ALICE)).unwrap().is_success());
+    assert_eq!(result.get(&address(BOB)), Some(&RecipientStatus::UnregisteredFailure));
+    assert_eq!(fake.requests()[1]["version"], "v1");
+}
+
+#[tokio::test]
+async fn test_edit_message() {
+    let fake = FakeSignald::start();
+    fake.set_version("0.23.0");
+    let mut signald = fake.connect();
+    let mut account = signald.account(ACCOUNT);
+
+    let sent = account.send(address(ALICE), Some("deploy finished".to_string())).await.unwrap();
+    let edited = account.edit_message(Recipient::Address(address(ALICE)), sent.timestamp, "deploy finished (staging)".to_string()).await.unwrap();
+    assert!(edited.is_success());
+
+    let request = &fake.requests()[1];
+    assert_eq!(request["version"], "v1");
+    assert_eq!(request["editTimestamp"], sent.timestamp);
+    assert_eq!(request["messageBody"], "deploy finished (staging)");
+
+    // Older signald versions can't edit messages
+    fake.set_version("0.13.1");
+    let mut old = fake.connect();
+    let result = old.edit_message(ACCOUNT.to_string(), Recipient::Address(address(ALICE)), sent.timestamp, "edit".to_string()).await;
+    assert!(matches!(result, Err(SignaldError::Unsupported { .. })));
+}
+
+#[tokio::test]
+async fn test_contacts_and_groups() {
+    let fake = FakeSignald::start();
+    fake.add_contact(ACCOUNT, ALICE, "Alice");
+    let mut signald = fake.connect();
+    let mut account = signald.account(ACCOUNT);
+
+    match account.list_contacts().await.unwrap().data {
+        ResponseType::ContactList(Some(contacts)) => assert_eq!(contacts[0].name.as_deref(), Some("Alice")),
+        other => panic!("Expected a contact list, got {:?}", other),
+    }
+
+    let created = account.create_group("Ops".to_string(), vec![address(ALICE)]).await.unwrap();
+    let group_id = created.data.data_value().unwrap()["groupId"].as_str().unwrap().to_string();
+    let groups = account.list_groups().await.unwrap().data.data_value().unwrap();
+    assert_eq!(groups["groups"][0]["name"], "Ops");
+    assert_eq!(groups["groups"][0]["members"], json!([{ "number": ACCOUNT }, { "number": ALICE }]));
+
+    account.leave_group(group_id.clone()).await.unwrap();
+    match account.leave_group(group_id).await {
+        Err(SignaldError::InputError(error)) => assert!(error.message.starts_with("unknown group")),
+        other => panic!("Expected an input error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_link() {
+    let fake = FakeSignald::start();
+    fake.add_account(ACCOUNT);
+    let mut signald = fake.connect();
+
+    let rx = signald.get_rx();
+    let response = signald.link_device(Some("ci".to_string())).await.unwrap();
+    assert!(matches!(response.data, ResponseType::LinkingUri(Some(_))));
+    assert_eq!(fake.requests()[0]["deviceName"], "ci");
+
+    let linked = rx.into_iter().find(|r| r.id == response.id && r.data.type_name() == "linking_successful").unwrap();
+    assert_eq!(linked.data.data_value().unwrap()["username"], ACCOUNT);
+}
+
+#[tokio::test]
+async fn test_version_capabilities() {
+    let fake = FakeSignald::start();
+    fake.set_version("0.10.0");
+    let mut signald = fake.connect();
+
+    assert!(signald.set_protocol(Protocol::V1).is_err());
+    match signald.version().await.unwrap().data {
+        ResponseType::Version(Some(version)) => assert_eq!(version.version, "0.10.0"),
+        other => panic!("Expected a version, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_framing_faults() {
+    let fake = FakeSignald::start();
+    fake.add_contact(ACCOUNT, ALICE, "Alice");
+    let mut signald = fake.connect();
+
+    // Responses written in small pieces, with "\r\n" endings or after a line that isn't json are read whole
+    for fault in [Fault::Split(3), Fault::CrLf, Fault::Garbage("not json".to_string()), Fault::Delay(Duration::from_millis(200))] {
+        fake.inject(fault.clone());
+        match signald.list_contacts(ACCOUNT.to_string()).await {
+            Ok(response) => assert!(matches!(response.data, ResponseType::ContactList(Some(_))), "{:?}", fault),
+            Err(error) => panic!("{:?} failed: {}", fault, error),
+        }
+    }
+
+    fake.inject(Fault::Error("input_error".to_string(), "invalid username".to_string()));
+    assert!(matches!(signald.list_contacts(ACCOUNT.to_string()).await, Err(SignaldError::InputError(_))));
+}
+
+#[tokio::test]
+async fn test_missing_response_and_disconnect() {
+    let fake = FakeSignald::start();
+    let mut signald = fake.connect();
+
+    fake.inject(Fault::DropResponse);
+    assert!(matches!(signald.subscribe(ACCOUNT.to_string()).await, Err(SignaldError::Timeout)));
+    // The connection still works after a missing response
+    assert!(signald.subscribe(ACCOUNT.to_string()).await.is_ok());
+
+    fake.inject(Fault::Disconnect);
+    assert!(signald.subscribe(ACCOUNT.to_string()).await.is_err());
+    assert!(fake.wait_for_request("subscribe", Duration::from_secs(1)).is_some());
+}
+
+#[tokio::test]
+async fn test_sync_contacts_keeps_stream_flowing() {
+    let fake = FakeSignald::start();
+    let mut signald = fake.connect();
+    signald.subscribe(ACCOUNT.to_string()).await.unwrap();
+
+    // The response of a sync request is never read, so it must not hold back the socket
+    signald.sync_contacts(ACCOUNT.to_string()).unwrap();
+    let rx = signald.get_rx();
+    for i in 0..150 {
+        fake.deliver(ACCOUNT, ALICE, &i.to_string());
+    }
+    let count = rx.into_iter()
+        .filter(|response| matches!(response.data, ResponseType::Message(Some(_))))
+        .take(150)
+        .count();
+    assert_eq!(count, 150);
+}
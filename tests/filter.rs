@@ -0,0 +1,65 @@
+use signald_rust::filter::{Filter, MessageKind};
+use signald_rust::signaladdress::SignalAddress;
+use signald_rust::signalderror::SignaldError;
+use signald_rust::signaldresponse::ResponseType;
+use signald_rust::socket::fakesignald::FakeSignald;
+use std::thread;
+use std::time::Duration;
+
+static ACCOUNT: &str = "+32000000000";
+static ALICE: &str = "+32111111111";
+static BOB: &str = "+32222222222";
+
+#[tokio::test]
+async fn test_messages_stream() {
+    let fake = FakeSignald::start();
+    let mut signald = fake.connect();
+    signald.subscribe(ACCOUNT.to_string()).await.unwrap();
+
+    let filter = Filter::Sender(SignalAddress::from_number(ALICE).unwrap())
+        .and(!Filter::body("^/").unwrap())
+        .and(Filter::within(Duration::from_secs(60)));
+    let messages = signald.messages(filter);
+
+    fake.deliver(ACCOUNT, BOB, "from bob");
+    fake.deliver(ACCOUNT, ALICE, "/command");
+    fake.deliver(ACCOUNT, ALICE, "hello");
+    fake.deliver(ACCOUNT, ALICE, "bye");
+    let bodies: Vec<String> = messages.take(2)
+        .map(|message| message.data_message.unwrap().message)
+        .collect();
+    assert_eq!(bodies, vec!["hello", "bye"]);
+}
+
+#[tokio::test]
+async fn test_wait_for() {
+    let fake = FakeSignald::start();
+    let mut signald = fake.connect();
+    signald.subscribe(ACCOUNT.to_string()).await.unwrap();
+
+    let filter = Filter::Account(ACCOUNT.to_string())
+        .and(Filter::Kind(MessageKind::Text))
+        .and(Filter::body("(?i)deploy").unwrap().or(Filter::HasAttachment));
+
+    // Messages that arrived before the call aren't returned
+    let mut rx = signald.get_rx();
+    fake.deliver(ACCOUNT, BOB, "Deploy started");
+    assert!(rx.iter().any(|response| filter.matches(&response)));
+
+    let response = thread::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(Duration::from_millis(200));
+            fake.deliver(ACCOUNT, BOB, "lunch?");
+            fake.deliver(ACCOUNT, BOB, "Deploy finished");
+        });
+        futures::executor::block_on(signald.wait_for(&filter, Duration::from_secs(3)))
+    }).unwrap();
+
+    match response.data {
+        ResponseType::Message(Some(message)) => assert_eq!(message.data_message.unwrap().message, "Deploy finished"),
+        other => panic!("Expected a message, got {:?}", other),
+    }
+
+    let result = signald.wait_for(&Filter::Group("Z3JvdXBpZA==".to_string()), Duration::from_millis(300)).await;
+    assert!(matches!(result, Err(SignaldError::Timeout)));
+}
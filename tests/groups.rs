@@ -0,0 +1,244 @@
+use signald_rust::groups::{GroupTracker, GroupEvent, AccessLevel};
+use signald_rust::signald::Signald;
+use signald_rust::signaladdress::SignalAddress;
+use signald_rust::signalderror::SignaldError;
+use signald_rust::signaldresponse::{SignaldResponse, ResponseType, MessageData};
+use signald_rust::socket::fakesignald::FakeSignald;
+use signald_rust::v1;
+use serde_json::{json, Value};
+
+static ACCOUNT: &str = "+32000000000";
+static ALICE: &str = "+32111111111";
+static BOB: &str = "+32222222222";
+static GROUP_ID: &str = "Z3JvdXBpZA==";
+static ALICE_UUID: &str = "8f2c4b4e-1d3a-4c8e-9a71-0123456789ab";
+
+// A daemon with a group the account is in
+fn group_daemon() -> (FakeSignald, Signald) {
+    let fake = FakeSignald::start();
+    fake.add_group(ACCOUNT, GROUP_ID, "Ops", &[ACCOUNT, ALICE]);
+    let signald = fake.connect();
+    (fake, signald)
+}
+
+fn last_request(fake: &FakeSignald) -> Value {
+    fake.requests().pop().unwrap()
+}
+
+fn address(number: &str) -> SignalAddress {
+    SignalAddress::from_number(number).unwrap()
+}
+
+fn group_message(source: &str, group: Value) -> MessageData {
+    let response = SignaldResponse::from_value(json!({
+        "type": "message",
+        "data": {
+            "username": ACCOUNT,
+            "source": source,
+            "timestamp": 1000,
+            "dataMessage": { "timestamp": 1000, "groupV2": group }
+        }
+    }));
+    match response.data {
+        ResponseType::Message(Some(message)) => message,
+        other => panic!("Expected a message, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_invitations_and_join_requests() {
+    let (fake, mut signald) = group_daemon();
+    fake.on("join_group", |_| vec![json!({
+        "type": "join_group",
+        "version": "v1",
+        "data": { "groupID": GROUP_ID, "title": "Ops", "memberCount": 3, "pendingAdminApproval": true }
+    })]);
+    let mut account = signald.account(ACCOUNT);
+
+    let join_info = account.join_group("https://signal.group/#CjQKIA".to_string()).await.unwrap();
+    assert_eq!(join_info.pending_admin_approval, Some(true));
+    assert_eq!(last_request(&fake)["uri"], "https://signal.group/#CjQKIA");
+
+    account.approve_join_requests(GROUP_ID.to_string(), vec![address(ALICE)]).await.unwrap();
+    let request = last_request(&fake);
+    assert_eq!(request["type"], "approve_membership");
+    assert_eq!(request["members"], json!([{ "number": ALICE }]));
+
+    let group = account.deny_join_requests(GROUP_ID.to_string(), vec![address(BOB)], true).await.unwrap();
+    assert_eq!(group.title.as_deref(), Some("Ops"));
+    let request = last_request(&fake);
+    assert_eq!(request["type"], "refuse_membership");
+    assert_eq!(request["also_ban"], true);
+
+    account.decline_invitation(GROUP_ID.to_string()).await.unwrap();
+    let request = last_request(&fake);
+    assert_eq!(request["type"], "leave_group");
+    assert_eq!(request["version"], "v1");
+    assert_eq!(request["groupID"], GROUP_ID);
+}
+
+#[tokio::test]
+async fn test_admin_controls() {
+    let (fake, mut signald) = group_daemon();
+    let mut account = signald.account(ACCOUNT);
+
+    let alice = SignalAddress::new(Some(ALICE), Some(ALICE_UUID)).unwrap();
+    let group = account.promote_admin(GROUP_ID.to_string(), alice.clone()).await.unwrap();
+    assert_eq!(group.revision, 2);
+    let request = last_request(&fake);
+    assert_eq!(request["type"], "update_group");
+    assert_eq!(request["updateRole"], json!({ "uuid": ALICE_UUID, "role": "ADMINISTRATOR" }));
+
+    account.demote_admin(GROUP_ID.to_string(), alice).await.unwrap();
+    assert_eq!(last_request(&fake)["updateRole"]["role"], "DEFAULT");
+
+    // The role of a member is changed by uuid
+    let result = account.promote_admin(GROUP_ID.to_string(), address(BOB)).await;
+    assert!(matches!(result, Err(SignaldError::InputError(_))));
+
+    account.reset_group_link(GROUP_ID.to_string()).await.unwrap();
+    assert_eq!(last_request(&fake)["resetLink"], true);
+
+    let access_control = v1::GroupAccessControl {
+        link: Some(AccessLevel::Administrator.to_string()),
+        attributes: Some(AccessLevel::Member.to_string()),
+        ..Default::default()
+    };
+    account.set_group_access(GROUP_ID.to_string(), access_control).await.unwrap();
+    assert_eq!(last_request(&fake)["updateAccessControl"], json!({ "link": "ADMINISTRATOR", "attributes": "MEMBER" }));
+
+    // Groups the account isn't in are errors
+    let result = account.reset_group_link("b3RoZXI=".to_string()).await;
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_group_update_events() {
+    let mut tracker = GroupTracker::new();
+    tracker.insert(ACCOUNT, &serde_json::from_value(json!({
+        "id": GROUP_ID,
+        "revision": 1,
+        "title": "Ops",
+        "members": [{ "number": ACCOUNT }, { "uuid": ALICE_UUID }],
+        "memberDetail": [{ "uuid": ALICE_UUID, "role": "DEFAULT" }],
+        "pendingMembers": [],
+        "requestingMembers": []
+    })).unwrap());
+
+    let update = tracker.update(&group_message(ACCOUNT, json!({
+        "id": GROUP_ID,
+        "revision": 2,
+        "title": "Operations",
+        "members": [{ "number": ACCOUNT }, { "uuid": ALICE_UUID }],
+        "memberDetail": [{ "uuid": ALICE_UUID, "role": "ADMINISTRATOR" }],
+        "pendingMembers": [{ "number": BOB }],
+        "requestingMembers": [],
+        "accessControl": { "link": "ADMINISTRATOR" },
+        "inviteLink": "https://signal.group/#CjQKIB"
+    }))).unwrap();
+    assert_eq!(update.group_id, GROUP_ID);
+    assert_eq!(update.revision, Some(2));
+    assert_eq!(update.editor, Some(address(ACCOUNT)));
+    assert_eq!(update.events, vec![
+        GroupEvent::TitleChanged("Operations".to_string()),
+        GroupEvent::MemberInvited(address(BOB)),
+        GroupEvent::AdminPromoted(SignalAddress::from_uuid(ALICE_UUID).unwrap()),
+        GroupEvent::AccessControlChanged(v1::GroupAccessControl {
+            link: Some("ADMINISTRATOR".to_string()),
+            ..Default::default()
+        }),
+        GroupEvent::InviteLinkChanged("https://signal.group/#CjQKIB".to_string()),
+    ]);
+
+    // Ordinary messages carry the current revision and aren't updates
+    assert_eq!(tracker.update(&group_message(ALICE, json!({ "id": GROUP_ID, "revision": 2 }))), None);
+
+    let update = tracker.update(&group_message(BOB, json!({
+        "id": GROUP_ID,
+        "revision": 3,
+        "members": [{ "number": ACCOUNT }, { "number": BOB }],
+        "pendingMembers": []
+    }))).unwrap();
+    assert_eq!(update.events, vec![
+        GroupEvent::MemberJoined(address(BOB)),
+        GroupEvent::MemberLeft(SignalAddress::from_uuid(ALICE_UUID).unwrap()),
+    ]);
+}
+
+#[test]
+fn test_legacy_group_updates() {
+    let legacy = |source: &str, typ: &str, members: Value| {
+        let response = SignaldResponse::from_value(json!({
+            "type": "message",
+            "data": {
+                "username": ACCOUNT,
+                "source": source,
+                "dataMessage": { "groupInfo": { "groupId": GROUP_ID, "type": typ, "name": "Ops", "members": members } }
+            }
+        }));
+        match response.data {
+            ResponseType::Message(Some(message)) => message,
+            _ => unreachable!(),
+        }
+    };
+
+    let mut tracker = GroupTracker::new();
+    // The first update of an unknown group is only remembered
+    let first = tracker.update(&legacy(ACCOUNT, "UPDATE", json!([ACCOUNT, ALICE]))).unwrap();
+    assert_eq!(first.events, vec![GroupEvent::FirstSeen]);
+
+    let update = tracker.update(&legacy(ACCOUNT, "UPDATE", json!([ACCOUNT, ALICE, BOB]))).unwrap();
+    assert_eq!(update.events, vec![GroupEvent::MemberJoined(address(BOB))]);
+
+    let quit = tracker.update(&legacy(ALICE, "QUIT", Value::Null)).unwrap();
+    assert_eq!(quit.events, vec![GroupEvent::MemberLeft(address(ALICE))]);
+    assert!(tracker.update(&legacy(ALICE, "DELIVER", Value::Null)).is_none());
+}
+
+#[test]
+fn test_first_update_of_unknown_group() {
+    // Groups that changed before the tracker started aren't known yet
+    let mut tracker = GroupTracker::new();
+    let update = tracker.update(&group_message(ALICE, json!({
+        "id": GROUP_ID,
+        "revision": 5,
+        "members": [{ "uuid": ALICE_UUID }, { "number": ACCOUNT }],
+        "pendingMembers": [{ "number": BOB }]
+    }))).unwrap();
+    // Nobody joined, the group just wasn't known
+    assert_eq!(update.events, vec![GroupEvent::FirstSeen]);
+    assert_eq!(tracker.update(&group_message(ALICE, json!({ "id": GROUP_ID, "revision": 5 }))), None);
+
+    let update = tracker.update(&group_message(ALICE, json!({
+        "id": GROUP_ID,
+        "revision": 6,
+        "members": [{ "uuid": ALICE_UUID }, { "number": ACCOUNT }, { "number": BOB }],
+        "pendingMembers": []
+    }))).unwrap();
+    assert_eq!(update.events, vec![GroupEvent::MemberJoined(address(BOB))]);
+}
+
+#[test]
+fn test_insert_legacy_group() {
+    let mut tracker = GroupTracker::new();
+    tracker.insert_legacy(ACCOUNT, &serde_json::from_value(json!({
+        "groupId": GROUP_ID,
+        "type": "UPDATE",
+        "name": "Ops",
+        "members": [ACCOUNT, ALICE]
+    })).unwrap());
+
+    let response = SignaldResponse::from_value(json!({
+        "type": "message",
+        "data": {
+            "username": ACCOUNT,
+            "source": ALICE,
+            "dataMessage": { "groupInfo": { "groupId": GROUP_ID, "type": "UPDATE", "name": "Ops", "members": [ACCOUNT, ALICE, BOB] } }
+        }
+    }));
+    let update = match response.data {
+        ResponseType::Message(Some(message)) => tracker.update(&message).unwrap(),
+        other => panic!("Expected a message, got {:?}", other),
+    };
+    assert_eq!(update.events, vec![GroupEvent::MemberJoined(address(BOB))]);
+}
@@ -0,0 +1,250 @@
+use signald_rust::signald::Signald;
+use signald_rust::signaldrequest::Recipient;
+use signald_rust::signaladdress::SignalAddress;
+use signald_rust::queue::{OutboundQueue, QueueConfig, DeliveryStatus};
+use signald_rust::v1::Protocol;
+use signald_rust::socket::fakesignald::FakeSignald;
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use std::time::Duration;
+
+static ACCOUNT: &str = "+32000000000";
+static RECIPIENT: &str = "+32111111111";
+
+fn send_response(result: Value) -> Value {
+    let mut result = result;
+    result["address"] = json!({ "number": RECIPIENT });
+    json!({
+        "type": "send",
+        "version": "v1",
+        "data": {
+            "results": [result],
+            "timestamp": 1583863426832u64
+        }
+    })
+}
+
+fn success() -> Value {
+    send_response(json!({ "success": { "unidentified": true, "needsSync": true }, "networkFailure": false, "unregisteredFailure": false }))
+}
+
+fn network_failure() -> Value {
+    send_response(json!({ "networkFailure": true, "unregisteredFailure": false }))
+}
+
+// A daemon that answers every send with the next response, the last one is repeated
+fn connect(responses: Vec<Value>) -> (FakeSignald, Signald) {
+    let fake = FakeSignald::start();
+    let responses = Mutex::new(responses);
+    fake.on("send", move |_| {
+        let mut responses = responses.lock().unwrap();
+        vec![if responses.len() > 1 { responses.remove(0) } else { responses[0].clone() }]
+    });
+    let mut signald = fake.connect();
+    signald.set_protocol(Protocol::V1).unwrap();
+    (fake, signald)
+}
+
+fn config() -> QueueConfig {
+    QueueConfig {
+        initial_backoff: Duration::from_millis(50),
+        ..Default::default()
+    }
+}
+
+fn recipient() -> Recipient {
+    Recipient::Address(SignalAddress::from_number(RECIPIENT).unwrap())
+}
+
+#[tokio::test]
+async fn test_retry_until_sent() {
+    let (_fake, mut signald) = connect(vec![network_failure(), network_failure(), success()]);
+    let mut queue = OutboundQueue::new(config());
+
+    let id = queue.push(ACCOUNT.to_string(), recipient(), "server down".to_string()).unwrap();
+    assert_eq!(queue.status(id), Some(DeliveryStatus::Pending));
+
+    assert!(queue.flush(&mut signald, Duration::from_secs(5)).await.unwrap());
+    assert_eq!(queue.status(id), Some(DeliveryStatus::Sent { timestamp: 1583863426832 }));
+    assert_eq!(queue.get(id).unwrap().attempts, 3);
+}
+
+#[tokio::test]
+async fn test_give_up_after_max_attempts() {
+    let (_fake, mut signald) = connect(vec![network_failure()]);
+    let mut queue = OutboundQueue::new(QueueConfig {
+        max_attempts: 2,
+        ..config()
+    });
+
+    let id = queue.push(ACCOUNT.to_string(), recipient(), "server down".to_string()).unwrap();
+    assert!(queue.flush(&mut signald, Duration::from_secs(5)).await.unwrap());
+    match queue.status(id) {
+        Some(DeliveryStatus::Failed { error }) => assert!(error.contains("network failure")),
+        status => panic!("Expected a failed message, got {:?}", status),
+    }
+    assert_eq!(queue.get(id).unwrap().attempts, 2);
+}
+
+#[tokio::test]
+async fn test_unregistered_recipient_fails_at_once() {
+    let unregistered = send_response(json!({ "networkFailure": false, "unregisteredFailure": true }));
+    let (_fake, mut signald) = connect(vec![unregistered]);
+    let mut queue = OutboundQueue::new(config());
+
+    let id = queue.push(ACCOUNT.to_string(), recipient(), "hello".to_string()).unwrap();
+    let tried = queue.process(&mut signald).await.unwrap();
+    assert_eq!(tried.len(), 1);
+    assert!(matches!(queue.status(id), Some(DeliveryStatus::Failed { .. })));
+    assert_eq!(queue.get(id).unwrap().attempts, 1);
+}
+
+#[tokio::test]
+async fn test_rate_limit() {
+    let (_fake, mut signald) = connect(vec![success()]);
+    let mut queue = OutboundQueue::new(QueueConfig {
+        rate_limit: 2,
+        rate_limit_period: Duration::from_secs(60),
+        ..config()
+    });
+
+    for body in &["one", "two", "three"] {
+        queue.push(ACCOUNT.to_string(), recipient(), body.to_string()).unwrap();
+    }
+    assert_eq!(queue.process(&mut signald).await.unwrap().len(), 2);
+    assert_eq!(queue.pending(), 1);
+    assert!(queue.process(&mut signald).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_signald_rate_limit_pauses_queue() {
+    let rate_limited = json!({
+        "type": "send",
+        "version": "v1",
+        "error_type": "RateLimitError",
+        "error": { "message": "rate limited" }
+    });
+    let (_fake, mut signald) = connect(vec![rate_limited, success()]);
+    let mut queue = OutboundQueue::new(QueueConfig {
+        initial_backoff: Duration::from_millis(300),
+        ..config()
+    });
+
+    let first = queue.push(ACCOUNT.to_string(), recipient(), "one".to_string()).unwrap();
+    queue.push(ACCOUNT.to_string(), recipient(), "two".to_string()).unwrap();
+    // The second message isn't tried while the queue is paused
+    assert_eq!(queue.process(&mut signald).await.unwrap().len(), 1);
+    assert_eq!(queue.get(first).unwrap().last_error.as_deref(), Some("RateLimitError: rate limited"));
+
+    assert!(queue.flush(&mut signald, Duration::from_secs(5)).await.unwrap());
+    assert_eq!(queue.pending(), 0);
+}
+
+#[tokio::test]
+async fn test_retry_unexpected_response() {
+    let (_fake, mut signald) = connect(vec![json!({ "type": "subscribed" }), success()]);
+    let mut queue = OutboundQueue::new(config());
+
+    let id = queue.push(ACCOUNT.to_string(), recipient(), "hello".to_string()).unwrap();
+    queue.process(&mut signald).await.unwrap();
+    assert_eq!(queue.status(id), Some(DeliveryStatus::Pending));
+    assert!(queue.get(id).unwrap().last_error.as_ref().unwrap().contains("expected a send response"));
+
+    assert!(queue.flush(&mut signald, Duration::from_secs(5)).await.unwrap());
+    assert_eq!(queue.status(id), Some(DeliveryStatus::Sent { timestamp: 1583863426832 }));
+}
+
+#[tokio::test]
+async fn test_retry_failed_group_members_only() {
+    let member = "+32222222222";
+    let partly_sent = json!({
+        "type": "send",
+        "version": "v1",
+        "data": {
+            "results": [
+                { "address": { "number": RECIPIENT }, "success": { "unidentified": true, "needsSync": true }, "networkFailure": false, "unregisteredFailure": false },
+                { "address": { "number": member }, "networkFailure": true, "unregisteredFailure": false }
+            ],
+            "timestamp": 1583863426832u64
+        }
+    });
+    let retried = json!({
+        "type": "send",
+        "version": "v1",
+        "data": {
+            "results": [
+                { "address": { "number": member }, "success": { "unidentified": true, "needsSync": true }, "networkFailure": false, "unregisteredFailure": false }
+            ],
+            "timestamp": 1583863499999u64
+        }
+    });
+    let (fake, mut signald) = connect(vec![partly_sent, retried]);
+    let mut queue = OutboundQueue::new(config());
+
+    let id = queue.push(ACCOUNT.to_string(), Recipient::Group("Z3JvdXBpZA==".to_string()), "hello group".to_string()).unwrap();
+    queue.process(&mut signald).await.unwrap();
+    assert_eq!(queue.get(id).unwrap().remaining_members, vec![SignalAddress::from_number(member).unwrap()]);
+
+    assert!(queue.flush(&mut signald, Duration::from_secs(5)).await.unwrap());
+    assert_eq!(queue.status(id), Some(DeliveryStatus::Sent { timestamp: 1583863426832 }));
+
+    let sends: Vec<Value> = fake.requests().into_iter().filter(|r| r["type"] == "send").collect();
+    assert_eq!(sends.len(), 2);
+    assert!(sends[0].get("members").is_none());
+    assert_eq!(sends[1]["members"], json!([{ "number": member }]));
+    assert_eq!(sends[1]["timestamp"], 1583863426832u64);
+}
+
+#[test]
+fn test_persist_next_to_tmp_file() {
+    let path = std::env::temp_dir().join(format!("signald-rust-queue-{}.tmp", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    {
+        let mut queue = OutboundQueue::open(&path, config()).unwrap();
+        queue.push(ACCOUNT.to_string(), recipient(), "critical alert".to_string()).unwrap();
+        queue.push(ACCOUNT.to_string(), recipient(), "second alert".to_string()).unwrap();
+    }
+
+    let queue = OutboundQueue::open(&path, config()).unwrap();
+    assert_eq!(queue.pending(), 2);
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(path.with_extension("tmp.id")).unwrap();
+}
+
+#[test]
+fn test_persist_pending_messages() {
+    let path = std::env::temp_dir().join(format!("signald-rust-queue-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    {
+        let mut queue = OutboundQueue::open(&path, config()).unwrap();
+        queue.push(ACCOUNT.to_string(), recipient(), "critical alert".to_string()).unwrap();
+        queue.push(ACCOUNT.to_string(), Recipient::Group("Z3JvdXBpZA==".to_string()), "second alert".to_string()).unwrap();
+    }
+
+    let mut queue = OutboundQueue::open(&path, config()).unwrap();
+    assert_eq!(queue.pending(), 2);
+    assert_eq!(queue.get(1).unwrap().recipient, Recipient::Group("Z3JvdXBpZA==".to_string()));
+    assert_eq!(queue.push(ACCOUNT.to_string(), recipient(), "third alert".to_string()).unwrap(), 2);
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(path.with_extension("jsonl.id")).unwrap();
+}
+
+#[tokio::test]
+async fn test_ids_not_reused_after_clear() {
+    let path = std::env::temp_dir().join(format!("signald-rust-queue-{}-clear.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let (_fake, mut signald) = connect(vec![success()]);
+    {
+        let mut queue = OutboundQueue::open(&path, config()).unwrap();
+        queue.push(ACCOUNT.to_string(), recipient(), "critical alert".to_string()).unwrap();
+        assert!(queue.flush(&mut signald, Duration::from_secs(5)).await.unwrap());
+        queue.clear_finished().unwrap();
+    }
+
+    // The sent message is gone, a new one still gets a new id after a restart
+    let mut queue = OutboundQueue::open(&path, config()).unwrap();
+    assert_eq!(queue.pending(), 0);
+    assert_eq!(queue.push(ACCOUNT.to_string(), recipient(), "second alert".to_string()).unwrap(), 1);
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(path.with_extension("jsonl.id")).unwrap();
+}
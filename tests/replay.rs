@@ -0,0 +1,73 @@
+use bus::BusReader;
+use signald_rust::signald::Signald;
+use signald_rust::signaladdress::SignalAddress;
+use signald_rust::signaldresponse::{SignaldResponse, ResponseType};
+use signald_rust::socket::fakesignald::FakeSignald;
+use signald_rust::socket::signaldsocket::SignaldSocket;
+use signald_rust::socket::recording::{RecordingSocket, Direction, read_recording};
+use signald_rust::socket::replay::ReplaySocket;
+use signald_rust::v1::Protocol;
+use std::time::{Duration, Instant};
+
+static ACCOUNT: &str = "+32000000000";
+
+fn next_message_body(rx: &mut BusReader<SignaldResponse>) -> String {
+    let end = Instant::now() + Duration::from_secs(3);
+    loop {
+        let response = rx.recv_timeout(end.saturating_duration_since(Instant::now())).expect("No message arrived in time");
+        if let ResponseType::Message(Some(message)) = response.data {
+            return message.data_message.unwrap().message;
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_record_and_replay() {
+    let recording = std::env::temp_dir().join(format!("signald-rust-recording-{}.jsonl", std::process::id()));
+    let fake = FakeSignald::start();
+    let socket = SignaldSocket::connect(fake.path().to_str().unwrap().to_string(), 100);
+
+    let mut signald = Signald::with_socket(Box::new(RecordingSocket::new(socket, &recording).unwrap()));
+    let mut rx = signald.get_rx();
+    assert_eq!(signald.subscribe(ACCOUNT.to_string()).await.unwrap().data, ResponseType::Subscribed);
+    fake.deliver(ACCOUNT, "+32111111111", "recorded");
+    assert_eq!(next_message_body(&mut rx), "recorded");
+
+    // Received lines are recorded before they are broadcast
+    let records = read_recording(&recording).unwrap();
+    let directions: Vec<Direction> = records.iter().map(|r| r.direction).collect();
+    assert_eq!(directions, vec![Direction::In, Direction::Out, Direction::In, Direction::In]);
+    assert_eq!(records[0].message["type"], "version");
+    assert_eq!(records[1].message["type"], "subscribe");
+    assert_eq!(records[2].message["id"], records[1].message["id"]);
+    assert!(records.windows(2).all(|r| r[0].time <= r[1].time));
+
+    // The replayed socket answers the same way without signald
+    let mut replayed = Signald::with_socket(Box::new(ReplaySocket::open(&recording).unwrap()));
+    assert_eq!(replayed.capabilities().version.as_ref().unwrap().to_string(), "0.13.1");
+    let mut rx = replayed.get_rx();
+    assert_eq!(replayed.subscribe(ACCOUNT.to_string()).await.unwrap().data, ResponseType::Subscribed);
+    assert_eq!(next_message_body(&mut rx), "recorded");
+    std::fs::remove_file(&recording).unwrap();
+}
+
+#[tokio::test]
+async fn test_replay_recording_fixture() {
+    let replay = ReplaySocket::open("tests/recordings/send_and_receive.jsonl").unwrap();
+    let sent = replay.sent_requests();
+    let mut signald = Signald::with_socket(Box::new(replay));
+    signald.set_protocol(Protocol::V1).unwrap();
+    let mut rx = signald.get_rx();
+
+    // The recorded response carries another id than this request, it is matched to it anyway
+    let recipient = SignalAddress::from_number("+32111111111").unwrap();
+    let result = signald.send("+32000000000".to_string(), recipient, Some("is the build green?".to_string())).await.unwrap();
+    assert_eq!(result.timestamp, 1583863426832);
+    assert!(result.is_success());
+    assert_eq!(next_message_body(&mut rx), "yes, deploying now");
+
+    let recorded = &read_recording("tests/recordings/send_and_receive.jsonl").unwrap()[1].message;
+    let sent = serde_json::to_value(&sent.lock().unwrap()[0]).unwrap();
+    assert_eq!(sent["messageBody"], recorded["messageBody"]);
+    assert_eq!(sent["recipientAddress"], recorded["recipientAddress"]);
+}
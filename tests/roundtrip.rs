@@ -0,0 +1,437 @@
+use signald_rust::signaldresponse::{SignaldResponse, ResponseType, ParseMode, SendResult, RecipientStatus};
+use serde_json::{json, Value};
+use proptest::prelude::*;
+use proptest::option;
+use proptest::collection::vec;
+
+// Every generated response is checked in both directions:
+// json -> SignaldResponse -> json must give back the original json
+// SignaldResponse -> json -> SignaldResponse must give back the original response
+fn assert_round_trip(original: Value) {
+    let response: SignaldResponse = serde_json::from_value(original.clone()).unwrap();
+    let serialized = serde_json::to_value(&response).unwrap();
+    assert_eq!(serialized, original);
+
+    let reparsed: SignaldResponse = serde_json::from_value(serialized).unwrap();
+    assert_eq!(reparsed, response);
+
+    // A complete response also parses in strict mode
+    assert_eq!(SignaldResponse::parse(original, ParseMode::Strict).unwrap(), response);
+}
+
+// Insert the optional fields that are set into a json object
+fn with_optional(mut val: Value, fields: Vec<(&str, Option<Value>)>) -> Value {
+    for (key, field) in fields {
+        if let Some(field) = field {
+            val[key] = field;
+        }
+    }
+    val
+}
+
+// json -> lenient SignaldResponse -> json must give back the original json, even when keys are missing
+fn assert_lenient_round_trip(original: Value) {
+    let response = SignaldResponse::parse(original.clone(), ParseMode::Lenient).unwrap();
+    assert!(!matches!(response.data, ResponseType::Unknown(..)), "{} didn't parse", original);
+    assert_eq!(response.to_value(), original);
+}
+
+// Leave out the keys of a json object, and of the objects inside it, for which `keep` gives false
+fn without_keys(val: Value, keep: &mut impl Iterator<Item = bool>) -> Value {
+    match val {
+        Value::Object(map) => {
+            let mut partial = serde_json::Map::new();
+            for (key, val) in map {
+                if keep.next().unwrap_or(true) {
+                    partial.insert(key, without_keys(val, keep));
+                }
+            }
+            Value::Object(partial)
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(|val| without_keys(val, keep)).collect()),
+        val => val,
+    }
+}
+
+// A generated json object with some of its keys left out
+fn partial(full: impl Strategy<Value = Value>) -> impl Strategy<Value = Value> {
+    (full, vec(any::<bool>(), 0..64)).prop_map(|(val, keep)| without_keys(val, &mut keep.into_iter()))
+}
+
+fn number() -> impl Strategy<Value = String> {
+    "\\+32[1-9][0-9]{8}"
+}
+
+fn uuid() -> impl Strategy<Value = String> {
+    "[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}"
+}
+
+fn timestamp() -> impl Strategy<Value = i64> {
+    0..4_102_444_800_000i64
+}
+
+fn id() -> impl Strategy<Value = Option<String>> {
+    option::of("[a-z0-9]{1,8}")
+}
+
+prop_compose! {
+    fn attachment()(content_type in "[a-z]+/[a-z]+", id in "[0-9]{1,19}", size in any::<u32>(),
+                    stored_filename in option::of("/[a-z/]{1,30}"), voice_note in option::of(any::<bool>())) -> Value {
+        with_optional(json!({
+            "contentType": content_type,
+            "id": id,
+            "size": size,
+        }), vec![
+            ("storedFilename", stored_filename.map(Value::from)),
+            ("voiceNote", voice_note.map(Value::from)),
+        ])
+    }
+}
+
+prop_compose! {
+    fn message()(timestamp in timestamp(), message in ".*", expires_in_seconds in 0..604_800i32,
+                 attachments in option::of(vec(attachment(), 0..3))) -> Value {
+        with_optional(json!({
+            "timestamp": timestamp,
+            "message": message,
+            "expiresInSeconds": expires_in_seconds,
+        }), vec![
+            ("attachments", attachments.map(Value::from)),
+        ])
+    }
+}
+
+prop_compose! {
+    fn sent_message()(destination in option::of(number()), timestamp in timestamp(), message in message(),
+                      unidentified in any::<bool>(), is_recipient_update in any::<bool>()) -> Value {
+        let mut status = serde_json::Map::new();
+        if let Some(destination) = &destination {
+            status.insert(destination.clone(), Value::from(unidentified));
+        }
+        with_optional(json!({
+            "timestamp": timestamp,
+            "expirationStartTimestamp": 0,
+            "message": message,
+            "unidentifiedStatus": status,
+            "isRecipientUpdate": is_recipient_update,
+        }), vec![
+            ("destination", destination.map(Value::from)),
+        ])
+    }
+}
+
+prop_compose! {
+    fn read_message()(sender in number(), timestamp in timestamp()) -> Value {
+        json!({
+            "sender": sender,
+            "timestamp": timestamp,
+        })
+    }
+}
+
+prop_compose! {
+    fn sync_message()(sent in option::of(sent_message()), contacts_complete in any::<bool>(),
+                      read_messages in option::of(vec(read_message(), 0..3)),
+                      blocked in option::of(vec(number(), 0..3)),
+                      read_receipts in option::of(any::<bool>())) -> Value {
+        with_optional(json!({
+            "contactsComplete": contacts_complete,
+        }), vec![
+            ("sent", sent),
+            ("readMessages", read_messages.map(Value::from)),
+            ("blockedList", blocked.map(|numbers| json!({"numbers": numbers, "groupIds": []}))),
+            ("configuration", read_receipts.map(|r| json!({"readReceipts": r}))),
+        ])
+    }
+}
+
+prop_compose! {
+    fn typing()(action in "STARTED|STOPPED", timestamp in timestamp()) -> Value {
+        json!({
+            "action": action,
+            "timestamp": timestamp,
+        })
+    }
+}
+
+prop_compose! {
+    fn receipt()(typ in "DELIVERY|READ", timestamps in vec(0..i64::MAX, 1..4), when in 0..i64::MAX) -> Value {
+        json!({
+            "type": typ,
+            "timestamps": timestamps,
+            "when": when,
+        })
+    }
+}
+
+prop_compose! {
+    fn message_data()(username in option::of(number()), uuid in option::of(uuid()), source in option::of(number()),
+                      typ in 0..10i32, timestamp in timestamp(), server_timestamp in timestamp(),
+                      flags in any::<[bool; 4]>(),
+                      sync_message in option::of(sync_message()), data_message in option::of(message()),
+                      typing in option::of(typing()), receipt in option::of(receipt())) -> Value {
+        with_optional(json!({
+            "type": typ,
+            "timestamp": timestamp,
+            "timestampISO": "2020-03-10T18:03:46.832Z",
+            "serverTimestamp": server_timestamp,
+            "hasLegacyMessage": flags[0],
+            "hasContent": flags[1],
+            "isReceipt": flags[2],
+            "isUnidentifiedSender": flags[3],
+        }), vec![
+            ("username", username.map(Value::from)),
+            ("uuid", uuid.map(Value::from)),
+            ("source", source.map(Value::from)),
+            ("syncMessage", sync_message),
+            ("dataMessage", data_message),
+            ("typing", typing),
+            ("receipt", receipt),
+        ])
+    }
+}
+
+prop_compose! {
+    fn contact()(name in option::of(".*"), number in number(), color in "[a-z_]{1,10}",
+                 profile_key in option::of("[A-Za-z0-9+/]{4,20}=")) -> Value {
+        with_optional(json!({
+            "number": number,
+            "color": color,
+        }), vec![
+            ("name", name.map(Value::from)),
+            ("profileKey", profile_key.map(Value::from)),
+        ])
+    }
+}
+
+prop_compose! {
+    fn account_info()(username in number(), uuid in option::of(uuid()), device_id in option::of(1..10i32),
+                      registered in any::<bool>(), subscribed in option::of(any::<bool>())) -> Value {
+        with_optional(json!({
+            "username": username,
+            "registered": registered,
+        }), vec![
+            ("uuid", uuid.map(Value::from)),
+            ("deviceId", device_id.map(Value::from)),
+            ("subscribed", subscribed.map(Value::from)),
+        ])
+    }
+}
+
+prop_compose! {
+    // The result for one recipient: a success, a network failure, an unregistered recipient or a changed identity
+    fn recipient_result()(address in prop_oneof![number().prop_map(|n| json!({ "number": n })), uuid().prop_map(|u| json!({ "uuid": u }))],
+                          kind in 0..4, flags in any::<[bool; 2]>(), identity_key in "[A-Za-z0-9+/]{4,20}=") -> Value {
+        let mut result = json!({
+            "address": address,
+            "networkFailure": kind == 1,
+            "unregisteredFailure": kind == 2,
+        });
+        match kind {
+            0 => result["success"] = json!({ "unidentified": flags[0], "needsSync": flags[1] }),
+            3 => result["identityFailure"] = Value::from(identity_key),
+            _ => (),
+        }
+        result
+    }
+}
+
+fn response(typ: &str, id: Option<String>, data: Option<Value>) -> Value {
+    with_optional(json!({ "type": typ }), vec![
+        ("id", id.map(Value::from)),
+        ("data", data),
+    ])
+}
+
+proptest! {
+    #[test]
+    fn test_round_trip_message(id in id(), data in message_data()) {
+        assert_round_trip(response("message", id, Some(data)));
+    }
+
+    #[test]
+    fn test_round_trip_partial_message(id in id(), data in partial(message_data())) {
+        assert_lenient_round_trip(response("message", id, Some(data)));
+    }
+
+    #[test]
+    fn test_round_trip_version(id in id(), name in "[a-z]{1,10}", version in "[0-9a-z.+-]{1,30}",
+                               branch in "[a-z]{1,10}", commit in "[0-9a-f]{40}") {
+        let data = json!({
+            "name": name,
+            "version": version,
+            "branch": branch,
+            "commit": commit,
+        });
+        assert_round_trip(response("version", id, Some(data)));
+    }
+
+    #[test]
+    fn test_round_trip_contact_list(id in id(), contacts in vec(contact(), 0..5)) {
+        assert_round_trip(response("contact_list", id, Some(Value::from(contacts))));
+    }
+
+    #[test]
+    fn test_round_trip_account_list(id in id(), accounts in vec(account_info(), 0..4)) {
+        assert_round_trip(response("account_list", id, Some(json!({ "accounts": accounts }))));
+    }
+
+    #[test]
+    fn test_round_trip_linking_uri(id in id(), uri in "tsdevice:/\\?uuid=[A-Za-z0-9]{22}&pub_key=[A-Za-z0-9]{44}") {
+        assert_round_trip(response("linking_uri", id, Some(json!({ "uri": uri }))));
+    }
+
+    #[test]
+    fn test_round_trip_linking_error(id in id(), msg_number in 0..i32::MAX, message in ".*", error in any::<bool>(),
+                                     expires_in_seconds in 0..i32::MAX, when in 0..i64::MAX) {
+        let data = json!({
+            "msg_number": msg_number,
+            "message": message,
+            "error": error,
+            "request": {
+                "type": "link",
+                "expiresInSeconds": expires_in_seconds,
+                "when": when,
+            }
+        });
+        assert_round_trip(response("linking_error", id, Some(data)));
+    }
+
+    #[test]
+    fn test_round_trip_subscription(id in id(), subscribed in any::<bool>()) {
+        let typ = if subscribed { "subscribed" } else { "unsubscribed" };
+        assert_round_trip(response(typ, id, None));
+    }
+
+    #[test]
+    fn test_round_trip_account_event(id in id(), typ in "listen_started|listen_stopped|account_refreshed", account in number()) {
+        assert_round_trip(response(&typ, id, Some(Value::from(account))));
+    }
+
+    #[test]
+    fn test_round_trip_websocket_connection_state(id in id(), account in number(), state in "CONNECTED|DISCONNECTED|RECONNECTING",
+                                                  socket in option::of("IDENTIFIED|UNIDENTIFIED")) {
+        let data = with_optional(json!({ "account": account, "state": state }), vec![
+            ("socket", socket.map(Value::from)),
+        ]);
+        assert_round_trip(response("websocket_connection_state", id, Some(data)));
+    }
+
+    #[test]
+    fn test_round_trip_error(id in id(), typ in "unexpected_error|input_error|invalid_request", message in ".*",
+                             request_type in option::of("[a-z_]{1,10}")) {
+        let data = with_optional(json!({ "message": message }), vec![
+            ("request", request_type.map(|typ| json!({ "type": typ }))),
+        ]);
+        assert_round_trip(response(&typ, id, Some(data)));
+    }
+
+    #[test]
+    fn test_round_trip_untrusted_identity(id in id(), username in number(), number in number(), fingerprint in "[0-9a-f ]{1,40}",
+                                          safety_number in "[0-9]{60}") {
+        let data = json!({
+            "username": username,
+            "number": number,
+            "fingerprint": fingerprint,
+            "safety_number": safety_number,
+        });
+        assert_round_trip(response("untrusted_identity", id, Some(data)));
+    }
+
+    #[test]
+    fn test_round_trip_v1(id in id(), typ in "[a-z_]{1,12}", timestamp in timestamp(),
+                          error in option::of(("[A-Za-z]{1,20}Error", ".*"))) {
+        let original = match error {
+            Some((error_type, message)) => json!({
+                "type": typ,
+                "version": "v1",
+                "error_type": error_type,
+                "error": { "message": message },
+            }),
+            None => json!({
+                "type": typ,
+                "version": "v1",
+                "data": { "results": [], "timestamp": timestamp },
+            }),
+        };
+        assert_round_trip(with_optional(original, vec![("id", id.map(Value::from))]));
+    }
+
+    #[test]
+    fn test_round_trip_send_results(id in id(), results in vec(recipient_result(), 0..5), timestamp in timestamp()) {
+        let original = response("send_results", id, Some(json!({ "results": results, "timestamp": timestamp })));
+        assert_round_trip(original.clone());
+
+        // Every failure is kept with its kind
+        let send_result = SendResult::from_response(&serde_json::from_value(original).unwrap()).unwrap();
+        prop_assert_eq!(send_result.timestamp, timestamp);
+        prop_assert_eq!(send_result.results.len(), results.len());
+        for (result, original) in send_result.results.iter().zip(&results) {
+            let expected = match (original.get("success"), original.get("identityFailure")) {
+                (Some(_), _) => result.status.is_success(),
+                (None, Some(key)) => result.status == RecipientStatus::IdentityFailure(key.as_str().unwrap().to_string()),
+                (None, None) if original["unregisteredFailure"] == true => result.status == RecipientStatus::UnregisteredFailure,
+                (None, None) => result.status == RecipientStatus::NetworkFailure,
+            };
+            prop_assert!(expected, "{:?} doesn't match {}", result.status, original);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_unknown(id in id(), typ in "x_[a-z_]{1,10}", data in option::of(vec(("[a-z]{1,8}", ".*"), 0..4))) {
+        let data = data.map(|fields| Value::Object(fields.into_iter().map(|(k, v)| (k, Value::from(v))).collect()));
+        assert_round_trip(response(&typ, id, data));
+    }
+}
+
+#[test]
+fn test_round_trip_bus_update() {
+    let response = SignaldResponse {
+        id: None,
+        defaulted: Vec::new(),
+        data: ResponseType::BusUpdate,
+    };
+    let serialized = serde_json::to_value(&response).unwrap();
+    assert_eq!(serialized, json!({ "type": "bus_update" }));
+    assert_eq!(serde_json::from_value::<SignaldResponse>(serialized).unwrap(), response);
+}
+
+#[test]
+fn test_round_trip_sync_message_fixture() {
+    assert_round_trip(json!({
+        "type": "message",
+        "id": "test",
+        "data": {
+            "username": "+32000000000",
+            "source": "+32000000000",
+            "sourceDevice": 1,
+            "type": 1,
+            "timestamp": 1583863426832u64,
+            "timestampISO": "2020-03-10T18:03:46.832Z",
+            "serverTimestamp": 1583863428672u64,
+            "hasLegacyMessage": false,
+            "hasContent": true,
+            "isReceipt": false,
+            "isUnidentifiedSender": false,
+            "syncMessage": {
+                "sent": {
+                    "destination": "+32111111111",
+                    "timestamp": 1583863426832u64,
+                    "expirationStartTimestamp": 0,
+                    "message": {
+                        "timestamp": 1583863426832u64,
+                        "message": "messagedata123",
+                        "expiresInSeconds": 0,
+                        "attachments": []
+                    },
+                    "unidentifiedStatus": {
+                        "+3211111111": true
+                    },
+                    "isRecipientUpdate": false
+                },
+                "contactsComplete": false,
+                "stickerPackOperations": []
+            }
+        }
+    }));
+}
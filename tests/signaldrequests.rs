@@ -0,0 +1,96 @@
+use signald_rust::signaldrequest::*;
+use signald_rust::signaladdress::SignalAddress;
+
+#[test]
+fn test_serialize_send_request() {
+    let mut send_request = SendRequest::new(
+        "+32000000000".to_string(),
+        Recipient::Address(SignalAddress::from_number("+32111111111").unwrap())
+    );
+    send_request.message_body = Some("Hello".to_string());
+    let request = SignaldRequest::with_id(Request::Send(send_request), "1".to_string());
+
+    let json: serde_json::Value = serde_json::from_str(&request.to_json_string()).unwrap();
+    assert_eq!(json, serde_json::json!({
+        "type": "send",
+        "id": "1",
+        "username": "+32000000000",
+        "recipientAddress": {
+            "number": "+32111111111"
+        },
+        "messageBody": "Hello"
+    }));
+}
+
+#[test]
+fn test_serialize_group_send_request() {
+    let send_request = SendRequest::new("+32000000000".to_string(), Recipient::Group("Z3JvdXBpZA==".to_string()));
+    let request = SignaldRequest::new(Request::Send(send_request));
+
+    let json: serde_json::Value = serde_json::from_str(&request.to_json_string()).unwrap();
+    assert_eq!(json, serde_json::json!({
+        "type": "send",
+        "username": "+32000000000",
+        "recipientGroupId": "Z3JvdXBpZA=="
+    }));
+}
+
+#[test]
+fn test_serialize_update_group_request() {
+    let request = Request::UpdateGroup(UpdateGroupRequest {
+        username: "+32000000000".to_string(),
+        recipient_group_id: None,
+        group_name: Some("Friends".to_string()),
+        members: Some(vec![SignalAddress::from_number("+32111111111").unwrap()]),
+        ..Default::default()
+    });
+    let request = SignaldRequest::with_id(request, "2".to_string());
+
+    let json: serde_json::Value = serde_json::from_str(&request.to_json_string()).unwrap();
+    assert_eq!(json, serde_json::json!({
+        "type": "update_group",
+        "id": "2",
+        "username": "+32000000000",
+        "groupName": "Friends",
+        "members": [{
+            "number": "+32111111111"
+        }]
+    }));
+}
+
+#[test]
+fn test_serialize_version_request() {
+    let request = SignaldRequest::with_id(Request::Version, "3".to_string());
+
+    let json: serde_json::Value = serde_json::from_str(&request.to_json_string()).unwrap();
+    assert_eq!(json, serde_json::json!({
+        "type": "version",
+        "id": "3"
+    }));
+}
+
+#[test]
+fn test_serialize_list_accounts_request() {
+    let request = SignaldRequest::with_id(Request::ListAccounts, "5".to_string());
+
+    let json: serde_json::Value = serde_json::from_str(&request.to_json_string()).unwrap();
+    assert_eq!(json, serde_json::json!({
+        "type": "list_accounts",
+        "id": "5"
+    }));
+}
+
+#[test]
+fn test_deserialize_send_request() {
+    let json = r#"{"type":"send","id":"4","username":"+32000000000","recipientGroupId":"Z3JvdXBpZA==","messageBody":"Hi"}"#;
+    let request: SignaldRequest = serde_json::from_str(json).unwrap();
+
+    assert_eq!(request.id.unwrap(), "4");
+    match request.request {
+        Request::Send(send_request) => {
+            assert_eq!(send_request.recipient().unwrap(), Recipient::Group("Z3JvdXBpZA==".to_string()));
+            assert_eq!(send_request.message_body.unwrap(), "Hi");
+        }
+        _ => panic!("Parsed wrong request type")
+    }
+}
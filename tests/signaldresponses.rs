@@ -1,4 +1,6 @@
-use signald_rust::signaldresponse::{SignaldResponse, ResponseType};
+use signald_rust::signaldresponse::{SignaldResponse, ResponseType, ParseMode, SendResult, RecipientStatus};
+use signald_rust::signaladdress::SignalAddress;
+use signald_rust::signalderror::SignaldError;
 
 #[test]
 fn test_parse_sync_message_text() {
@@ -88,7 +90,7 @@ fn test_parse_sync_message_read() {
 
             let sync_message = x.sync_message.unwrap();
             let read_message = sync_message.read_messages.unwrap();
-            assert_eq!(read_message.get(0).unwrap().sender, "+32111111111");
+            assert_eq!(read_message.first().unwrap().sender.clone().unwrap(), "+32111111111");
         }
         _ => panic!("Received wrong response type")
     }
@@ -200,7 +202,7 @@ fn test_parse_receipt_message() {
             let x = x.unwrap();
             let receipt = x.receipt.unwrap();
             assert_eq!(receipt.typ, "DELIVERY");
-            assert_eq!(receipt.timestamps.get(0).unwrap().clone(), 1583863426832u64);
+            assert_eq!(receipt.timestamps.first().unwrap().clone(), 1583863426832i64);
         }
         _ => panic!("Received wrong response type")
     }
@@ -270,11 +272,11 @@ fn test_parse_contact_list_message() {
     match result.data {
         ResponseType::ContactList(x) => {
             let x = x.unwrap();
-            let first_entry = x.get(0).unwrap();
+            let first_entry = x.first().unwrap();
             assert_eq!(first_entry.name, Some("AAAAA".to_string()));
             assert_eq!(first_entry.color, "blue_grey");
             assert_eq!(first_entry.profile_key.clone().unwrap(), "11111=");
-            assert_eq!(first_entry.number, "+32111111111");
+            assert_eq!(first_entry.number.clone().unwrap(), "+32111111111");
         }
         _ => panic!("Received wrong response type")
     }
@@ -321,7 +323,7 @@ fn test_parse_linking_error_message() {
             let x = x.unwrap();
             assert_eq!(x.msg_number, 1);
             assert_eq!(x.message, "Timed out while waiting for device to link");
-            assert_eq!(x.error, true);
+            assert!(x.error);
             assert_eq!(x.request.typ, "link");
             assert_eq!(x.request.expires_in_seconds, 0);
             assert_eq!(x.request.when, 0);
@@ -329,3 +331,452 @@ fn test_parse_linking_error_message() {
         _ => panic!("Received wrong response type")
     }
 }
+
+#[test]
+fn test_parse_sync_message_blocked_list() {
+    let message = serde_json::json!({
+            "type": "message",
+            "data": {
+                "username": "+32000000000",
+                "source": "+32000000000",
+                "sourceDevice": 1,
+                "type": 1,
+                "timestamp": 1583863416850u64,
+                "timestampISO": "2020-03-10T18:03:36.850Z",
+                "serverTimestamp": 1583863418138u64,
+                "hasLegacyMessage": false,
+                "hasContent": true,
+                "isReceipt": false,
+                "isUnidentifiedSender": false,
+                "syncMessage": {
+                    "contactsComplete": false,
+                    "blockedList": {
+                        "numbers": ["+32111111111"],
+                        "groupIds": ["Z3JvdXBpZA=="]
+                    }
+                }
+            }
+        });
+    // Try to parse the message
+    let result = SignaldResponse::from_value(message);
+    match result.data {
+        ResponseType::Message(x) => {
+            let sync_message = x.unwrap().sync_message.unwrap();
+            let blocked_list = sync_message.blocked_list.unwrap();
+            assert_eq!(blocked_list.numbers, vec!["+32111111111"]);
+            assert_eq!(blocked_list.group_ids, vec!["Z3JvdXBpZA=="]);
+        }
+        _ => panic!("Received wrong response type")
+    }
+}
+
+#[test]
+fn test_parse_sync_message_contacts_and_configuration() {
+    let message = serde_json::json!({
+            "type": "message",
+            "data": {
+                "username": "+32000000000",
+                "source": "+32000000000",
+                "sourceDevice": 1,
+                "type": 1,
+                "timestamp": 1583863416850u64,
+                "timestampISO": "2020-03-10T18:03:36.850Z",
+                "serverTimestamp": 1583863418138u64,
+                "hasLegacyMessage": false,
+                "hasContent": true,
+                "isReceipt": false,
+                "isUnidentifiedSender": false,
+                "syncMessage": {
+                    "contacts": {
+                        "contentType": "application/octet-stream",
+                        "id": "1234567890",
+                        "size": 512,
+                        "storedFilename": "/var/lib/signald/attachments/1234567890"
+                    },
+                    "contactsComplete": true,
+                    "configuration": {
+                        "readReceipts": true,
+                        "unidentifiedDeliveryIndicators": false,
+                        "typingIndicators": true,
+                        "linkPreviews": false
+                    },
+                    "viewOnceOpen": {
+                        "sender": "+32111111111",
+                        "timestamp": 1583863416783u64
+                    },
+                    "stickerPackOperations": [{
+                        "packID": "c2lnbmFsZA==",
+                        "packKey": "a2V5",
+                        "type": "INSTALL"
+                    }],
+                    "fetchType": "LOCAL_PROFILE"
+                }
+            }
+        });
+    // Try to parse the message
+    let result = SignaldResponse::from_value(message);
+    match result.data {
+        ResponseType::Message(x) => {
+            let sync_message = x.unwrap().sync_message.unwrap();
+            assert!(sync_message.contacts_complete);
+
+            let contacts = sync_message.contacts.unwrap();
+            assert_eq!(contacts.size, Some(512));
+            assert_eq!(contacts.stored_filename.unwrap(), "/var/lib/signald/attachments/1234567890");
+
+            let configuration = sync_message.configuration.unwrap();
+            assert_eq!(configuration.read_receipts, Some(true));
+            assert_eq!(configuration.link_previews, Some(false));
+
+            let view_once_open = sync_message.view_once_open.unwrap();
+            assert_eq!(view_once_open.sender.clone().unwrap(), "+32111111111");
+            assert_eq!(view_once_open.timestamp, 1583863416783);
+
+            let sticker_pack_operation = sync_message.sticker_pack_operations.unwrap();
+            assert_eq!(sticker_pack_operation.first().unwrap().typ, "INSTALL");
+            assert_eq!(sync_message.fetch_type.unwrap(), "LOCAL_PROFILE");
+        }
+        _ => panic!("Received wrong response type")
+    }
+}
+
+#[test]
+fn test_parse_message_sender_address() {
+    let message = serde_json::json!({
+            "type": "message",
+            "data": {
+                "username": "+32000000000",
+                "uuid": "8f2c4b4e-1d3a-4c8e-9a71-0123456789ab",
+                "source": "+32111111111",
+                "sourceDevice": 1,
+                "type": 1,
+                "timestamp": 1583863470594u64,
+                "timestampISO": "2020-03-10T18:04:30.594Z",
+                "serverTimestamp": 1583863470817u64,
+                "hasLegacyMessage": false,
+                "hasContent": true,
+                "isReceipt": false,
+                "isUnidentifiedSender": false
+            }
+        });
+    // Try to parse the message
+    let result = SignaldResponse::from_value(message);
+    match result.data {
+        ResponseType::Message(x) => {
+            let sender = x.unwrap().sender().unwrap();
+            assert_eq!(sender.number(), Some("+32111111111"));
+            assert_eq!(sender.uuid(), Some("8f2c4b4e-1d3a-4c8e-9a71-0123456789ab"));
+        }
+        _ => panic!("Received wrong response type")
+    }
+}
+
+#[test]
+fn test_parse_lenient_missing_fields() {
+    let message = serde_json::json!({
+            "type": "message",
+            "data": {
+                "username": "+32000000000",
+                "source": "+32111111111",
+                "timestamp": 1583863470594u64,
+                "dataMessage": {
+                    "timestamp": 1583863470594u64,
+                    "reaction": {
+                        "emoji": "👍",
+                        "targetSentTimestamp": 1583863426832u64
+                    },
+                    "sticker": {
+                        "packID": "abc"
+                    }
+                }
+            }
+        });
+    // Missing fields default, unknown fields are kept
+    let result = SignaldResponse::parse(message.clone(), ParseMode::Lenient).unwrap();
+    match result.data.clone() {
+        ResponseType::Message(x) => {
+            let x = x.unwrap();
+            assert_eq!(x.timestamp_iso, "");
+            let data_message = x.data_message.unwrap();
+            assert_eq!(data_message.message, "");
+            assert_eq!(data_message.reaction.unwrap().emoji, "👍");
+            assert_eq!(data_message.extra["sticker"]["packID"], "abc");
+        }
+        _ => panic!("Received wrong response type")
+    }
+    // The filled in fields are left out again
+    assert_eq!(result.to_value(), message);
+    assert_eq!(serde_json::to_value(&result).unwrap(), message);
+
+    // Strict parsing rejects the incomplete message
+    assert!(SignaldResponse::parse(message.clone(), ParseMode::Strict).is_err());
+    match SignaldResponse::from_value_with_mode(message, ParseMode::Strict).data {
+        ResponseType::Unknown(typ, _) => assert_eq!(typ, "message"),
+        _ => panic!("Received wrong response type")
+    }
+}
+
+#[test]
+fn test_missing_address_round_trips() {
+    let message = serde_json::json!({
+            "type": "message",
+            "data": {
+                "username": "+32000000000",
+                "timestamp": 1583863470594u64,
+                "syncMessage": {
+                    "readMessages": [{ "timestamp": 1583863416783u64 }],
+                    "viewOnceOpen": { "timestamp": 1583863416783u64 }
+                },
+                "dataMessage": {
+                    "timestamp": 1583863470594u64,
+                    "reaction": { "emoji": "👍", "targetSentTimestamp": 1583863426832u64 }
+                }
+            }
+        });
+    // A missing address stays missing instead of becoming an empty address that can't be read back
+    let result = SignaldResponse::parse(message, ParseMode::Lenient).unwrap();
+    let x = match &result.data {
+        ResponseType::Message(x) => x.as_ref().unwrap(),
+        _ => panic!("Received wrong response type")
+    };
+    let sync_message = x.sync_message.as_ref().unwrap();
+    assert_eq!(sync_message.read_messages.as_ref().unwrap()[0].sender, None);
+    assert_eq!(sync_message.view_once_open.as_ref().unwrap().sender, None);
+    assert_eq!(x.data_message.as_ref().unwrap().reaction.as_ref().unwrap().target_author, None);
+
+    let value = serde_json::to_value(&result).unwrap();
+    assert_eq!(SignaldResponse::parse(value, ParseMode::Lenient).unwrap(), result);
+}
+
+#[test]
+fn test_parse_unparseable_data_is_unknown() {
+    let message = serde_json::json!({
+            "type": "version",
+            "data": {
+                "name": ["not", "a", "string"]
+            }
+        });
+    let result = SignaldResponse::from_value(message);
+    match result.data {
+        ResponseType::Unknown(typ, data) => {
+            assert_eq!(typ, "version");
+            assert_eq!(data["name"][0], "not");
+        }
+        _ => panic!("Received wrong response type")
+    }
+}
+
+#[test]
+fn test_parse_error_message() {
+    let message = serde_json::json!({
+            "type": "unexpected_error",
+            "id": "5",
+            "data": {
+                "message": "org.whispersystems.signalservice.api.push.exceptions.RateLimitException",
+                "request": {
+                    "type": "send",
+                    "id": "5",
+                    "username": "+32000000000",
+                    "messageBody": "Hello"
+                }
+            }
+        });
+    // Try to parse the message
+    let result = SignaldResponse::from_value(message);
+    match result.clone().data {
+        ResponseType::Error(SignaldError::UnexpectedError(x)) => {
+            assert_eq!(x.message, "org.whispersystems.signalservice.api.push.exceptions.RateLimitException");
+        }
+        _ => panic!("Received wrong response type")
+    }
+
+    let error = result.into_result().unwrap_err();
+    assert_eq!(error.type_name(), "unexpected_error");
+    assert_eq!(error.request().unwrap()["messageBody"], "Hello");
+}
+
+#[test]
+fn test_parse_untrusted_identity_message() {
+    let message = serde_json::json!({
+            "type": "untrusted_identity",
+            "data": {
+                "username": "+32000000000",
+                "number": "+32111111111",
+                "fingerprint": "05 a1 b2",
+                "safety_number": "123451234512345",
+                "request": {
+                    "type": "send"
+                }
+            }
+        });
+    // Try to parse the message
+    let result = SignaldResponse::from_value(message);
+    match result.into_result() {
+        Err(SignaldError::UntrustedIdentity(x)) => {
+            assert_eq!(x.number.unwrap(), "+32111111111");
+            assert_eq!(x.safety_number.unwrap(), "123451234512345");
+            assert_eq!(x.request.unwrap()["type"], "send");
+        }
+        _ => panic!("Received wrong response type")
+    }
+}
+
+#[test]
+fn test_linking_error_into_result() {
+    let message = serde_json::json!({
+            "type": "linking_error",
+            "data": {
+                "msg_number": 1,
+                "message": "Timed out while waiting for device to link",
+                "error": true,
+                "request": {
+                    "type": "link",
+                    "expiresInSeconds": 0,
+                    "when": 0
+                }
+            }
+        });
+    let result = SignaldResponse::from_value(message);
+    match result.into_result() {
+        Err(SignaldError::LinkingError(x)) => {
+            assert_eq!(x.message, "Timed out while waiting for device to link");
+        }
+        _ => panic!("Received wrong response type")
+    }
+}
+
+#[test]
+fn test_parse_account_state_events() {
+    let started = SignaldResponse::from_value(serde_json::json!({"type": "listen_started", "data": "+32000000000"}));
+    assert_eq!(started.data, ResponseType::ListenStarted(Some("+32000000000".to_string())));
+    assert_eq!(started.data.account(), Some("+32000000000"));
+
+    let refreshed = SignaldResponse::from_value(serde_json::json!({"type": "account_refreshed", "data": "+32000000000"}));
+    assert_eq!(refreshed.data, ResponseType::AccountRefreshed(Some("+32000000000".to_string())));
+
+    let state = SignaldResponse::from_value(serde_json::json!({
+        "type": "websocket_connection_state",
+        "data": {
+            "account": "+32000000000",
+            "state": "RECONNECTING",
+            "socket": "IDENTIFIED"
+        }
+    }));
+    match &state.data {
+        ResponseType::WebSocketConnectionState(Some(state)) => {
+            assert_eq!(state.account, "+32000000000");
+            assert!(!state.is_connected());
+            assert_eq!(state.socket.as_deref(), Some("IDENTIFIED"));
+        }
+        _ => panic!("Expected a websocket connection state")
+    }
+    assert_eq!(state.data.account(), Some("+32000000000"));
+}
+
+#[test]
+fn test_parse_send_results() {
+    let results = serde_json::json!({
+        "results": [
+            {
+                "address": {"number": "+32111111111"},
+                "success": {"unidentified": true, "needsSync": true},
+                "networkFailure": false,
+                "unregisteredFailure": false
+            },
+            {"address": {"number": "+32222222222"}, "networkFailure": true, "unregisteredFailure": false},
+            {"address": {"number": "+32333333333"}, "networkFailure": false, "unregisteredFailure": true},
+            {"address": {"number": "+32444444444"}, "identityFailure": "BXcBx2y5LQ==", "networkFailure": false, "unregisteredFailure": false}
+        ],
+        "timestamp": 1583863426832u64
+    });
+    let v1 = SignaldResponse::from_value(serde_json::json!({"id": "1", "type": "send", "version": "v1", "data": results.clone()}));
+    let v0 = SignaldResponse::from_value(serde_json::json!({"id": "1", "type": "send_results", "data": results}));
+    assert_eq!(v0.data.type_name(), "send_results");
+
+    for response in &[v1, v0] {
+        let result = SendResult::from_response(response).unwrap();
+        assert_eq!(result.timestamp, 1583863426832);
+        assert!(!result.is_success());
+        assert_eq!(result.failures().count(), 3);
+
+        let address = |number: &str| SignalAddress::from_number(number).unwrap();
+        assert_eq!(result.get(&address("+32111111111")), Some(&RecipientStatus::Success { unidentified: true, needs_sync: true }));
+        assert_eq!(result.get(&address("+32222222222")), Some(&RecipientStatus::NetworkFailure));
+        assert_eq!(result.get(&address("+32333333333")), Some(&RecipientStatus::UnregisteredFailure));
+        assert_eq!(result.get(&address("+32444444444")), Some(&RecipientStatus::IdentityFailure("BXcBx2y5LQ==".to_string())));
+        assert_eq!(result.get(&address("+32555555555")), None);
+    }
+
+    let subscribed = SignaldResponse::from_value(serde_json::json!({"type": "subscribed"}));
+    assert!(SendResult::from_response(&subscribed).is_err());
+}
+
+#[test]
+fn test_parse_edit_message() {
+    let response = SignaldResponse::from_value(serde_json::json!({
+        "type": "message",
+        "data": {
+            "username": "+32000000000",
+            "source": {"number": "+32111111111"},
+            "timestamp": 1583863427000u64,
+            "editMessage": {
+                "targetSentTimestamp": 1583863426832u64,
+                "dataMessage": {"timestamp": 1583863427000u64, "message": "deploy finished (staging)"}
+            }
+        }
+    }));
+    let message = match response.data {
+        ResponseType::Message(Some(message)) => message,
+        other => panic!("Expected a message, got {:?}", other),
+    };
+
+    let edit = message.edit_message.as_ref().unwrap();
+    assert_eq!(edit.data_message.message, "deploy finished (staging)");
+    assert!(message.data_message.is_none());
+    assert_eq!(message.edit_target(), Some((SignalAddress::from_number("+32111111111").unwrap(), 1583863426832)));
+}
+
+#[test]
+fn test_changed_default_is_serialized() {
+    let message = serde_json::json!({
+            "type": "message",
+            "data": {
+                "timestamp": 1583863470594u64,
+                "dataMessage": { "timestamp": 1583863470594u64 }
+            }
+        });
+    let mut result = SignaldResponse::from_value(message);
+    if let ResponseType::Message(Some(x)) = &mut result.data {
+        x.data_message.as_mut().unwrap().message = "edited".to_string();
+    }
+    let value = result.to_value();
+    assert_eq!(value["data"]["dataMessage"]["message"], "edited");
+    assert!(value["data"]["dataMessage"].get("expiresInSeconds").is_none());
+    assert!(value["data"].get("timestampISO").is_none());
+}
+
+#[test]
+fn test_unchecked_source_parses() {
+    let message = serde_json::json!({
+            "type": "message",
+            "data": {
+                "username": "+32000000000",
+                "uuid": "8f2c4b4e-1d3a-4c8e-9a71-0123456789ab",
+                "source": { "number": "12345", "relay": "textsecure" },
+                "timestamp": 1583863470594u64,
+                "syncMessage": { "blockedList": { "numbers": ["0470123456"], "groupIds": [] } }
+            }
+        });
+    // A short code source doesn't turn the whole message into an unknown response
+    let result = SignaldResponse::parse(message.clone(), ParseMode::Lenient).unwrap();
+    match &result.data {
+        ResponseType::Message(Some(x)) => {
+            let sender = x.sender().unwrap();
+            assert_eq!(sender.number(), Some("12345"));
+            assert_eq!(sender.uuid(), Some("8f2c4b4e-1d3a-4c8e-9a71-0123456789ab"));
+            assert_eq!(x.sync_message.as_ref().unwrap().blocked_list.as_ref().unwrap().numbers[0].number(), Some("0470123456"));
+        }
+        _ => panic!("Received wrong response type")
+    }
+    assert_eq!(result.to_value(), message);
+}
@@ -0,0 +1,243 @@
+#![cfg(feature = "store")]
+use signald_rust::store::{MessageStore, MessageQuery};
+use signald_rust::signaldresponse::SignaldResponse;
+use signald_rust::signaladdress::SignalAddress;
+use signald_rust::conversation::Conversation;
+use serde_json::{json, Value};
+
+static ACCOUNT: &str = "+32000000000";
+static ALICE: &str = "+32111111111";
+static BOB: &str = "+32222222222";
+
+fn message(source: &str, timestamp: i64, data_message: Value) -> SignaldResponse {
+    SignaldResponse::from_value(json!({
+        "type": "message",
+        "data": {
+            "username": ACCOUNT,
+            "source": source,
+            "timestamp": timestamp,
+            "dataMessage": data_message,
+        }
+    }))
+}
+
+fn text(source: &str, timestamp: i64, body: &str) -> SignaldResponse {
+    message(source, timestamp, json!({ "timestamp": timestamp, "message": body }))
+}
+
+fn group_text(source: &str, timestamp: i64, body: &str) -> SignaldResponse {
+    message(source, timestamp, json!({
+        "timestamp": timestamp,
+        "message": body,
+        "groupInfo": { "groupId": "Z3JvdXBpZA==", "type": "DELIVER" }
+    }))
+}
+
+fn sent(destination: &str, timestamp: i64, body: &str) -> SignaldResponse {
+    SignaldResponse::from_value(json!({
+        "type": "message",
+        "data": {
+            "username": ACCOUNT,
+            "source": ACCOUNT,
+            "timestamp": timestamp,
+            "syncMessage": {
+                "sent": {
+                    "destination": destination,
+                    "timestamp": timestamp,
+                    "message": { "timestamp": timestamp, "message": body }
+                }
+            }
+        }
+    }))
+}
+
+fn address(number: &str) -> SignalAddress {
+    SignalAddress::from_number(number).unwrap()
+}
+
+fn store() -> MessageStore {
+    let store = MessageStore::open_in_memory().unwrap();
+    store.record(&text(ALICE, 1000, "deploy to staging please")).unwrap();
+    store.record(&sent(ALICE, 2000, "done, staging is live")).unwrap();
+    store.record(&text(BOB, 3000, "can you deploy production")).unwrap();
+    store.record(&group_text(ALICE, 4000, "lunch?")).unwrap();
+    store.record(&group_text(BOB, 5000, "sure")).unwrap();
+    store
+}
+
+fn bodies(messages: Vec<signald_rust::store::StoredMessage>) -> Vec<String> {
+    messages.into_iter().map(|m| m.body).collect()
+}
+
+#[test]
+fn test_query_by_conversation() {
+    let store = store();
+
+    let direct = store.by_conversation(&Conversation::direct(ACCOUNT, address(ALICE))).unwrap();
+    assert_eq!(bodies(direct.clone()), vec!["deploy to staging please", "done, staging is live"]);
+    assert!(!direct[0].outgoing);
+    assert!(direct[1].outgoing);
+    assert_eq!(direct[0].conversation(), Conversation::direct(ACCOUNT, address(ALICE)));
+
+    let group = Conversation::group(ACCOUNT, "Z3JvdXBpZA==");
+    assert_eq!(bodies(store.by_conversation(&group).unwrap()), vec!["lunch?", "sure"]);
+    assert_eq!(bodies(store.by_conversation(&group.with_sender(address(BOB))).unwrap()), vec!["sure"]);
+}
+
+#[test]
+fn test_query_by_sender_and_time() {
+    let store = store();
+
+    assert_eq!(bodies(store.by_sender(ACCOUNT, &address(BOB)).unwrap()), vec!["can you deploy production", "sure"]);
+    assert_eq!(bodies(store.between(ACCOUNT, 2000, 4000).unwrap()), vec!["done, staging is live", "can you deploy production"]);
+    assert_eq!(bodies(store.query(&MessageQuery::new().account(ACCOUNT).sender(&address(ALICE)).limit(1)).unwrap()),
+               vec!["deploy to staging please"]);
+}
+
+#[test]
+fn test_full_text_search() {
+    let store = store();
+
+    assert_eq!(bodies(store.search(ACCOUNT, "deploy").unwrap()), vec!["deploy to staging please", "can you deploy production"]);
+    assert_eq!(bodies(store.search(ACCOUNT, "staging").unwrap()), vec!["deploy to staging please", "done, staging is live"]);
+    assert!(store.search("+32999999999", "deploy").unwrap().is_empty());
+}
+
+#[test]
+fn test_duplicate_messages_are_stored_once() {
+    let store = store();
+    store.record(&text(ALICE, 1000, "deploy to staging please")).unwrap();
+
+    assert_eq!(store.by_sender(ACCOUNT, &address(ALICE)).unwrap().len(), 2);
+}
+
+#[test]
+fn test_apply_receipts() {
+    let store = store();
+    let receipt = |typ: &str, when: i64| SignaldResponse::from_value(json!({
+        "type": "message",
+        "data": {
+            "username": ACCOUNT,
+            "source": ALICE,
+            "receipt": { "type": typ, "timestamps": [2000], "when": when }
+        }
+    }));
+    store.record(&receipt("DELIVERY", 2100)).unwrap();
+    store.record(&receipt("READ", 2200)).unwrap();
+
+    let sent = &store.between(ACCOUNT, 2000, 2001).unwrap()[0];
+    assert_eq!(sent.delivered_at, Some(2100));
+    assert_eq!(sent.read_at, Some(2200));
+}
+
+#[test]
+fn test_apply_delete() {
+    let store = store();
+    store.record(&message(BOB, 3500, json!({ "timestamp": 3500, "remoteDelete": { "targetSentTimestamp": 3000 } }))).unwrap();
+
+    let deleted = &store.between(ACCOUNT, 3000, 3001).unwrap()[0];
+    assert!(deleted.deleted);
+    assert_eq!(deleted.body, "");
+    assert!(store.search(ACCOUNT, "production").unwrap().is_empty());
+}
+
+#[test]
+fn test_apply_edit() {
+    let store = store();
+    store.apply_edit(ACCOUNT, &address(BOB), 3000, "can you deploy staging", 3100).unwrap();
+
+    let edited = &store.between(ACCOUNT, 3000, 3001).unwrap()[0];
+    assert_eq!(edited.body, "can you deploy staging");
+    assert_eq!(edited.edited_at, Some(3100));
+    assert_eq!(store.edits(edited).unwrap(), vec![(3100, "can you deploy staging".to_string())]);
+    assert!(store.search(ACCOUNT, "production").unwrap().is_empty());
+}
+
+#[test]
+fn test_record_edit() {
+    let store = store();
+    store.record(&SignaldResponse::from_value(json!({
+        "type": "message",
+        "data": {
+            "username": ACCOUNT,
+            "source": BOB,
+            "timestamp": 3200,
+            "editMessage": {
+                "targetSentTimestamp": 3000,
+                "dataMessage": { "timestamp": 3200, "message": "can you deploy staging" }
+            }
+        }
+    }))).unwrap();
+
+    let edited = &store.between(ACCOUNT, 3000, 3001).unwrap()[0];
+    assert_eq!(edited.body, "can you deploy staging");
+    assert_eq!(edited.edited_at, Some(3200));
+    // The edit isn't stored as a message of its own
+    assert!(store.between(ACCOUNT, 3200, 3201).unwrap().is_empty());
+}
+
+#[test]
+fn test_record_sync_sent_edit() {
+    let store = store();
+    // The account edited a message from one of its other devices
+    store.record(&SignaldResponse::from_value(json!({
+        "type": "message",
+        "data": {
+            "username": ACCOUNT,
+            "source": ACCOUNT,
+            "timestamp": 2500,
+            "syncMessage": {
+                "sent": {
+                    "destination": ALICE,
+                    "timestamp": 2500,
+                    "editMessage": {
+                        "targetSentTimestamp": 2000,
+                        "dataMessage": { "timestamp": 2500, "message": "done, staging is live again" }
+                    }
+                }
+            }
+        }
+    }))).unwrap();
+
+    let edited = &store.between(ACCOUNT, 2000, 2001).unwrap()[0];
+    assert!(edited.outgoing);
+    assert_eq!(edited.body, "done, staging is live again");
+    assert_eq!(edited.edited_at, Some(2500));
+    assert!(store.between(ACCOUNT, 2500, 2501).unwrap().is_empty());
+}
+
+#[test]
+fn test_apply_reactions() {
+    let store = store();
+    let reaction = |emoji: &str, remove: bool, timestamp: i64| message(BOB, timestamp, json!({
+        "timestamp": timestamp,
+        "reaction": { "emoji": emoji, "remove": remove, "targetAuthor": ALICE, "targetSentTimestamp": 1000 }
+    }));
+    store.record(&reaction("👍", false, 1100)).unwrap();
+    store.record(&reaction("❤", false, 1200)).unwrap();
+
+    let message = &store.between(ACCOUNT, 1000, 1001).unwrap()[0];
+    let reactions = store.reactions(message).unwrap();
+    assert_eq!(reactions.len(), 1);
+    assert_eq!(reactions[0].emoji, "❤");
+    assert_eq!(reactions[0].reactor, BOB);
+
+    store.record(&reaction("❤", true, 1300)).unwrap();
+    assert!(store.reactions(message).unwrap().is_empty());
+    // Reactions aren't stored as messages
+    assert_eq!(store.by_sender(ACCOUNT, &address(BOB)).unwrap().len(), 2);
+}
+
+#[test]
+fn test_open_file() {
+    let path = std::env::temp_dir().join(format!("signald-rust-store-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    {
+        let store = MessageStore::open(&path).unwrap();
+        store.record(&text(ALICE, 1000, "persisted")).unwrap();
+    }
+
+    let store = MessageStore::open(&path).unwrap();
+    assert_eq!(bodies(store.search(ACCOUNT, "persisted").unwrap()), vec!["persisted"]);
+    std::fs::remove_file(&path).unwrap();
+}
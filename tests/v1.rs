@@ -0,0 +1,117 @@
+use signald_rust::signaldrequest::{SignaldRequest, Request};
+use signald_rust::signaldresponse::{SignaldResponse, ResponseType};
+use signald_rust::signalderror::SignaldError;
+use signald_rust::signaladdress::SignalAddress;
+use signald_rust::v1;
+
+#[test]
+fn test_serialize_v1_send_request() {
+    let send_request = v1::SendRequest {
+        username: "+32000000000".to_string(),
+        recipient_address: Some(SignalAddress::from_number("+32111111111").unwrap()),
+        message_body: Some("Hello".to_string()),
+        ..Default::default()
+    };
+    let request = SignaldRequest::with_id(Request::V1(v1::Request::Send(send_request)), "1".to_string());
+
+    let json: serde_json::Value = serde_json::from_str(&request.to_json_string()).unwrap();
+    assert_eq!(json, serde_json::json!({
+        "type": "send",
+        "version": "v1",
+        "id": "1",
+        "username": "+32000000000",
+        "recipientAddress": {
+            "number": "+32111111111"
+        },
+        "messageBody": "Hello"
+    }));
+}
+
+#[test]
+fn test_deserialize_v1_request() {
+    let json = r#"{"type":"get_group","version":"v1","id":"2","account":"+32000000000","groupID":"Z3JvdXBpZA=="}"#;
+    let request: SignaldRequest = serde_json::from_str(json).unwrap();
+
+    assert_eq!(request.id.unwrap(), "2");
+    match request.request {
+        Request::V1(v1::Request::GetGroup(get_group)) => {
+            assert_eq!(get_group.account, "+32000000000");
+            assert_eq!(get_group.group_id, "Z3JvdXBpZA==");
+            assert!(get_group.extra.is_empty());
+        }
+        _ => panic!("Parsed wrong request type")
+    }
+}
+
+#[test]
+fn test_parse_v1_send_response() {
+    let message = serde_json::json!({
+        "type": "send",
+        "version": "v1",
+        "id": "3",
+        "data": {
+            "results": [{
+                "address": {
+                    "number": "+32111111111",
+                    "uuid": "8f2c4b4e-1d3a-4c8e-9a71-0123456789ab"
+                },
+                "success": {
+                    "unidentified": true,
+                    "needsSync": true
+                },
+                "networkFailure": false,
+                "unregisteredFailure": false
+            }],
+            "timestamp": 1583863470594u64
+        }
+    });
+    let result = SignaldResponse::from_value(message.clone());
+    assert_eq!(result.id.clone().unwrap(), "3");
+    assert_eq!(result.to_value(), message);
+
+    match result.into_result().unwrap().data {
+        ResponseType::V1(response) => {
+            let send_response: v1::SendResponse = response.parse().unwrap();
+            assert_eq!(send_response.timestamp, 1583863470594);
+            let result = send_response.results.first().unwrap();
+            assert_eq!(result.address.clone().unwrap(), "+32111111111");
+            assert!(result.success.as_ref().unwrap().unidentified);
+        }
+        _ => panic!("Received wrong response type")
+    }
+}
+
+#[test]
+fn test_parse_v1_error_response() {
+    let message = serde_json::json!({
+        "type": "get_group",
+        "version": "v1",
+        "id": "4",
+        "error_type": "InvalidRequestError",
+        "error": {
+            "message": "unknown group"
+        }
+    });
+    let result = SignaldResponse::from_value(message);
+    match result.into_result() {
+        Err(SignaldError::InvalidRequest(error)) => assert_eq!(error.message, "unknown group"),
+        _ => panic!("Received wrong response type")
+    }
+
+    let message = serde_json::json!({
+        "type": "accept_invitation",
+        "version": "v1",
+        "error_type": "GroupNotActiveError",
+        "error": {
+            "message": "group is not active"
+        }
+    });
+    let result = SignaldResponse::from_value(message);
+    match result.into_result() {
+        Err(SignaldError::Other(typ, error)) => {
+            assert_eq!(typ, "GroupNotActiveError");
+            assert_eq!(error.message, "group is not active");
+        }
+        _ => panic!("Received wrong response type")
+    }
+}
@@ -0,0 +1,109 @@
+use signald_rust::signaldrequest::{Recipient, OutgoingAttachment};
+use signald_rust::signaldresponse::{SignaldResponse, ResponseType, Message};
+use signald_rust::signaladdress::SignalAddress;
+use signald_rust::socket::fakesignald::FakeSignald;
+use signald_rust::v1::Protocol;
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+
+static ACCOUNT: &str = "+32000000000";
+static ALICE: &str = "+32111111111";
+
+// A downloaded attachment in the temp dir
+fn attachment_file(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("signald-rust-viewonce-{}-{}", name, std::process::id()));
+    fs::write(&path, b"secret photo").unwrap();
+    path
+}
+
+fn view_once_message(path: &PathBuf, view_once: bool) -> Message {
+    let response = SignaldResponse::from_value(json!({
+        "type": "message",
+        "data": {
+            "username": ACCOUNT,
+            "source": ALICE,
+            "timestamp": 1000,
+            "dataMessage": {
+                "timestamp": 1000,
+                "viewOnce": view_once,
+                "attachments": [
+                    { "contentType": "image/jpeg", "id": "4717", "size": 12, "storedFilename": path },
+                    { "contentType": "image/jpeg", "id": "4718", "size": 12 }
+                ]
+            }
+        }
+    }));
+    match response.data {
+        ResponseType::Message(Some(message)) => message.data_message.unwrap(),
+        other => panic!("Expected a message, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_consume_deletes_file() {
+    let path = attachment_file("consume");
+    let mut message = view_once_message(&path, true);
+    assert!(message.is_view_once());
+
+    // Attachments that weren't downloaded have no file
+    let mut attachments = message.take_view_once_attachments();
+    assert_eq!(attachments.len(), 1);
+    let attachment = attachments.remove(0);
+    assert_eq!(attachment.attachment().id.as_deref(), Some("4717"));
+    assert_eq!(attachment.path(), path.as_path());
+
+    // The attachments can only be taken once
+    assert!(message.take_view_once_attachments().is_empty());
+    assert_eq!(message.attachments, None);
+
+    assert_eq!(attachment.read().unwrap(), b"secret photo");
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_file_is_only_deleted_when_consumed() {
+    let path = attachment_file("drop");
+    let mut message = view_once_message(&path, true);
+    drop(message.take_view_once_attachments());
+    assert!(path.exists());
+
+    let attachment = view_once_message(&path, true).take_view_once_attachments().remove(0);
+    attachment.discard().unwrap();
+    assert!(!path.exists());
+
+    // A consumer that fails leaves the file for another try
+    let path = attachment_file("failed");
+    let attachment = view_once_message(&path, true).take_view_once_attachments().remove(0);
+    let result = attachment.consume(|_| Err::<(), _>(std::io::Error::other("upload failed")));
+    assert_eq!(result.unwrap_err().to_string(), "upload failed");
+    assert!(path.exists());
+    fs::remove_file(&path).unwrap();
+
+    // Ordinary attachments are left alone
+    let path = attachment_file("ordinary");
+    let mut message = view_once_message(&path, false);
+    assert!(!message.is_view_once());
+    assert!(message.take_view_once_attachments().is_empty());
+    assert!(message.attachments.is_some());
+    fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn test_send_view_once() {
+    let fake = FakeSignald::start();
+    let mut signald = fake.connect();
+    signald.set_protocol(Protocol::V1).unwrap();
+
+    let attachment = OutgoingAttachment {
+        filename: "/tmp/photo.jpg".to_string(),
+        ..Default::default()
+    };
+    let recipient = Recipient::Address(SignalAddress::from_number(ALICE).unwrap());
+    let result = signald.account(ACCOUNT).send_view_once(recipient, vec![attachment]).await.unwrap();
+    assert!(result.is_success());
+
+    let request = &fake.requests()[0];
+    assert_eq!(request["viewOnce"], true);
+    assert_eq!(request["attachments"], json!([{ "filename": "/tmp/photo.jpg" }]));
+}
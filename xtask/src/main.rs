@@ -0,0 +1,278 @@
+use serde_json::{Map, Value};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+static PROTOCOL_PATH: &str = "protocol/protocol.json";
+/// The protocol versions that are generated and the module each one is written to
+static OUTPUTS: [(&str, &str); 2] = [("v0", "src/v0/types.rs"), ("v1", "src/v1/types.rs")];
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.iter().map(|a| a.as_str()).collect::<Vec<&str>>().as_slice() {
+        ["codegen"] => {
+            for (path, generated) in codegen(&project_root()) {
+                fs::write(project_root().join(path), generated).expect("Failed to write the generated types");
+                println!("Generated {}", path);
+            }
+        }
+        ["codegen", "--check"] => {
+            let outdated = outdated(&project_root());
+            for path in &outdated {
+                eprintln!("{} is out of date, run `cargo run -p xtask -- codegen`", path);
+            }
+            if !outdated.is_empty() {
+                process::exit(1);
+            }
+        }
+        _ => {
+            eprintln!("Usage: cargo run -p xtask -- codegen [--check]");
+            process::exit(1);
+        }
+    }
+}
+
+fn project_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap().to_path_buf()
+}
+
+/// The checked in type modules that don't match the protocol description
+fn outdated(root: &Path) -> Vec<&'static str> {
+    codegen(root).into_iter()
+        .filter(|(path, generated)| fs::read_to_string(root.join(path)).unwrap_or_default() != *generated)
+        .map(|(path, _)| path)
+        .collect()
+}
+
+fn read_protocol(root: &Path) -> Value {
+    let protocol = fs::read_to_string(root.join(PROTOCOL_PATH)).expect("Failed to read the protocol description");
+    serde_json::from_str(&protocol).expect("Failed to parse the protocol description")
+}
+
+/// Generate the type module of every protocol version from the protocol description
+fn codegen(root: &Path) -> Vec<(&'static str, String)> {
+    let protocol = read_protocol(root);
+    OUTPUTS.iter().map(|(version, path)| (*path, generate(&protocol, version))).collect()
+}
+
+fn generate(protocol: &Value, version: &str) -> String {
+    let types = protocol["types"][version].as_object().expect("No types in the protocol description");
+
+    let mut out = String::new();
+    out.push_str("// Generated by `cargo run -p xtask -- codegen` from protocol/protocol.json, do not edit\n");
+    out.push_str("use serde::{Serialize, Deserialize};\n");
+    out.push_str("use serde_json::{Value, Map};\n");
+    out.push_str("use crate::signaladdress::SignalAddress;\n");
+    for other in other_versions(types, version) {
+        out.push_str(&format!("use crate::{};\n", other));
+    }
+
+    // Only versions with actions have a request enum, the v0 requests are listed by hand
+    if let Some(actions) = protocol["actions"][version].as_object() {
+        out.push_str(&generate_request_enum(version, actions));
+    }
+    for (name, typ) in types {
+        if builtin_type(name).is_some() {
+            continue;
+        }
+        out.push_str(&generate_struct(name, typ, version));
+    }
+
+    out
+}
+
+/// The other protocol versions the fields of these types refer to
+fn other_versions(types: &Map<String, Value>, version: &str) -> Vec<String> {
+    let mut versions: Vec<String> = types.values()
+        .flat_map(|typ| typ["fields"].as_object().cloned().unwrap_or_default().into_iter())
+        .filter(|(_, field)| builtin_type(field["type"].as_str().unwrap_or_default()).is_none())
+        .filter_map(|(_, field)| field["version"].as_str().map(|v| v.to_string()))
+        .filter(|v| v != version)
+        .collect();
+    versions.sort();
+    versions.dedup();
+    versions
+}
+
+/// The Request enum with one variant per action
+fn generate_request_enum(version: &str, actions: &Map<String, Value>) -> String {
+    let mut out = String::new();
+
+    out.push_str(&format!("\n/// Every {} command\n", version));
+    out.push_str("#[derive(Deserialize, Clone, Debug, PartialEq)]\n");
+    out.push_str("#[serde(tag = \"type\", rename_all = \"snake_case\")]\n");
+    out.push_str("pub enum Request {\n");
+    for (action, description) in actions {
+        let request = description["request"].as_str().unwrap();
+        out.push_str(&format!("    {}({}),\n", variant_name(action), request));
+    }
+    out.push_str("}\n");
+
+    out.push_str("impl Request {\n");
+    out.push_str("    /// The signald type of this request\n");
+    out.push_str("    pub fn type_name(&self) -> &str {\n");
+    out.push_str("        match self {\n");
+    for action in actions.keys() {
+        out.push_str(&format!("            Request::{}(_) => \"{}\",\n", variant_name(action), action));
+    }
+    out.push_str("        }\n");
+    out.push_str("    }\n");
+    out.push_str("    /// The fields of this request as json\n");
+    out.push_str("    pub fn data_value(&self) -> serde_json::Result<Value> {\n");
+    out.push_str("        match self {\n");
+    for action in actions.keys() {
+        out.push_str(&format!("            Request::{}(request) => serde_json::to_value(request),\n", variant_name(action)));
+    }
+    out.push_str("        }\n");
+    out.push_str("    }\n");
+    out.push_str("}\n");
+
+    out
+}
+
+fn generate_struct(name: &str, typ: &Value, version: &str) -> String {
+    let mut out = String::new();
+
+    out.push('\n');
+    out.push_str(&doc_comment("", &typ["doc"]));
+    out.push_str("#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]\n");
+    out.push_str("#[serde(default)]\n");
+    out.push_str(&format!("pub struct {} {{\n", name));
+
+    let fields = typ["fields"].as_object().cloned().unwrap_or_default();
+    for (field_name, field) in &fields {
+        let required = field["required"].as_bool().unwrap_or(false);
+        let mut rust_type = field_type(field, version);
+        if field["list"].as_bool().unwrap_or(false) {
+            rust_type = format!("Vec<{}>", rust_type);
+        }
+
+        out.push_str(&doc_comment("    ", &field["doc"]));
+        // An address has no valid default, so a missing one reads as None even when it's required
+        if required && rust_type != "SignalAddress" {
+            out.push_str(&format!("    #[serde(rename = \"{}\")]\n", field_name));
+            out.push_str(&format!("    pub {}: {},\n", field_ident(field_name), rust_type));
+        } else {
+            out.push_str(&format!("    #[serde(rename = \"{}\", skip_serializing_if = \"Option::is_none\")]\n", field_name));
+            out.push_str(&format!("    pub {}: Option<{}>,\n", field_ident(field_name), rust_type));
+        }
+    }
+
+    out.push_str("    #[serde(flatten)]\n");
+    out.push_str("    pub extra: Map<String, Value>,\n");
+    out.push_str("}\n");
+
+    out
+}
+
+/// A doc comment with a line for every line of the doc
+fn doc_comment(indent: &str, doc: &Value) -> String {
+    doc.as_str().unwrap_or_default().lines().map(|line| format!("{}/// {}\n", indent, line)).collect()
+}
+
+/// Types that map to an existing type of the crate instead of a generated struct
+fn builtin_type(name: &str) -> Option<&'static str> {
+    match name {
+        "JsonAddress" => Some("SignalAddress"),
+        _ => None,
+    }
+}
+
+/// The rust type of a field, types of another protocol version are taken from its module
+fn field_type(field: &Value, version: &str) -> String {
+    let typ = field["type"].as_str().expect("Field without a type");
+    let primitive = match typ {
+        "String" | "UUID" => "String",
+        "long" | "Long" => "i64",
+        "int" | "Integer" => "i32",
+        "boolean" | "Boolean" => "bool",
+        "Map" => "Map<String, Value>",
+        "Object" => "Value",
+        _ => "",
+    };
+
+    if !primitive.is_empty() {
+        return primitive.to_string();
+    }
+    if let Some(builtin) = builtin_type(typ) {
+        return builtin.to_string();
+    }
+    match field["version"].as_str() {
+        Some(other) if other != version => format!("{}::{}", other, typ),
+        _ => typ.to_string(),
+    }
+}
+
+/// Convert a json field name such as "recipientGroupId" or "groupID" to snake case
+fn field_ident(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut ident = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() {
+            let previous_lower = i > 0 && chars[i - 1].is_ascii_lowercase();
+            let next_lower = chars.get(i + 1).map(|n| n.is_ascii_lowercase()).unwrap_or(false);
+            let previous_upper = i > 0 && chars[i - 1].is_ascii_uppercase();
+            if previous_lower || (previous_upper && next_lower) {
+                ident.push('_');
+            }
+            ident.push(c.to_ascii_lowercase());
+        } else {
+            ident.push(*c);
+        }
+    }
+
+    match ident.as_str() {
+        // The crate names type fields typ everywhere
+        "type" => "typ".to_string(),
+        "async" | "ref" | "move" | "box" | "match" => format!("r#{}", ident),
+        _ => ident,
+    }
+}
+
+/// Convert an action such as "get_profile" to a variant name such as "GetProfile"
+fn variant_name(action: &str) -> String {
+    action.split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_types_up_to_date() {
+        assert_eq!(outdated(&project_root()), Vec::<&str>::new(), "run `cargo run -p xtask -- codegen`");
+    }
+
+    #[test]
+    fn test_field_type() {
+        let reaction = serde_json::json!({"type": "JsonReaction", "version": "v1"});
+        assert_eq!(field_type(&reaction, "v0"), "v1::JsonReaction");
+        assert_eq!(field_type(&reaction, "v1"), "JsonReaction");
+        assert_eq!(field_type(&serde_json::json!({"type": "JsonAddress", "version": "v1"}), "v0"), "SignalAddress");
+        assert_eq!(field_type(&serde_json::json!({"type": "long"}), "v0"), "i64");
+    }
+
+    #[test]
+    fn test_field_ident() {
+        assert_eq!(field_ident("recipientGroupId"), "recipient_group_id");
+        assert_eq!(field_ident("groupID"), "group_id");
+        assert_eq!(field_ident("profile_name"), "profile_name");
+        assert_eq!(field_ident("async"), "r#async");
+        assert_eq!(field_ident("type"), "typ");
+    }
+
+    #[test]
+    fn test_variant_name() {
+        assert_eq!(variant_name("accept_invitation"), "AcceptInvitation");
+        assert_eq!(variant_name("send"), "Send");
+    }
+}